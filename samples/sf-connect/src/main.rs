@@ -52,7 +52,9 @@ async fn doit(
 
     println!("{:#?}", res);
 
-    let res = query_client.get_partition_list(&service_name, 1000).await?;
+    let res = query_client
+        .get_partition_list(&service_name, None, 1000)
+        .await?;
 
     println!("{:#?}", res);
 
@@ -0,0 +1,1190 @@
+use std::{collections::BTreeMap, ffi::c_void, ptr, time::Duration};
+
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use windows::core::{ComInterface, PCWSTR};
+
+use crate::{
+    agile::AgileRef, application_health_policy, arena::Arena, callback::begin_async, error::Error,
+    optional_string, poll_upgrade, run_with_retry, to_wide, upgrade_domain_list, wide_to_string,
+    ApplicationHealthPolicy, FailureAction, IFabricApplicationManagementClient10,
+    IFabricApplicationUpgradeProgressResult2, IFabricApplicationUpgradeProgressResult3, MakeClient,
+    MonitoringPolicy, RollingUpgradePolicy, UpgradeDomainStatus, UpgradeEvent, UpgradeMode,
+    UpgradeState, FABRIC_APPLICATION_DESCRIPTION, FABRIC_APPLICATION_METRIC_DESCRIPTION,
+    FABRIC_APPLICATION_METRIC_LIST, FABRIC_APPLICATION_PARAMETER,
+    FABRIC_APPLICATION_PARAMETER_LIST, FABRIC_APPLICATION_UPDATE_DESCRIPTION,
+    FABRIC_APPLICATION_UPDATE_DESCRIPTION_FLAGS_MAXNODES,
+    FABRIC_APPLICATION_UPDATE_DESCRIPTION_FLAGS_METRICS,
+    FABRIC_APPLICATION_UPDATE_DESCRIPTION_FLAGS_MINNODES, FABRIC_APPLICATION_UPGRADE_DESCRIPTION,
+    FABRIC_APPLICATION_UPGRADE_KIND_ROLLING, FABRIC_APPLICATION_UPGRADE_UPDATE_DESCRIPTION,
+    FABRIC_DELETE_APPLICATION_DESCRIPTION,
+    FABRIC_EXTERNAL_STORE_PROVISION_APPLICATION_TYPE_DESCRIPTION,
+    FABRIC_PROVISION_APPLICATION_TYPE_DESCRIPTION,
+    FABRIC_PROVISION_APPLICATION_TYPE_DESCRIPTION_BASE,
+    FABRIC_PROVISION_APPLICATION_TYPE_KIND_EXTERNAL_STORE,
+    FABRIC_PROVISION_APPLICATION_TYPE_KIND_IMAGE_STORE_PATH,
+    FABRIC_ROLLING_UPGRADE_UPDATE_FLAGS_FAILURE_ACTION,
+    FABRIC_ROLLING_UPGRADE_UPDATE_FLAGS_FORCE_RESTART,
+    FABRIC_ROLLING_UPGRADE_UPDATE_FLAGS_HEALTH_CHECK_RETRY,
+    FABRIC_ROLLING_UPGRADE_UPDATE_FLAGS_HEALTH_CHECK_STABLE,
+    FABRIC_ROLLING_UPGRADE_UPDATE_FLAGS_HEALTH_CHECK_WAIT,
+    FABRIC_ROLLING_UPGRADE_UPDATE_FLAGS_HEALTH_POLICY,
+    FABRIC_ROLLING_UPGRADE_UPDATE_FLAGS_REPLICA_SET_CHECK_TIMEOUT,
+    FABRIC_ROLLING_UPGRADE_UPDATE_FLAGS_UPGRADE_DOMAIN_TIMEOUT,
+    FABRIC_ROLLING_UPGRADE_UPDATE_FLAGS_UPGRADE_MODE,
+    FABRIC_ROLLING_UPGRADE_UPDATE_FLAGS_UPGRADE_TIMEOUT,
+    FABRIC_UNPROVISION_APPLICATION_TYPE_DESCRIPTION,
+};
+
+/// Where SF should provision an application type from. When `is_async` is
+/// set the call returns once provisioning has been accepted rather than when
+/// it finishes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProvisionApplicationTypeDescription {
+    /// A package already copied to the image store, relative to its root.
+    ImageStorePath { build_path: String, is_async: bool },
+    /// An `.sfpkg` that SF downloads itself.
+    ExternalStore {
+        application_type_name: String,
+        application_type_version: String,
+        download_uri: String,
+        is_async: bool,
+    },
+}
+
+impl ProvisionApplicationTypeDescription {
+    fn to_raw(&self, arena: &mut Arena) -> FABRIC_PROVISION_APPLICATION_TYPE_DESCRIPTION_BASE {
+        match self {
+            ProvisionApplicationTypeDescription::ImageStorePath {
+                build_path,
+                is_async,
+            } => {
+                let build_path = arena.wide(build_path);
+                let value = arena.alloc(FABRIC_PROVISION_APPLICATION_TYPE_DESCRIPTION {
+                    BuildPath: build_path,
+                    Async: (*is_async).into(),
+                    Reserved: ptr::null_mut(),
+                });
+
+                FABRIC_PROVISION_APPLICATION_TYPE_DESCRIPTION_BASE {
+                    Kind: FABRIC_PROVISION_APPLICATION_TYPE_KIND_IMAGE_STORE_PATH,
+                    Value: value as *mut c_void,
+                }
+            }
+            ProvisionApplicationTypeDescription::ExternalStore {
+                application_type_name,
+                application_type_version,
+                download_uri,
+                is_async,
+            } => {
+                let application_type_name = arena.wide(application_type_name);
+                let application_type_version = arena.wide(application_type_version);
+                let download_uri = arena.wide(download_uri);
+                let value = arena.alloc(
+                    FABRIC_EXTERNAL_STORE_PROVISION_APPLICATION_TYPE_DESCRIPTION {
+                        ApplicationTypeName: application_type_name,
+                        ApplicationTypeVersion: application_type_version,
+                        ApplicationPackageDownloadUri: download_uri,
+                        Async: (*is_async).into(),
+                        Reserved: ptr::null_mut(),
+                    },
+                );
+
+                FABRIC_PROVISION_APPLICATION_TYPE_DESCRIPTION_BASE {
+                    Kind: FABRIC_PROVISION_APPLICATION_TYPE_KIND_EXTERNAL_STORE,
+                    Value: value as *mut c_void,
+                }
+            }
+        }
+    }
+}
+
+/// Describes an application to create from a provisioned application type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApplicationDescription {
+    pub application_name: String,
+    pub application_type_name: String,
+    pub application_type_version: String,
+    /// Overrides for parameters declared in the application manifest.
+    pub parameters: BTreeMap<String, String>,
+}
+
+impl ApplicationDescription {
+    pub fn new(
+        application_name: &str,
+        application_type_name: &str,
+        application_type_version: &str,
+    ) -> Self {
+        Self {
+            application_name: application_name.to_string(),
+            application_type_name: application_type_name.to_string(),
+            application_type_version: application_type_version.to_string(),
+            parameters: BTreeMap::new(),
+        }
+    }
+
+    pub fn parameter(mut self, name: &str, value: &str) -> Self {
+        self.parameters.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    fn to_raw(&self, arena: &mut Arena) -> FABRIC_APPLICATION_DESCRIPTION {
+        let application_name = arena.uri(&self.application_name);
+        let application_type_name = arena.wide(&self.application_type_name);
+        let application_type_version = arena.wide(&self.application_type_version);
+
+        FABRIC_APPLICATION_DESCRIPTION {
+            ApplicationName: application_name,
+            ApplicationTypeName: application_type_name,
+            ApplicationTypeVersion: application_type_version,
+            ApplicationParameters: parameter_list(arena, &self.parameters),
+            Reserved: ptr::null_mut(),
+        }
+    }
+}
+
+/// Describes a rolling upgrade of an application to another provisioned
+/// version of its application type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApplicationUpgradeDescription {
+    pub application_name: String,
+    pub target_application_type_version: String,
+    /// Overrides for parameters declared in the application manifest. SF
+    /// reverts any parameter left out here to its manifest default.
+    pub parameters: BTreeMap<String, String>,
+    pub policy: RollingUpgradePolicy,
+    /// Used by monitored upgrades. SF falls back to the policy in the
+    /// application manifest when this isn't set.
+    pub health_policy: Option<ApplicationHealthPolicy>,
+}
+
+impl ApplicationUpgradeDescription {
+    pub fn new(
+        application_name: &str,
+        target_application_type_version: &str,
+        policy: RollingUpgradePolicy,
+    ) -> Self {
+        Self {
+            application_name: application_name.to_string(),
+            target_application_type_version: target_application_type_version.to_string(),
+            parameters: BTreeMap::new(),
+            policy,
+            health_policy: None,
+        }
+    }
+
+    pub fn parameter(mut self, name: &str, value: &str) -> Self {
+        self.parameters.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    pub fn health_policy(mut self, health_policy: ApplicationHealthPolicy) -> Self {
+        self.health_policy = Some(health_policy);
+        self
+    }
+
+    fn to_raw(&self, arena: &mut Arena) -> FABRIC_APPLICATION_UPGRADE_DESCRIPTION {
+        let application_name = arena.uri(&self.application_name);
+        let target_version = arena.wide(&self.target_application_type_version);
+        let health_policy =
+            application_health_policy(arena, self.health_policy.as_ref()) as *mut c_void;
+
+        FABRIC_APPLICATION_UPGRADE_DESCRIPTION {
+            ApplicationName: application_name,
+            TargetApplicationTypeVersion: target_version,
+            ApplicationParameters: parameter_list(arena, &self.parameters),
+            UpgradeKind: FABRIC_APPLICATION_UPGRADE_KIND_ROLLING,
+            UpgradePolicyDescription: self.policy.to_raw(arena, health_policy, ptr::null_mut()),
+            Reserved: ptr::null_mut(),
+        }
+    }
+}
+
+/// Changes to the policy of an application upgrade that is in progress.
+/// Only the fields that are set are sent to SF.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApplicationUpgradeUpdateDescription {
+    pub mode: Option<UpgradeMode>,
+    pub force_restart: Option<bool>,
+    pub replica_set_check_timeout: Option<Duration>,
+    pub failure_action: Option<FailureAction>,
+    pub health_check_wait: Option<Duration>,
+    pub health_check_stable: Option<Duration>,
+    pub health_check_retry_timeout: Option<Duration>,
+    pub upgrade_timeout: Option<Duration>,
+    pub upgrade_domain_timeout: Option<Duration>,
+    pub health_policy: Option<ApplicationHealthPolicy>,
+}
+
+impl ApplicationUpgradeUpdateDescription {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn mode(mut self, mode: UpgradeMode) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    pub fn force_restart(mut self, force_restart: bool) -> Self {
+        self.force_restart = Some(force_restart);
+        self
+    }
+
+    pub fn replica_set_check_timeout(mut self, replica_set_check_timeout: Duration) -> Self {
+        self.replica_set_check_timeout = Some(replica_set_check_timeout);
+        self
+    }
+
+    pub fn failure_action(mut self, failure_action: FailureAction) -> Self {
+        self.failure_action = Some(failure_action);
+        self
+    }
+
+    pub fn health_check_wait(mut self, health_check_wait: Duration) -> Self {
+        self.health_check_wait = Some(health_check_wait);
+        self
+    }
+
+    pub fn health_check_stable(mut self, health_check_stable: Duration) -> Self {
+        self.health_check_stable = Some(health_check_stable);
+        self
+    }
+
+    pub fn health_check_retry_timeout(mut self, health_check_retry_timeout: Duration) -> Self {
+        self.health_check_retry_timeout = Some(health_check_retry_timeout);
+        self
+    }
+
+    pub fn upgrade_timeout(mut self, upgrade_timeout: Duration) -> Self {
+        self.upgrade_timeout = Some(upgrade_timeout);
+        self
+    }
+
+    pub fn upgrade_domain_timeout(mut self, upgrade_domain_timeout: Duration) -> Self {
+        self.upgrade_domain_timeout = Some(upgrade_domain_timeout);
+        self
+    }
+
+    pub fn health_policy(mut self, health_policy: ApplicationHealthPolicy) -> Self {
+        self.health_policy = Some(health_policy);
+        self
+    }
+
+    fn flags(&self) -> u32 {
+        [
+            (
+                self.mode.is_some(),
+                FABRIC_ROLLING_UPGRADE_UPDATE_FLAGS_UPGRADE_MODE,
+            ),
+            (
+                self.force_restart.is_some(),
+                FABRIC_ROLLING_UPGRADE_UPDATE_FLAGS_FORCE_RESTART,
+            ),
+            (
+                self.replica_set_check_timeout.is_some(),
+                FABRIC_ROLLING_UPGRADE_UPDATE_FLAGS_REPLICA_SET_CHECK_TIMEOUT,
+            ),
+            (
+                self.failure_action.is_some(),
+                FABRIC_ROLLING_UPGRADE_UPDATE_FLAGS_FAILURE_ACTION,
+            ),
+            (
+                self.health_check_wait.is_some(),
+                FABRIC_ROLLING_UPGRADE_UPDATE_FLAGS_HEALTH_CHECK_WAIT,
+            ),
+            (
+                self.health_check_stable.is_some(),
+                FABRIC_ROLLING_UPGRADE_UPDATE_FLAGS_HEALTH_CHECK_STABLE,
+            ),
+            (
+                self.health_check_retry_timeout.is_some(),
+                FABRIC_ROLLING_UPGRADE_UPDATE_FLAGS_HEALTH_CHECK_RETRY,
+            ),
+            (
+                self.upgrade_timeout.is_some(),
+                FABRIC_ROLLING_UPGRADE_UPDATE_FLAGS_UPGRADE_TIMEOUT,
+            ),
+            (
+                self.upgrade_domain_timeout.is_some(),
+                FABRIC_ROLLING_UPGRADE_UPDATE_FLAGS_UPGRADE_DOMAIN_TIMEOUT,
+            ),
+            (
+                self.health_policy.is_some(),
+                FABRIC_ROLLING_UPGRADE_UPDATE_FLAGS_HEALTH_POLICY,
+            ),
+        ]
+        .into_iter()
+        .filter(|(present, _)| *present)
+        .fold(0, |flags, (_, flag)| flags | flag.0 as u32)
+    }
+
+    fn to_raw(
+        &self,
+        arena: &mut Arena,
+        application_name: &str,
+    ) -> FABRIC_APPLICATION_UPGRADE_UPDATE_DESCRIPTION {
+        // Fields without a flag are ignored by SF, so whatever the
+        // placeholder policy holds for them doesn't matter.
+        let mut monitoring_policy =
+            MonitoringPolicy::new(self.failure_action.unwrap_or(FailureAction::Manual));
+        monitoring_policy.health_check_wait = self.health_check_wait.unwrap_or_default();
+        monitoring_policy.health_check_stable = self.health_check_stable.unwrap_or_default();
+        monitoring_policy.health_check_retry_timeout =
+            self.health_check_retry_timeout.unwrap_or_default();
+        monitoring_policy.upgrade_timeout = self.upgrade_timeout;
+        monitoring_policy.upgrade_domain_timeout = self.upgrade_domain_timeout;
+        let policy = RollingUpgradePolicy {
+            mode: self.mode.unwrap_or(UpgradeMode::UnmonitoredManual),
+            force_restart: self.force_restart.unwrap_or_default(),
+            replica_set_check_timeout: self.replica_set_check_timeout,
+            monitoring_policy,
+        };
+
+        let application_name = arena.uri(application_name);
+        let health_policy =
+            application_health_policy(arena, self.health_policy.as_ref()) as *mut c_void;
+
+        FABRIC_APPLICATION_UPGRADE_UPDATE_DESCRIPTION {
+            ApplicationName: application_name,
+            UpgradeKind: FABRIC_APPLICATION_UPGRADE_KIND_ROLLING,
+            UpdateFlags: self.flags(),
+            UpgradePolicyDescription: policy.to_raw(arena, health_policy, ptr::null_mut()),
+            Reserved: ptr::null_mut(),
+        }
+    }
+}
+
+/// A snapshot of an application upgrade.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApplicationUpgradeProgress {
+    pub application_name: String,
+    pub application_type_name: String,
+    pub target_application_type_version: String,
+    pub state: UpgradeState,
+    pub mode: UpgradeMode,
+    /// The domain a manual upgrade will move to next, if any.
+    pub next_upgrade_domain: Option<String>,
+    pub upgrade_domains: Vec<UpgradeDomainStatus>,
+    pub upgrade_duration: Duration,
+    pub current_upgrade_domain_duration: Duration,
+}
+
+impl TryFrom<&IFabricApplicationUpgradeProgressResult2> for ApplicationUpgradeProgress {
+    type Error = Error;
+
+    fn try_from(value: &IFabricApplicationUpgradeProgressResult2) -> Result<Self, Self::Error> {
+        let result = value.cast::<IFabricApplicationUpgradeProgressResult3>()?;
+        let progress = unsafe { &*result.get_UpgradeProgress() };
+        let next_upgrade_domain =
+            unsafe { optional_string(PCWSTR(value.get_NextUpgradeDomain().0))? }
+                .filter(|name| !name.is_empty());
+
+        Ok(Self {
+            application_name: unsafe { wide_to_string(value.get_ApplicationName())? },
+            application_type_name: unsafe { wide_to_string(value.get_ApplicationTypeName().0)? },
+            target_application_type_version: unsafe {
+                wide_to_string(value.get_TargetApplicationTypeVersion().0)?
+            },
+            state: unsafe { value.get_UpgradeState() }.into(),
+            mode: unsafe { value.get_RollingUpgradeMode() }.into(),
+            next_upgrade_domain,
+            upgrade_domains: unsafe { upgrade_domain_list(progress.UpgradeDomains)? },
+            upgrade_duration: Duration::from_secs(progress.UpgradeDurationInSeconds as u64),
+            current_upgrade_domain_duration: Duration::from_secs(
+                progress.CurrentUpgradeDomainDurationInSeconds as u64,
+            ),
+        })
+    }
+}
+
+/// Capacity limits for one metric of an application.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApplicationMetricDescription {
+    pub name: String,
+    /// Load reserved on every node the application is placed on.
+    pub node_reservation_capacity: u32,
+    /// The most load the application may put on a single node.
+    pub maximum_node_capacity: u32,
+    /// The most load the application may put on the cluster.
+    pub total_application_capacity: u32,
+}
+
+/// Changes to the capacity settings of an application. Only the fields that
+/// are set are sent to SF.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApplicationUpdateDescription {
+    pub minimum_nodes: Option<u32>,
+    pub maximum_nodes: Option<u32>,
+    /// Replaces the metric list of the application.
+    pub metrics: Option<Vec<ApplicationMetricDescription>>,
+    /// Clears every capacity setting. It can't be combined with the other
+    /// fields.
+    pub remove_application_capacity: bool,
+}
+
+impl ApplicationUpdateDescription {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn minimum_nodes(mut self, minimum_nodes: u32) -> Self {
+        self.minimum_nodes = Some(minimum_nodes);
+        self
+    }
+
+    pub fn maximum_nodes(mut self, maximum_nodes: u32) -> Self {
+        self.maximum_nodes = Some(maximum_nodes);
+        self
+    }
+
+    pub fn metric(mut self, metric: ApplicationMetricDescription) -> Self {
+        self.metrics.get_or_insert_with(Vec::new).push(metric);
+        self
+    }
+
+    pub fn remove_application_capacity() -> Self {
+        Self {
+            remove_application_capacity: true,
+            ..Self::default()
+        }
+    }
+
+    /// Checks the settings SF would reject before sending them.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.remove_application_capacity
+            && (self.minimum_nodes.is_some()
+                || self.maximum_nodes.is_some()
+                || self.metrics.is_some())
+        {
+            return Err(Error::InvalidApplicationCapacity(
+                "removing application capacity can't be combined with other changes",
+            ));
+        }
+        if let (Some(minimum_nodes), Some(maximum_nodes)) = (self.minimum_nodes, self.maximum_nodes)
+        {
+            if maximum_nodes != 0 && minimum_nodes > maximum_nodes {
+                return Err(Error::InvalidApplicationCapacity(
+                    "minimum nodes must not exceed maximum nodes",
+                ));
+            }
+        }
+        for metric in self.metrics.iter().flatten() {
+            if metric.maximum_node_capacity != 0
+                && metric.node_reservation_capacity > metric.maximum_node_capacity
+            {
+                return Err(Error::InvalidApplicationCapacity(
+                    "node reservation capacity must not exceed maximum node capacity",
+                ));
+            }
+            if metric.total_application_capacity != 0
+                && metric.maximum_node_capacity > metric.total_application_capacity
+            {
+                return Err(Error::InvalidApplicationCapacity(
+                    "maximum node capacity must not exceed total application capacity",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn to_raw(
+        &self,
+        arena: &mut Arena,
+        application_name: &str,
+    ) -> FABRIC_APPLICATION_UPDATE_DESCRIPTION {
+        let mut flags = 0;
+        if self.minimum_nodes.is_some() {
+            flags |= FABRIC_APPLICATION_UPDATE_DESCRIPTION_FLAGS_MINNODES.0 as u32;
+        }
+        if self.maximum_nodes.is_some() {
+            flags |= FABRIC_APPLICATION_UPDATE_DESCRIPTION_FLAGS_MAXNODES.0 as u32;
+        }
+        if self.metrics.is_some() {
+            flags |= FABRIC_APPLICATION_UPDATE_DESCRIPTION_FLAGS_METRICS.0 as u32;
+        }
+
+        let metrics = self.metrics.as_ref().map_or(ptr::null(), |metrics| {
+            let items = metrics
+                .iter()
+                .map(|metric| FABRIC_APPLICATION_METRIC_DESCRIPTION {
+                    Name: arena.wide(&metric.name),
+                    NodeReservationCapacity: metric.node_reservation_capacity,
+                    MaximumNodeCapacity: metric.maximum_node_capacity,
+                    TotalApplicationCapacity: metric.total_application_capacity,
+                    Reserved: ptr::null_mut(),
+                })
+                .collect::<Vec<_>>();
+            let count = items.len() as u32;
+            let items = arena.slice(items);
+            arena.alloc(FABRIC_APPLICATION_METRIC_LIST {
+                Count: count,
+                Capacities: items,
+            }) as *const _
+        });
+
+        FABRIC_APPLICATION_UPDATE_DESCRIPTION {
+            Flags: flags,
+            ApplicationName: arena.uri(application_name),
+            RemoveApplicationCapacity: self.remove_application_capacity.into(),
+            MaximumNodes: self.maximum_nodes.unwrap_or_default(),
+            MinimumNodes: self.minimum_nodes.unwrap_or_default(),
+            Metrics: metrics,
+            Reserved: ptr::null_mut(),
+        }
+    }
+}
+
+/// How an application parameter would change when moving from one set of
+/// parameter overrides to another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParameterChange {
+    Added {
+        name: String,
+        value: String,
+    },
+    /// The override is dropped, so SF reverts the parameter to the default
+    /// in the application manifest.
+    Removed {
+        name: String,
+        previous: String,
+    },
+    Changed {
+        name: String,
+        previous: String,
+        value: String,
+    },
+}
+
+/// Compares the parameter overrides an application currently has with the
+/// ones an upgrade would apply. Unchanged parameters are left out.
+pub fn diff_parameters(
+    current: &BTreeMap<String, String>,
+    target: &BTreeMap<String, String>,
+) -> Vec<ParameterChange> {
+    let removed = current
+        .iter()
+        .filter(|(name, _)| !target.contains_key(*name))
+        .map(|(name, previous)| ParameterChange::Removed {
+            name: name.clone(),
+            previous: previous.clone(),
+        });
+    let added_or_changed = target
+        .iter()
+        .filter_map(|(name, value)| match current.get(name) {
+            None => Some(ParameterChange::Added {
+                name: name.clone(),
+                value: value.clone(),
+            }),
+            Some(previous) if previous != value => Some(ParameterChange::Changed {
+                name: name.clone(),
+                previous: previous.clone(),
+                value: value.clone(),
+            }),
+            Some(_) => None,
+        });
+
+    removed.chain(added_or_changed).collect()
+}
+
+pub(crate) fn parameter_list(
+    arena: &mut Arena,
+    parameters: &BTreeMap<String, String>,
+) -> *mut FABRIC_APPLICATION_PARAMETER_LIST {
+    let items = parameters
+        .iter()
+        .map(|(name, value)| FABRIC_APPLICATION_PARAMETER {
+            Name: arena.wide(name),
+            Value: arena.wide(value),
+            Reserved: ptr::null_mut(),
+        })
+        .collect::<Vec<_>>();
+    let count = items.len() as u32;
+    let items = arena.slice(items);
+
+    arena.alloc(FABRIC_APPLICATION_PARAMETER_LIST {
+        Count: count,
+        Items: items,
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct ApplicationManagementClient {
+    client: AgileRef<IFabricApplicationManagementClient10>,
+}
+
+impl MakeClient for ApplicationManagementClient {
+    type Interface = IFabricApplicationManagementClient10;
+
+    fn make(client: Self::Interface) -> Result<Self, Error> {
+        Ok(Self {
+            client: AgileRef::new(client.cast()?)?,
+        })
+    }
+}
+
+impl ApplicationManagementClient {
+    pub fn new(client: IFabricApplicationManagementClient10) -> Result<Self, Error> {
+        Ok(Self {
+            client: AgileRef::new(client.cast()?)?,
+        })
+    }
+
+    pub async fn provision_application_type(
+        &self,
+        description: &ProvisionApplicationTypeDescription,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        run_with_retry("provision_application_type", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx =
+                    try_provision_application_type(client.resolve()?, description, timeout_ms)?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("ProvisionApplicationType"))?
+            }
+        })
+        .await
+    }
+
+    /// Removes a provisioned application type version. It must not be in use
+    /// by any application.
+    pub async fn unprovision_application_type(
+        &self,
+        application_type_name: &str,
+        application_type_version: &str,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        run_with_retry("unprovision_application_type", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx = try_unprovision_application_type(
+                    client.resolve()?,
+                    application_type_name,
+                    application_type_version,
+                    timeout_ms,
+                )?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("UnprovisionApplicationType"))?
+            }
+        })
+        .await
+    }
+
+    pub async fn create_application(
+        &self,
+        description: &ApplicationDescription,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        run_with_retry("create_application", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx = try_create_application(client.resolve()?, description, timeout_ms)?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("CreateApplication"))?
+            }
+        })
+        .await
+    }
+
+    /// Deletes an application and all of its services. `force` skips the
+    /// graceful close of their replicas.
+    pub async fn delete_application(
+        &self,
+        application_name: &str,
+        force: bool,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        run_with_retry("delete_application", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx =
+                    try_delete_application(client.resolve()?, application_name, force, timeout_ms)?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("DeleteApplication"))?
+            }
+        })
+        .await
+    }
+
+    /// Changes the capacity settings of an existing application.
+    pub async fn update_application(
+        &self,
+        application_name: &str,
+        description: &ApplicationUpdateDescription,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        description.validate()?;
+        run_with_retry("update_application", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx = try_update_application(
+                    client.resolve()?,
+                    application_name,
+                    description,
+                    timeout_ms,
+                )?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("UpdateApplication"))?
+            }
+        })
+        .await
+    }
+
+    /// Starts a rolling upgrade. The call returns once SF has accepted the
+    /// upgrade; use `get_application_upgrade_progress` or `watch_upgrade` to
+    /// follow it.
+    pub async fn upgrade_application(
+        &self,
+        description: &ApplicationUpgradeDescription,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        if let Some(health_policy) = &description.health_policy {
+            health_policy.validate()?;
+        }
+        run_with_retry("upgrade_application", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx = try_upgrade_application(client.resolve()?, description, timeout_ms)?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("UpgradeApplication"))?
+            }
+        })
+        .await
+    }
+
+    pub async fn get_application_upgrade_progress(
+        &self,
+        application_name: &str,
+        timeout_ms: u32,
+    ) -> Result<ApplicationUpgradeProgress, Error> {
+        run_with_retry("get_application_upgrade_progress", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx = try_get_application_upgrade_progress(
+                    client.resolve()?,
+                    application_name,
+                    timeout_ms,
+                )?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("GetApplicationUpgradeProgress"))?
+            }
+        })
+        .await
+    }
+
+    /// Starts upgrading `next_upgrade_domain` in an `UnmonitoredManual`
+    /// upgrade. It must be the domain reported as next by the upgrade
+    /// progress.
+    pub async fn move_next_application_upgrade_domain(
+        &self,
+        application_name: &str,
+        next_upgrade_domain: &str,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        run_with_retry("move_next_application_upgrade_domain", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx = try_move_next_application_upgrade_domain(
+                    client.resolve()?,
+                    application_name,
+                    next_upgrade_domain,
+                    timeout_ms,
+                )?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("MoveNextApplicationUpgradeDomain"))?
+            }
+        })
+        .await
+    }
+
+    /// Changes the policy of an upgrade that is in progress.
+    pub async fn update_application_upgrade(
+        &self,
+        application_name: &str,
+        description: &ApplicationUpgradeUpdateDescription,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        if let Some(health_policy) = &description.health_policy {
+            health_policy.validate()?;
+        }
+        run_with_retry("update_application_upgrade", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx = try_update_application_upgrade(
+                    client.resolve()?,
+                    application_name,
+                    description,
+                    timeout_ms,
+                )?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("UpdateApplicationUpgrade"))?
+            }
+        })
+        .await
+    }
+
+    /// Rolls an in-progress upgrade back to the previous version.
+    pub async fn rollback_application_upgrade(
+        &self,
+        application_name: &str,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        run_with_retry("rollback_application_upgrade", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx = try_rollback_application_upgrade(
+                    client.resolve()?,
+                    application_name,
+                    timeout_ms,
+                )?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("RollbackApplicationUpgrade"))?
+            }
+        })
+        .await
+    }
+
+    /// Polls the upgrade progress of an application every `interval` and
+    /// streams each upgrade domain state change. The stream ends with
+    /// `UpgradeEvent::Finished` once the upgrade completes, rolls back or
+    /// fails, or after the first error. Polling stops once the stream is
+    /// dropped. Called outside a tokio runtime, the stream yields
+    /// `Error::NoTokioRuntime`.
+    pub fn watch_upgrade(
+        &self,
+        application_name: &str,
+        interval: Duration,
+        timeout_ms: u32,
+    ) -> ReceiverStream<Result<UpgradeEvent, Error>> {
+        let client = self.clone();
+        let application_name = application_name.to_string();
+        poll_upgrade(interval, move || {
+            let client = client.clone();
+            let application_name = application_name.clone();
+            async move {
+                let progress = client
+                    .get_application_upgrade_progress(&application_name, timeout_ms)
+                    .await?;
+                Ok((progress.state, progress.upgrade_domains))
+            }
+        })
+    }
+}
+
+fn try_provision_application_type(
+    client: IFabricApplicationManagementClient10,
+    description: &ProvisionApplicationTypeDescription,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<(), Error>>, Error> {
+    let mut arena = Arena::new();
+    let description = description.to_raw(&mut arena);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginProvisionApplicationType3(&description, timeout_ms, Some(callback))
+        },
+        move |context| Ok(unsafe { end_client.EndProvisionApplicationType3(context) }?),
+    )
+}
+
+fn try_unprovision_application_type(
+    client: IFabricApplicationManagementClient10,
+    application_type_name: &str,
+    application_type_version: &str,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<(), Error>>, Error> {
+    let application_type_name = to_wide(application_type_name);
+    let application_type_version = to_wide(application_type_version);
+    let description = FABRIC_UNPROVISION_APPLICATION_TYPE_DESCRIPTION {
+        ApplicationTypeName: PCWSTR(application_type_name.as_ptr()),
+        ApplicationTypeVersion: PCWSTR(application_type_version.as_ptr()),
+        Async: false.into(),
+        Reserved: ptr::null_mut(),
+    };
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginUnprovisionApplicationType2(&description, timeout_ms, Some(callback))
+        },
+        move |context| Ok(unsafe { end_client.EndUnprovisionApplicationType2(context) }?),
+    )
+}
+
+fn try_create_application(
+    client: IFabricApplicationManagementClient10,
+    description: &ApplicationDescription,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<(), Error>>, Error> {
+    let mut arena = Arena::new();
+    let description = description.to_raw(&mut arena);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginCreateApplication(&description, timeout_ms, Some(callback))
+        },
+        move |context| Ok(unsafe { end_client.EndCreateApplication(context) }?),
+    )
+}
+
+fn try_delete_application(
+    client: IFabricApplicationManagementClient10,
+    application_name: &str,
+    force: bool,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<(), Error>>, Error> {
+    let mut application_name = to_wide(application_name);
+    let description = FABRIC_DELETE_APPLICATION_DESCRIPTION {
+        ApplicationName: application_name.as_mut_ptr(),
+        ForceDelete: force.into(),
+        Reserved: ptr::null_mut(),
+    };
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginDeleteApplication2(&description, timeout_ms, Some(callback))
+        },
+        move |context| Ok(unsafe { end_client.EndDeleteApplication2(context) }?),
+    )
+}
+
+fn try_update_application(
+    client: IFabricApplicationManagementClient10,
+    application_name: &str,
+    description: &ApplicationUpdateDescription,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<(), Error>>, Error> {
+    let mut arena = Arena::new();
+    let description = description.to_raw(&mut arena, application_name);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginUpdateApplication(&description, timeout_ms, Some(callback))
+        },
+        move |context| Ok(unsafe { end_client.EndUpdateApplication(context) }?),
+    )
+}
+
+fn try_upgrade_application(
+    client: IFabricApplicationManagementClient10,
+    description: &ApplicationUpgradeDescription,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<(), Error>>, Error> {
+    let mut arena = Arena::new();
+    let description = description.to_raw(&mut arena);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginUpgradeApplication(&description, timeout_ms, Some(callback))
+        },
+        move |context| Ok(unsafe { end_client.EndUpgradeApplication(context) }?),
+    )
+}
+
+fn try_get_application_upgrade_progress(
+    client: IFabricApplicationManagementClient10,
+    application_name: &str,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<ApplicationUpgradeProgress, Error>>, Error> {
+    let application_name = to_wide(application_name);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginGetApplicationUpgradeProgress(
+                application_name.as_ptr(),
+                timeout_ms,
+                Some(callback),
+            )
+        },
+        move |context| {
+            let res = unsafe { end_client.EndGetApplicationUpgradeProgress(context) }?;
+            ApplicationUpgradeProgress::try_from(&res)
+        },
+    )
+}
+
+fn try_move_next_application_upgrade_domain(
+    client: IFabricApplicationManagementClient10,
+    application_name: &str,
+    next_upgrade_domain: &str,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<(), Error>>, Error> {
+    let application_name = to_wide(application_name);
+    let next_upgrade_domain = to_wide(next_upgrade_domain);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginMoveNextApplicationUpgradeDomain2(
+                application_name.as_ptr(),
+                PCWSTR(next_upgrade_domain.as_ptr()),
+                timeout_ms,
+                Some(callback),
+            )
+        },
+        move |context| Ok(unsafe { end_client.EndMoveNextApplicationUpgradeDomain2(context) }?),
+    )
+}
+
+fn try_update_application_upgrade(
+    client: IFabricApplicationManagementClient10,
+    application_name: &str,
+    description: &ApplicationUpgradeUpdateDescription,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<(), Error>>, Error> {
+    let mut arena = Arena::new();
+    let description = description.to_raw(&mut arena, application_name);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginUpdateApplicationUpgrade(&description, timeout_ms, Some(callback))
+        },
+        move |context| Ok(unsafe { end_client.EndUpdateApplicationUpgrade(context) }?),
+    )
+}
+
+fn try_rollback_application_upgrade(
+    client: IFabricApplicationManagementClient10,
+    application_name: &str,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<(), Error>>, Error> {
+    let application_name = to_wide(application_name);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginRollbackApplicationUpgrade(
+                application_name.as_ptr(),
+                timeout_ms,
+                Some(callback),
+            )
+        },
+        move |context| Ok(unsafe { end_client.EndRollbackApplicationUpgrade(context) }?),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        FABRIC_ROLLING_UPGRADE_MODE_MONITORED, FABRIC_ROLLING_UPGRADE_POLICY_DESCRIPTION,
+        FABRIC_ROLLING_UPGRADE_POLICY_DESCRIPTION_EX1,
+    };
+
+    fn parameters(items: &[(&str, &str)]) -> BTreeMap<String, String> {
+        items
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn diff_parameters_reports_only_changes() {
+        let current = parameters(&[("Instances", "3"), ("LogLevel", "Info"), ("Port", "80")]);
+        let target = parameters(&[("Instances", "5"), ("Port", "80"), ("Region", "west")]);
+
+        assert_eq!(
+            diff_parameters(&current, &target),
+            vec![
+                ParameterChange::Removed {
+                    name: "LogLevel".to_string(),
+                    previous: "Info".to_string(),
+                },
+                ParameterChange::Changed {
+                    name: "Instances".to_string(),
+                    previous: "3".to_string(),
+                    value: "5".to_string(),
+                },
+                ParameterChange::Added {
+                    name: "Region".to_string(),
+                    value: "west".to_string(),
+                },
+            ]
+        );
+        assert!(diff_parameters(&target, &target).is_empty());
+    }
+
+    #[test]
+    fn invalid_capacity_updates_are_rejected() {
+        assert!(ApplicationUpdateDescription::new()
+            .minimum_nodes(4)
+            .maximum_nodes(2)
+            .validate()
+            .is_err());
+
+        let mut description = ApplicationUpdateDescription::remove_application_capacity();
+        assert!(description.validate().is_ok());
+        description.maximum_nodes = Some(3);
+        assert!(description.validate().is_err());
+    }
+
+    #[test]
+    fn upgrade_update_flags_match_the_fields_set() {
+        assert_eq!(ApplicationUpgradeUpdateDescription::new().flags(), 0);
+
+        let description = ApplicationUpgradeUpdateDescription::new()
+            .force_restart(false)
+            .health_check_retry_timeout(Duration::from_secs(60))
+            .health_policy(ApplicationHealthPolicy::new());
+        assert_eq!(
+            description.flags(),
+            FABRIC_ROLLING_UPGRADE_UPDATE_FLAGS_FORCE_RESTART.0 as u32
+                | FABRIC_ROLLING_UPGRADE_UPDATE_FLAGS_HEALTH_CHECK_RETRY.0 as u32
+                | FABRIC_ROLLING_UPGRADE_UPDATE_FLAGS_HEALTH_POLICY.0 as u32
+        );
+    }
+
+    #[test]
+    fn upgrade_update_to_raw() {
+        let description = ApplicationUpgradeUpdateDescription::new()
+            .mode(UpgradeMode::Monitored)
+            .force_restart(true)
+            .health_policy(ApplicationHealthPolicy::new());
+        let mut arena = Arena::new();
+        let raw = description.to_raw(&mut arena, "fabric:/app");
+
+        assert_eq!(
+            unsafe { wide_to_string(raw.ApplicationName) }.unwrap(),
+            "fabric:/app"
+        );
+        assert_eq!(raw.UpdateFlags, description.flags());
+        let policy = unsafe {
+            &*(raw.UpgradePolicyDescription as *const FABRIC_ROLLING_UPGRADE_POLICY_DESCRIPTION)
+        };
+        assert_eq!(
+            policy.RollingUpgradeMode,
+            FABRIC_ROLLING_UPGRADE_MODE_MONITORED
+        );
+        assert!(policy.ForceRestart.as_bool());
+        let ex1 =
+            unsafe { &*(policy.Reserved as *const FABRIC_ROLLING_UPGRADE_POLICY_DESCRIPTION_EX1) };
+        assert!(!ex1.HealthPolicy.is_null());
+
+        let raw = ApplicationUpgradeUpdateDescription::new().to_raw(&mut arena, "fabric:/app");
+        let policy = unsafe {
+            &*(raw.UpgradePolicyDescription as *const FABRIC_ROLLING_UPGRADE_POLICY_DESCRIPTION)
+        };
+        let ex1 =
+            unsafe { &*(policy.Reserved as *const FABRIC_ROLLING_UPGRADE_POLICY_DESCRIPTION_EX1) };
+        assert!(ex1.HealthPolicy.is_null());
+    }
+}
@@ -0,0 +1,59 @@
+use std::{any::Any, ptr};
+
+use windows::core::PCWSTR;
+
+use crate::to_wide;
+
+/// Owns the buffers that an SF description struct points into.
+///
+/// SF descriptions are trees of raw pointers. Building one means allocating
+/// each of the nested strings, lists and structs somewhere that outlives the
+/// `Begin*` call they are passed to. Values handed to the arena are boxed so
+/// their addresses stay put as more values are added.
+#[derive(Default)]
+pub(crate) struct Arena {
+    items: Vec<Box<dyn Any>>,
+}
+
+impl Arena {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores `value` and returns a pointer to it.
+    pub fn alloc<T: 'static>(&mut self, value: T) -> *mut T {
+        let mut value = Box::new(value);
+        let ptr: *mut T = value.as_mut();
+        self.items.push(value);
+        ptr
+    }
+
+    /// Stores `values` and returns a pointer to the first element, or null if
+    /// there aren't any.
+    pub fn slice<T: 'static>(&mut self, values: Vec<T>) -> *mut T {
+        if values.is_empty() {
+            return ptr::null_mut();
+        }
+
+        let mut values = Box::new(values);
+        let ptr = values.as_mut_ptr();
+        self.items.push(values);
+        ptr
+    }
+
+    /// Stores `value` as a null terminated wide string.
+    pub fn wide(&mut self, value: &str) -> PCWSTR {
+        PCWSTR(self.slice(to_wide(value)))
+    }
+
+    /// Like `wide` but returns a mutable pointer, as used for `FABRIC_URI`
+    /// fields.
+    pub fn uri(&mut self, value: &str) -> *mut u16 {
+        self.slice(to_wide(value))
+    }
+
+    /// Like `wide` but maps `None` to a null string.
+    pub fn optional_wide(&mut self, value: Option<&str>) -> PCWSTR {
+        value.map_or(PCWSTR::null(), |value| self.wide(value))
+    }
+}
@@ -0,0 +1,48 @@
+use tokio::sync::mpsc;
+use windows::core::{implement, Result as WindowsResult};
+
+use crate::{
+    channel_send, error::Error, IFabricAsyncOperationCallback, IFabricAsyncOperationCallback_Impl,
+    IFabricAsyncOperationContext,
+};
+
+type Completion = Box<dyn Fn(Option<&IFabricAsyncOperationContext>)>;
+
+/// An `IFabricAsyncOperationCallback` that hands the completed operation's
+/// context over to a closure.
+#[implement(IFabricAsyncOperationCallback)]
+pub(crate) struct AsyncCallback {
+    complete: Completion,
+}
+
+impl IFabricAsyncOperationCallback_Impl for AsyncCallback {
+    fn Invoke(&self, context: Option<&IFabricAsyncOperationContext>) {
+        (self.complete)(context)
+    }
+}
+
+/// Starts an SF async operation and returns a channel that receives its result.
+///
+/// `begin` calls the `Begin*` method with the callback it is given and `end`
+/// is run from that callback to call the matching `End*` method.
+pub(crate) fn begin_async<T, B, E>(
+    begin: B,
+    end: E,
+) -> Result<mpsc::Receiver<Result<T, Error>>, Error>
+where
+    T: Send + 'static,
+    B: FnOnce(&IFabricAsyncOperationCallback) -> WindowsResult<IFabricAsyncOperationContext>,
+    E: Fn(Option<&IFabricAsyncOperationContext>) -> Result<T, Error> + 'static,
+{
+    let (tx, rx) = mpsc::channel(1);
+    let callback: IFabricAsyncOperationCallback = AsyncCallback {
+        complete: Box::new(move |context| {
+            let _ = channel_send(tx.clone(), end(context));
+        }),
+    }
+    .into();
+
+    let _context = begin(&callback)?;
+
+    Ok(rx)
+}
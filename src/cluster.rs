@@ -0,0 +1,1196 @@
+use std::{collections::BTreeMap, ffi::c_void, ptr, time::Duration};
+
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use windows::core::{ComInterface, PCWSTR};
+
+use crate::{
+    agile::AgileRef,
+    application_health_policy_map,
+    arena::Arena,
+    callback::begin_async,
+    cluster_health_policy,
+    error::{Error, FabricErrorCode},
+    list_items, optional_string, optional_wide, poll_upgrade, run_with_retry, seconds, to_wide,
+    upgrade_domain_list, validate_percent, wide_to_string, ApplicationHealthPolicy,
+    ClusterHealthPolicy, ClusterUpgradeHealthPolicy, FailureAction,
+    IFabricClusterManagementClient15, IFabricUpgradeProgressResult2, IFabricUpgradeProgressResult3,
+    MakeClient, MetricLoad, MonitoringPolicy, Node, NodeDeactivationIntent, NodeDeactivationStatus,
+    PartitionLoadUpdateResult, PartitionMetricLoad, QueryClient, RollingUpgradePolicy,
+    UpgradeDomainStatus, UpgradeEvent, UpgradeMode, UpgradeState,
+    FABRIC_CLUSTER_MANIFEST_QUERY_DESCRIPTION, FABRIC_METRIC_LOAD_DESCRIPTION,
+    FABRIC_METRIC_LOAD_DESCRIPTION_LIST, FABRIC_PARTITION_METRIC_LOAD_DESCRIPTION,
+    FABRIC_PARTITION_METRIC_LOAD_DESCRIPTION_LIST, FABRIC_REPLICA_METRIC_LOAD_DESCRIPTION,
+    FABRIC_REPLICA_METRIC_LOAD_DESCRIPTION_LIST, FABRIC_ROLLING_UPGRADE_POLICY_DESCRIPTION_EX2,
+    FABRIC_ROLLING_UPGRADE_POLICY_DESCRIPTION_EX3, FABRIC_START_UPGRADE_DESCRIPTION,
+    FABRIC_START_UPGRADE_DESCRIPTION_EX1, FABRIC_UPDATE_PARTITION_LOAD_QUERY_DESCRIPTION,
+    FABRIC_UPDATE_PARTITION_LOAD_QUERY_RESULT_LIST, FABRIC_UPGRADE_DESCRIPTION,
+    FABRIC_UPGRADE_KIND_ROLLING,
+};
+
+/// The outcome of asking SF to start a cluster upgrade. SF rejects a new
+/// upgrade while another is running or when the cluster already runs the
+/// target version; those are reported here rather than as errors.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FabricUpgradeOutcome {
+    Started,
+    AlreadyInProgress,
+    AlreadyInTargetVersion,
+}
+
+/// Describes a rolling upgrade of the SF runtime, its cluster manifest or
+/// both. Versions must have been provisioned with `provision_fabric` first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FabricUpgradeDescription {
+    pub code_version: Option<String>,
+    pub config_version: Option<String>,
+    pub policy: RollingUpgradePolicy,
+    /// Used by monitored upgrades. SF falls back to the policy in the
+    /// cluster manifest when this isn't set.
+    pub health_policy: Option<ClusterHealthPolicy>,
+    /// Enables delta health evaluation with these limits.
+    pub upgrade_health_policy: Option<ClusterUpgradeHealthPolicy>,
+    /// Health policies for specific applications, keyed by application name.
+    pub application_health_policies: BTreeMap<String, ApplicationHealthPolicy>,
+}
+
+impl FabricUpgradeDescription {
+    pub fn new(
+        code_version: Option<&str>,
+        config_version: Option<&str>,
+        policy: RollingUpgradePolicy,
+    ) -> Self {
+        Self {
+            code_version: code_version.map(str::to_string),
+            config_version: config_version.map(str::to_string),
+            policy,
+            health_policy: None,
+            upgrade_health_policy: None,
+            application_health_policies: BTreeMap::new(),
+        }
+    }
+
+    pub fn health_policy(mut self, health_policy: ClusterHealthPolicy) -> Self {
+        self.health_policy = Some(health_policy);
+        self
+    }
+
+    pub fn upgrade_health_policy(
+        mut self,
+        upgrade_health_policy: ClusterUpgradeHealthPolicy,
+    ) -> Self {
+        self.upgrade_health_policy = Some(upgrade_health_policy);
+        self
+    }
+
+    pub fn application_health_policy(
+        mut self,
+        application_name: &str,
+        health_policy: ApplicationHealthPolicy,
+    ) -> Self {
+        self.application_health_policies
+            .insert(application_name.to_string(), health_policy);
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        if let Some(health_policy) = &self.health_policy {
+            health_policy.validate()?;
+        }
+        if let Some(upgrade_health_policy) = &self.upgrade_health_policy {
+            upgrade_health_policy.validate()?;
+        }
+        self.application_health_policies
+            .values()
+            .try_for_each(ApplicationHealthPolicy::validate)
+    }
+
+    fn to_raw(&self, arena: &mut Arena) -> FABRIC_UPGRADE_DESCRIPTION {
+        let health_policy =
+            cluster_health_policy(arena, self.health_policy.as_ref()) as *mut c_void;
+        let application_health_policies = if self.application_health_policies.is_empty() {
+            ptr::null()
+        } else {
+            application_health_policy_map(arena, &self.application_health_policies)
+        };
+        let ex3 = arena.alloc(FABRIC_ROLLING_UPGRADE_POLICY_DESCRIPTION_EX3 {
+            ApplicationHealthPolicyMap: application_health_policies,
+            Reserved: ptr::null_mut(),
+        });
+        let upgrade_health_policy = self
+            .upgrade_health_policy
+            .map_or(ptr::null_mut(), |policy| {
+                arena.alloc(policy.to_raw()) as *mut c_void
+            });
+        let ex2 = arena.alloc(FABRIC_ROLLING_UPGRADE_POLICY_DESCRIPTION_EX2 {
+            EnableDeltaHealthEvaluation: self.upgrade_health_policy.is_some().into(),
+            UpgradeHealthPolicy: upgrade_health_policy,
+            Reserved: ex3 as *mut c_void,
+        });
+        let code_version = arena.optional_wide(self.code_version.as_deref());
+        let config_version = arena.optional_wide(self.config_version.as_deref());
+
+        FABRIC_UPGRADE_DESCRIPTION {
+            CodeVersion: code_version,
+            ConfigVersion: config_version,
+            UpgradeKind: FABRIC_UPGRADE_KIND_ROLLING,
+            UpgradePolicyDescription: self.policy.to_raw(arena, health_policy, ex2 as *mut c_void),
+            Reserved: ptr::null_mut(),
+        }
+    }
+}
+
+/// A snapshot of a cluster upgrade.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FabricUpgradeProgress {
+    pub target_code_version: String,
+    pub target_config_version: String,
+    pub state: UpgradeState,
+    pub mode: UpgradeMode,
+    /// The domain a manual upgrade will move to next, if any.
+    pub next_upgrade_domain: Option<String>,
+    pub upgrade_domains: Vec<UpgradeDomainStatus>,
+    pub upgrade_duration: Duration,
+    pub current_upgrade_domain_duration: Duration,
+}
+
+impl TryFrom<&IFabricUpgradeProgressResult2> for FabricUpgradeProgress {
+    type Error = Error;
+
+    fn try_from(value: &IFabricUpgradeProgressResult2) -> Result<Self, Self::Error> {
+        let result = value.cast::<IFabricUpgradeProgressResult3>()?;
+        let progress = unsafe { &*result.get_UpgradeProgress() };
+        let next_upgrade_domain =
+            unsafe { optional_string(PCWSTR(value.get_NextUpgradeDomain().0))? }
+                .filter(|name| !name.is_empty());
+
+        Ok(Self {
+            target_code_version: unsafe { wide_to_string(value.get_TargetCodeVersion().0)? },
+            target_config_version: unsafe { wide_to_string(value.get_TargetConfigVersion().0)? },
+            state: unsafe { value.get_UpgradeState() }.into(),
+            mode: unsafe { value.get_RollingUpgradeMode() }.into(),
+            next_upgrade_domain,
+            upgrade_domains: unsafe { upgrade_domain_list(progress.UpgradeDomains)? },
+            upgrade_duration: Duration::from_secs(progress.UpgradeDurationInSeconds as u64),
+            current_upgrade_domain_duration: Duration::from_secs(
+                progress.CurrentUpgradeDomainDurationInSeconds as u64,
+            ),
+        })
+    }
+}
+
+/// Describes a configuration upgrade of a standalone cluster from its JSON
+/// cluster configuration. These upgrades are always monitored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClusterConfigurationUpgradeDescription {
+    /// The full JSON cluster configuration to move to.
+    pub cluster_config: String,
+    pub health_check_wait: Duration,
+    pub health_check_stable: Duration,
+    pub health_check_retry_timeout: Duration,
+    pub upgrade_timeout: Option<Duration>,
+    pub upgrade_domain_timeout: Option<Duration>,
+    pub max_percent_unhealthy_applications: u8,
+    pub max_percent_unhealthy_nodes: u8,
+    pub upgrade_health_policy: ClusterUpgradeHealthPolicy,
+    /// Health policies for specific applications, keyed by application name.
+    pub application_health_policies: BTreeMap<String, ApplicationHealthPolicy>,
+}
+
+impl ClusterConfigurationUpgradeDescription {
+    /// Creates a description with SF's default health check timing and
+    /// zero tolerance for unhealthy nodes and applications.
+    pub fn new(cluster_config: &str) -> Self {
+        let monitoring_policy = MonitoringPolicy::new(FailureAction::Rollback);
+        Self {
+            cluster_config: cluster_config.to_string(),
+            health_check_wait: monitoring_policy.health_check_wait,
+            health_check_stable: monitoring_policy.health_check_stable,
+            health_check_retry_timeout: monitoring_policy.health_check_retry_timeout,
+            upgrade_timeout: monitoring_policy.upgrade_timeout,
+            upgrade_domain_timeout: monitoring_policy.upgrade_domain_timeout,
+            max_percent_unhealthy_applications: 0,
+            max_percent_unhealthy_nodes: 0,
+            upgrade_health_policy: ClusterUpgradeHealthPolicy::default(),
+            application_health_policies: BTreeMap::new(),
+        }
+    }
+
+    pub fn health_check_wait(mut self, health_check_wait: Duration) -> Self {
+        self.health_check_wait = health_check_wait;
+        self
+    }
+
+    pub fn health_check_stable(mut self, health_check_stable: Duration) -> Self {
+        self.health_check_stable = health_check_stable;
+        self
+    }
+
+    pub fn health_check_retry_timeout(mut self, health_check_retry_timeout: Duration) -> Self {
+        self.health_check_retry_timeout = health_check_retry_timeout;
+        self
+    }
+
+    pub fn upgrade_timeout(mut self, upgrade_timeout: Duration) -> Self {
+        self.upgrade_timeout = Some(upgrade_timeout);
+        self
+    }
+
+    pub fn upgrade_domain_timeout(mut self, upgrade_domain_timeout: Duration) -> Self {
+        self.upgrade_domain_timeout = Some(upgrade_domain_timeout);
+        self
+    }
+
+    pub fn max_percent_unhealthy_applications(mut self, percent: u8) -> Self {
+        self.max_percent_unhealthy_applications = percent;
+        self
+    }
+
+    pub fn max_percent_unhealthy_nodes(mut self, percent: u8) -> Self {
+        self.max_percent_unhealthy_nodes = percent;
+        self
+    }
+
+    pub fn upgrade_health_policy(
+        mut self,
+        upgrade_health_policy: ClusterUpgradeHealthPolicy,
+    ) -> Self {
+        self.upgrade_health_policy = upgrade_health_policy;
+        self
+    }
+
+    pub fn application_health_policy(
+        mut self,
+        application_name: &str,
+        health_policy: ApplicationHealthPolicy,
+    ) -> Self {
+        self.application_health_policies
+            .insert(application_name.to_string(), health_policy);
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        validate_percent(self.max_percent_unhealthy_applications)?;
+        validate_percent(self.max_percent_unhealthy_nodes)?;
+        self.upgrade_health_policy.validate()?;
+        self.application_health_policies
+            .values()
+            .try_for_each(ApplicationHealthPolicy::validate)
+    }
+
+    fn to_raw(&self, arena: &mut Arena) -> FABRIC_START_UPGRADE_DESCRIPTION {
+        let application_health_policies = if self.application_health_policies.is_empty() {
+            ptr::null()
+        } else {
+            application_health_policy_map(arena, &self.application_health_policies)
+        };
+        let ex1 = arena.alloc(FABRIC_START_UPGRADE_DESCRIPTION_EX1 {
+            ApplicationHealthPolicyMap: application_health_policies,
+            Reserved: ptr::null_mut(),
+        });
+
+        FABRIC_START_UPGRADE_DESCRIPTION {
+            ClusterConfig: arena.wide(&self.cluster_config),
+            HealthCheckRetryTimeoutInSeconds: seconds(Some(self.health_check_retry_timeout)),
+            HealthCheckWaitDurationInSeconds: seconds(Some(self.health_check_wait)),
+            HealthCheckStableDurationInSeconds: seconds(Some(self.health_check_stable)),
+            UpgradeDomainTimeoutInSeconds: seconds(self.upgrade_domain_timeout),
+            UpgradeTimeoutInSeconds: seconds(self.upgrade_timeout),
+            MaxPercentUnhealthyApplications: self.max_percent_unhealthy_applications,
+            MaxPercentUnhealthyNodes: self.max_percent_unhealthy_nodes,
+            MaxPercentDeltaUnhealthyNodes: self
+                .upgrade_health_policy
+                .max_percent_delta_unhealthy_nodes,
+            MaxPercentUpgradeDomainDeltaUnhealthyNodes: self
+                .upgrade_health_policy
+                .max_percent_upgrade_domain_delta_unhealthy_nodes,
+            Reserved: ex1 as *mut c_void,
+        }
+    }
+}
+
+fn upgrade_outcome(res: Result<(), Error>) -> Result<FabricUpgradeOutcome, Error> {
+    match res {
+        Ok(()) => Ok(FabricUpgradeOutcome::Started),
+        Err(e) => match e.fabric_error_code() {
+            Some(FabricErrorCode::FabricUpgradeInProgress) => {
+                Ok(FabricUpgradeOutcome::AlreadyInProgress)
+            }
+            Some(FabricErrorCode::FabricAlreadyInTargetVersion) => {
+                Ok(FabricUpgradeOutcome::AlreadyInTargetVersion)
+            }
+            _ => Err(e),
+        },
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ClusterManagementClient {
+    client: AgileRef<IFabricClusterManagementClient15>,
+    query: QueryClient,
+}
+
+impl MakeClient for ClusterManagementClient {
+    type Interface = IFabricClusterManagementClient15;
+
+    fn make(client: Self::Interface) -> Result<Self, Error> {
+        Ok(Self {
+            client: AgileRef::new(client.cast()?)?,
+            query: QueryClient::new(client.cast()?)?,
+        })
+    }
+}
+
+impl ClusterManagementClient {
+    pub fn new(client: IFabricClusterManagementClient15) -> Result<Self, Error> {
+        Ok(Self {
+            client: AgileRef::new(client.cast()?)?,
+            query: QueryClient::new(client.cast()?)?,
+        })
+    }
+
+    /// Reports load for partitions on behalf of their replicas. The result
+    /// carries the outcome for each partition in `partitions`.
+    pub async fn update_partition_load(
+        &self,
+        partitions: &[PartitionMetricLoad],
+        timeout_ms: u32,
+    ) -> Result<Vec<PartitionLoadUpdateResult>, Error> {
+        let client = self.client.clone();
+        run_with_retry("update_partition_load", move || {
+            let client = client.clone();
+            async move {
+                let mut rx = try_update_partition_load(client.resolve()?, partitions, timeout_ms)?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("UpdatePartitionLoad"))?
+            }
+        })
+        .await
+    }
+
+    /// Asks SF to deactivate a node. SF runs the safety checks for `intent`
+    /// before the node is disabled; use `wait_for_node_status` to wait for
+    /// them.
+    pub async fn deactivate_node(
+        &self,
+        node_name: &str,
+        intent: NodeDeactivationIntent,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        let client = self.client.clone();
+        run_with_retry("deactivate_node", move || {
+            let client = client.clone();
+            async move {
+                let mut rx = try_deactivate_node(client.resolve()?, node_name, intent, timeout_ms)?;
+                rx.recv().await.ok_or(Error::Abandoned("DeactivateNode"))?
+            }
+        })
+        .await
+    }
+
+    /// Cancels the deactivation of a node.
+    pub async fn activate_node(&self, node_name: &str, timeout_ms: u32) -> Result<(), Error> {
+        let client = self.client.clone();
+        run_with_retry("activate_node", move || {
+            let client = client.clone();
+            async move {
+                let mut rx = try_activate_node(client.resolve()?, node_name, timeout_ms)?;
+                rx.recv().await.ok_or(Error::Abandoned("ActivateNode"))?
+            }
+        })
+        .await
+    }
+
+    /// Tells SF that the state on a node that is down has been lost for good,
+    /// so replicas that were on it can be rebuilt elsewhere.
+    pub async fn remove_node_state(&self, node_name: &str, timeout_ms: u32) -> Result<(), Error> {
+        let client = self.client.clone();
+        run_with_retry("remove_node_state", move || {
+            let client = client.clone();
+            async move {
+                let mut rx = try_remove_node_state(client.resolve()?, node_name, timeout_ms)?;
+                rx.recv().await.ok_or(Error::Abandoned("RemoveNodeState"))?
+            }
+        })
+        .await
+    }
+
+    /// Polls the node list every `interval` until `node_name` reports the
+    /// deactivation `status` and returns the node at that point. Gives up
+    /// with `Error::TimedOut` after `timeout`; `query_timeout_ms` applies to
+    /// each individual query.
+    pub async fn wait_for_node_status(
+        &self,
+        node_name: &str,
+        status: NodeDeactivationStatus,
+        interval: Duration,
+        timeout: Duration,
+        query_timeout_ms: u32,
+    ) -> Result<Node, Error> {
+        let wait = async {
+            loop {
+                let node = self
+                    .query
+                    .get_node_list(Some(node_name), query_timeout_ms)
+                    .await?
+                    .into_iter()
+                    .find(|node| node.name == node_name);
+                if let Some(node) = node {
+                    let current = node
+                        .deactivation_info
+                        .map_or(NodeDeactivationStatus::None, |info| info.status);
+                    if current == status {
+                        return Ok(node);
+                    }
+                }
+
+                tokio::time::sleep(interval).await;
+            }
+        };
+
+        tokio::time::timeout(timeout, wait)
+            .await
+            .map_err(|_| Error::TimedOut("node deactivation status"))?
+    }
+
+    /// Registers a runtime package and/or cluster manifest from the image
+    /// store so that their versions can be upgraded to. Paths are relative to
+    /// the image store root.
+    pub async fn provision_fabric(
+        &self,
+        code_file_path: Option<&str>,
+        cluster_manifest_file_path: Option<&str>,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        let client = self.client.clone();
+        run_with_retry("provision_fabric", move || {
+            let client = client.clone();
+            async move {
+                let mut rx = try_provision_fabric(
+                    client.resolve()?,
+                    code_file_path,
+                    cluster_manifest_file_path,
+                    timeout_ms,
+                )?;
+                rx.recv().await.ok_or(Error::Abandoned("ProvisionFabric"))?
+            }
+        })
+        .await
+    }
+
+    /// Starts a cluster upgrade. Use `get_fabric_upgrade_progress` or
+    /// `watch_fabric_upgrade` to follow it. The call is not retried: a retry
+    /// after a timed out attempt that SF did accept would be reported as
+    /// `FabricUpgradeOutcome::AlreadyInProgress`, hiding that this call
+    /// started the upgrade.
+    pub async fn upgrade_fabric(
+        &self,
+        description: &FabricUpgradeDescription,
+        timeout_ms: u32,
+    ) -> Result<FabricUpgradeOutcome, Error> {
+        description.validate()?;
+        let mut rx = try_upgrade_fabric(self.client.resolve()?, description, timeout_ms)?;
+        let res = rx.recv().await.ok_or(Error::Abandoned("UpgradeFabric"))?;
+
+        upgrade_outcome(res)
+    }
+
+    pub async fn get_fabric_upgrade_progress(
+        &self,
+        timeout_ms: u32,
+    ) -> Result<FabricUpgradeProgress, Error> {
+        let client = self.client.clone();
+        run_with_retry("get_fabric_upgrade_progress", move || {
+            let client = client.clone();
+            async move {
+                let mut rx = try_get_fabric_upgrade_progress(client.resolve()?, timeout_ms)?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("GetFabricUpgradeProgress"))?
+            }
+        })
+        .await
+    }
+
+    /// Starts upgrading `next_upgrade_domain` in an `UnmonitoredManual`
+    /// cluster upgrade.
+    pub async fn move_next_fabric_upgrade_domain(
+        &self,
+        next_upgrade_domain: &str,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        let client = self.client.clone();
+        run_with_retry("move_next_fabric_upgrade_domain", move || {
+            let client = client.clone();
+            async move {
+                let mut rx = try_move_next_fabric_upgrade_domain(
+                    client.resolve()?,
+                    next_upgrade_domain,
+                    timeout_ms,
+                )?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("MoveNextFabricUpgradeDomain"))?
+            }
+        })
+        .await
+    }
+
+    /// Rolls an in-progress cluster upgrade back to the previous version.
+    pub async fn rollback_fabric_upgrade(&self, timeout_ms: u32) -> Result<(), Error> {
+        let client = self.client.clone();
+        run_with_retry("rollback_fabric_upgrade", move || {
+            let client = client.clone();
+            async move {
+                let mut rx = try_rollback_fabric_upgrade(client.resolve()?, timeout_ms)?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("RollbackFabricUpgrade"))?
+            }
+        })
+        .await
+    }
+
+    /// Starts a configuration upgrade of a standalone cluster. Like
+    /// `upgrade_fabric`, the call is not retried.
+    pub async fn upgrade_configuration(
+        &self,
+        description: &ClusterConfigurationUpgradeDescription,
+        timeout_ms: u32,
+    ) -> Result<FabricUpgradeOutcome, Error> {
+        description.validate()?;
+        let mut rx = try_upgrade_configuration(self.client.resolve()?, description, timeout_ms)?;
+        let res = rx
+            .recv()
+            .await
+            .ok_or(Error::Abandoned("UpgradeConfiguration"))?;
+
+        upgrade_outcome(res)
+    }
+
+    /// Polls the cluster upgrade progress every `interval` and streams each
+    /// upgrade domain state change. The stream ends with
+    /// `UpgradeEvent::Finished` once the upgrade completes, rolls back or
+    /// fails, or after the first error. Polling stops once the stream is
+    /// dropped. Called outside a tokio runtime, the stream yields
+    /// `Error::NoTokioRuntime`.
+    pub fn watch_fabric_upgrade(
+        &self,
+        interval: Duration,
+        timeout_ms: u32,
+    ) -> ReceiverStream<Result<UpgradeEvent, Error>> {
+        let client = self.clone();
+        poll_upgrade(interval, move || {
+            let client = client.clone();
+            async move {
+                let progress = client.get_fabric_upgrade_progress(timeout_ms).await?;
+                Ok((progress.state, progress.upgrade_domains))
+            }
+        })
+    }
+
+    /// Fetches the cluster manifest XML for `version`, or for the version the
+    /// cluster currently runs when `version` is `None`. Use
+    /// `ClusterManifest::parse` to read it.
+    pub async fn get_cluster_manifest(
+        &self,
+        version: Option<&str>,
+        timeout_ms: u32,
+    ) -> Result<String, Error> {
+        let client = self.client.clone();
+        run_with_retry("get_cluster_manifest", move || {
+            let client = client.clone();
+            async move {
+                let mut rx = try_get_cluster_manifest(client.resolve()?, version, timeout_ms)?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("GetClusterManifest"))?
+            }
+        })
+        .await
+    }
+}
+
+fn try_deactivate_node(
+    client: IFabricClusterManagementClient15,
+    node_name: &str,
+    intent: NodeDeactivationIntent,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<(), Error>>, Error> {
+    let node_name = to_wide(node_name);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginDeactivateNode(
+                PCWSTR(node_name.as_ptr()),
+                intent.into(),
+                timeout_ms,
+                Some(callback),
+            )
+        },
+        move |context| Ok(unsafe { end_client.EndDeactivateNode(context) }?),
+    )
+}
+
+fn try_activate_node(
+    client: IFabricClusterManagementClient15,
+    node_name: &str,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<(), Error>>, Error> {
+    let node_name = to_wide(node_name);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginActivateNode(PCWSTR(node_name.as_ptr()), timeout_ms, Some(callback))
+        },
+        move |context| Ok(unsafe { end_client.EndActivateNode(context) }?),
+    )
+}
+
+fn try_remove_node_state(
+    client: IFabricClusterManagementClient15,
+    node_name: &str,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<(), Error>>, Error> {
+    let node_name = to_wide(node_name);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginNodeStateRemoved(PCWSTR(node_name.as_ptr()), timeout_ms, Some(callback))
+        },
+        move |context| Ok(unsafe { end_client.EndNodeStateRemoved(context) }?),
+    )
+}
+
+fn try_provision_fabric(
+    client: IFabricClusterManagementClient15,
+    code_file_path: Option<&str>,
+    cluster_manifest_file_path: Option<&str>,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<(), Error>>, Error> {
+    let code_file_path = code_file_path.map(to_wide);
+    let cluster_manifest_file_path = cluster_manifest_file_path.map(to_wide);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginProvisionFabric(
+                PCWSTR(optional_wide(&code_file_path)),
+                PCWSTR(optional_wide(&cluster_manifest_file_path)),
+                timeout_ms,
+                Some(callback),
+            )
+        },
+        move |context| Ok(unsafe { end_client.EndProvisionFabric(context) }?),
+    )
+}
+
+fn try_upgrade_fabric(
+    client: IFabricClusterManagementClient15,
+    description: &FabricUpgradeDescription,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<(), Error>>, Error> {
+    let mut arena = Arena::new();
+    let description = description.to_raw(&mut arena);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe { client.BeginUpgradeFabric(&description, timeout_ms, Some(callback)) },
+        move |context| Ok(unsafe { end_client.EndUpgradeFabric(context) }?),
+    )
+}
+
+fn try_get_fabric_upgrade_progress(
+    client: IFabricClusterManagementClient15,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<FabricUpgradeProgress, Error>>, Error> {
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe { client.BeginGetFabricUpgradeProgress(timeout_ms, Some(callback)) },
+        move |context| {
+            let res = unsafe { end_client.EndGetFabricUpgradeProgress(context) }?;
+            FabricUpgradeProgress::try_from(&res)
+        },
+    )
+}
+
+fn try_move_next_fabric_upgrade_domain(
+    client: IFabricClusterManagementClient15,
+    next_upgrade_domain: &str,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<(), Error>>, Error> {
+    let next_upgrade_domain = to_wide(next_upgrade_domain);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginMoveNextFabricUpgradeDomain2(
+                PCWSTR(next_upgrade_domain.as_ptr()),
+                timeout_ms,
+                Some(callback),
+            )
+        },
+        move |context| Ok(unsafe { end_client.EndMoveNextFabricUpgradeDomain2(context) }?),
+    )
+}
+
+fn try_rollback_fabric_upgrade(
+    client: IFabricClusterManagementClient15,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<(), Error>>, Error> {
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe { client.BeginRollbackFabricUpgrade(timeout_ms, Some(callback)) },
+        move |context| Ok(unsafe { end_client.EndRollbackFabricUpgrade(context) }?),
+    )
+}
+
+fn try_upgrade_configuration(
+    client: IFabricClusterManagementClient15,
+    description: &ClusterConfigurationUpgradeDescription,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<(), Error>>, Error> {
+    let mut arena = Arena::new();
+    let description = description.to_raw(&mut arena);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginUpgradeConfiguration(&description, timeout_ms, Some(callback))
+        },
+        move |context| Ok(unsafe { end_client.EndUpgradeConfiguration(context) }?),
+    )
+}
+
+fn try_get_cluster_manifest(
+    client: IFabricClusterManagementClient15,
+    version: Option<&str>,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<String, Error>>, Error> {
+    let version = version.map(to_wide);
+    let description = FABRIC_CLUSTER_MANIFEST_QUERY_DESCRIPTION {
+        ClusterManifestVersion: PCWSTR(optional_wide(&version)),
+        Reserved: ptr::null_mut(),
+    };
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginGetClusterManifest2(&description, timeout_ms, Some(callback))
+        },
+        move |context| {
+            let res = unsafe { end_client.EndGetClusterManifest2(context) }?;
+            unsafe { wide_to_string(res.get_String().0) }
+        },
+    )
+}
+
+fn try_update_partition_load(
+    client: IFabricClusterManagementClient15,
+    partitions: &[PartitionMetricLoad],
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<Vec<PartitionLoadUpdateResult>, Error>>, Error> {
+    let mut arena = Arena::new();
+    let description = partition_load_description(&mut arena, partitions);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginUpdatePartitionLoad(&description, timeout_ms, Some(callback))
+        },
+        move |context| {
+            let res = unsafe { end_client.EndUpdatePartitionLoad(context) }?;
+            unsafe { partition_load_results(res.get_UpdatePartitionLoad()) }
+        },
+    )
+}
+
+fn partition_load_description(
+    arena: &mut Arena,
+    partitions: &[PartitionMetricLoad],
+) -> FABRIC_UPDATE_PARTITION_LOAD_QUERY_DESCRIPTION {
+    let items = partitions
+        .iter()
+        .map(|partition| FABRIC_PARTITION_METRIC_LOAD_DESCRIPTION {
+            PartitionId: partition.partition_id,
+            PrimaryReplicaLoadEntries: metric_load_list(arena, &partition.primary_replica_load),
+            SecondaryReplicasOrInstancesLoadEntries: metric_load_list(
+                arena,
+                &partition.secondary_replicas_or_instances_load,
+            ),
+            SecondaryReplicaOrInstanceLoadEntriesPerNode: replica_metric_load_list(
+                arena, partition,
+            ),
+            Reserved: ptr::null_mut(),
+        })
+        .collect::<Vec<_>>();
+    let count = items.len() as u32;
+    let items = arena.slice(items);
+
+    FABRIC_UPDATE_PARTITION_LOAD_QUERY_DESCRIPTION {
+        PartitionMetricLoadDescriptionList: arena.alloc(
+            FABRIC_PARTITION_METRIC_LOAD_DESCRIPTION_LIST {
+                Count: count,
+                Items: items,
+            },
+        ),
+        ContinuationToken: PCWSTR::null(),
+        MaxResults: 0,
+        Reserved: ptr::null_mut(),
+    }
+}
+
+unsafe fn partition_load_results(
+    list: *const FABRIC_UPDATE_PARTITION_LOAD_QUERY_RESULT_LIST,
+) -> Result<Vec<PartitionLoadUpdateResult>, Error> {
+    if list.is_null() {
+        return Err(Error::NullPointer("UpdatePartitionLoad"));
+    }
+    let list = &*list;
+
+    Ok(list_items(list.Items, list.Count)
+        .iter()
+        .map(PartitionLoadUpdateResult::from)
+        .collect())
+}
+
+fn metric_load_list(
+    arena: &mut Arena,
+    loads: &[MetricLoad],
+) -> *mut FABRIC_METRIC_LOAD_DESCRIPTION_LIST {
+    let items = loads
+        .iter()
+        .map(|load| FABRIC_METRIC_LOAD_DESCRIPTION {
+            MetricName: arena.wide(&load.metric_name),
+            CurrentLoad: load.current_load.unwrap_or_default(),
+            IsCurrentLoadSpecified: load.current_load.is_some().into(),
+            PredictedLoad: load.predicted_load.unwrap_or_default(),
+            IsPredictedLoadSpecified: load.predicted_load.is_some().into(),
+            Reserved: ptr::null_mut(),
+        })
+        .collect::<Vec<_>>();
+    let count = items.len() as u32;
+    let items = arena.slice(items);
+
+    arena.alloc(FABRIC_METRIC_LOAD_DESCRIPTION_LIST {
+        Count: count,
+        Items: items,
+    })
+}
+
+fn replica_metric_load_list(
+    arena: &mut Arena,
+    partition: &PartitionMetricLoad,
+) -> *mut FABRIC_REPLICA_METRIC_LOAD_DESCRIPTION_LIST {
+    let items = partition
+        .secondary_replica_or_instance_load_per_node
+        .iter()
+        .map(|replica| FABRIC_REPLICA_METRIC_LOAD_DESCRIPTION {
+            NodeName: arena.wide(&replica.node_name),
+            ReplicaOrInstanceLoadEntries: metric_load_list(arena, &replica.load),
+            Reserved: ptr::null_mut(),
+        })
+        .collect::<Vec<_>>();
+    let count = items.len() as u32;
+    let items = arena.slice(items);
+
+    arena.alloc(FABRIC_REPLICA_METRIC_LOAD_DESCRIPTION_LIST {
+        Count: count,
+        Items: items,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use windows::core::{GUID, HRESULT};
+
+    use super::*;
+    use crate::{
+        ReplicaMetricLoad, FABRIC_CLUSTER_HEALTH_POLICY, FABRIC_CLUSTER_UPGRADE_HEALTH_POLICY,
+        FABRIC_MONITORED_UPGRADE_FAILURE_ACTION_ROLLBACK, FABRIC_ROLLING_UPGRADE_MODE_MONITORED,
+        FABRIC_ROLLING_UPGRADE_MODE_UNMONITORED_AUTO, FABRIC_ROLLING_UPGRADE_POLICY_DESCRIPTION,
+        FABRIC_ROLLING_UPGRADE_POLICY_DESCRIPTION_EX1,
+        FABRIC_UPDATE_PARTITION_LOAD_QUERY_RESULT_ITEM,
+    };
+
+    fn string(value: PCWSTR) -> String {
+        unsafe { wide_to_string(value.0) }.unwrap()
+    }
+
+    fn fabric_error(code: FabricErrorCode) -> Error {
+        Error::from(windows::core::Error::from(HRESULT(code as i32)))
+    }
+
+    fn rolling_policy(
+        raw: &FABRIC_UPGRADE_DESCRIPTION,
+    ) -> (
+        &FABRIC_ROLLING_UPGRADE_POLICY_DESCRIPTION,
+        &FABRIC_ROLLING_UPGRADE_POLICY_DESCRIPTION_EX1,
+        &FABRIC_ROLLING_UPGRADE_POLICY_DESCRIPTION_EX2,
+        &FABRIC_ROLLING_UPGRADE_POLICY_DESCRIPTION_EX3,
+    ) {
+        let policy = unsafe {
+            &*(raw.UpgradePolicyDescription as *const FABRIC_ROLLING_UPGRADE_POLICY_DESCRIPTION)
+        };
+        let ex1 =
+            unsafe { &*(policy.Reserved as *const FABRIC_ROLLING_UPGRADE_POLICY_DESCRIPTION_EX1) };
+        let ex2 =
+            unsafe { &*(ex1.Reserved as *const FABRIC_ROLLING_UPGRADE_POLICY_DESCRIPTION_EX2) };
+        let ex3 =
+            unsafe { &*(ex2.Reserved as *const FABRIC_ROLLING_UPGRADE_POLICY_DESCRIPTION_EX3) };
+        (policy, ex1, ex2, ex3)
+    }
+
+    #[test]
+    fn upgrade_outcome_maps_rejections() {
+        assert_eq!(
+            upgrade_outcome(Ok(())).unwrap(),
+            FabricUpgradeOutcome::Started
+        );
+
+        for (code, outcome) in [
+            (
+                FabricErrorCode::FabricUpgradeInProgress,
+                FabricUpgradeOutcome::AlreadyInProgress,
+            ),
+            (
+                FabricErrorCode::FabricAlreadyInTargetVersion,
+                FabricUpgradeOutcome::AlreadyInTargetVersion,
+            ),
+        ] {
+            assert_eq!(upgrade_outcome(Err(fabric_error(code))).unwrap(), outcome);
+        }
+
+        let res = upgrade_outcome(Err(fabric_error(FabricErrorCode::FabricVersionNotFound)));
+        assert_eq!(
+            res.unwrap_err().fabric_error_code(),
+            Some(FabricErrorCode::FabricVersionNotFound)
+        );
+        assert!(matches!(
+            upgrade_outcome(Err(Error::Abandoned("UpgradeFabric"))),
+            Err(Error::Abandoned("UpgradeFabric"))
+        ));
+    }
+
+    #[test]
+    fn fabric_upgrade_description_follows_the_extension_chain() {
+        let mut arena = Arena::new();
+        let description = FabricUpgradeDescription::new(
+            Some("10.1.0"),
+            None,
+            RollingUpgradePolicy::monitored(MonitoringPolicy::new(FailureAction::Rollback))
+                .force_restart(true)
+                .replica_set_check_timeout(Duration::from_secs(60)),
+        )
+        .health_policy(ClusterHealthPolicy::new().max_percent_unhealthy_nodes(10))
+        .upgrade_health_policy(ClusterUpgradeHealthPolicy {
+            max_percent_delta_unhealthy_nodes: 20,
+            max_percent_upgrade_domain_delta_unhealthy_nodes: 30,
+        })
+        .application_health_policy("fabric:/app", ApplicationHealthPolicy::new());
+
+        let raw = description.to_raw(&mut arena);
+        assert_eq!(string(raw.CodeVersion), "10.1.0");
+        assert!(raw.ConfigVersion.is_null());
+        assert_eq!(raw.UpgradeKind, FABRIC_UPGRADE_KIND_ROLLING);
+        assert!(raw.Reserved.is_null());
+
+        let (policy, ex1, ex2, ex3) = rolling_policy(&raw);
+        assert_eq!(
+            policy.RollingUpgradeMode,
+            FABRIC_ROLLING_UPGRADE_MODE_MONITORED
+        );
+        assert!(policy.ForceRestart.as_bool());
+        assert_eq!(policy.UpgradeReplicaSetCheckTimeoutInSeconds, 60);
+
+        let monitoring_policy = unsafe { &*ex1.MonitoringPolicy };
+        assert_eq!(
+            monitoring_policy.FailureAction,
+            FABRIC_MONITORED_UPGRADE_FAILURE_ACTION_ROLLBACK
+        );
+        let health_policy = unsafe { &*(ex1.HealthPolicy as *const FABRIC_CLUSTER_HEALTH_POLICY) };
+        assert_eq!(health_policy.MaxPercentUnhealthyNodes, 10);
+
+        assert!(ex2.EnableDeltaHealthEvaluation.as_bool());
+        let upgrade_health_policy =
+            unsafe { &*(ex2.UpgradeHealthPolicy as *const FABRIC_CLUSTER_UPGRADE_HEALTH_POLICY) };
+        assert_eq!(upgrade_health_policy.MaxPercentDeltaUnhealthyNodes, 20);
+        assert_eq!(
+            upgrade_health_policy.MaxPercentUpgradeDomainDeltaUnhealthyNodes,
+            30
+        );
+
+        let map = unsafe { &*ex3.ApplicationHealthPolicyMap };
+        let items = unsafe { list_items(map.Items, map.Count) };
+        assert_eq!(items.len(), 1);
+        assert_eq!(
+            unsafe { wide_to_string(items[0].ApplicationName) }.unwrap(),
+            "fabric:/app"
+        );
+        assert!(!items[0].HealthPolicy.is_null());
+        assert!(ex3.Reserved.is_null());
+    }
+
+    #[test]
+    fn fabric_upgrade_description_without_health_policies() {
+        let mut arena = Arena::new();
+        let raw = FabricUpgradeDescription::new(
+            None,
+            Some("2.0"),
+            RollingUpgradePolicy::unmonitored_auto(),
+        )
+        .to_raw(&mut arena);
+        assert!(raw.CodeVersion.is_null());
+        assert_eq!(string(raw.ConfigVersion), "2.0");
+
+        let (policy, ex1, ex2, ex3) = rolling_policy(&raw);
+        assert_eq!(
+            policy.RollingUpgradeMode,
+            FABRIC_ROLLING_UPGRADE_MODE_UNMONITORED_AUTO
+        );
+        assert_eq!(policy.UpgradeReplicaSetCheckTimeoutInSeconds, u32::MAX);
+        assert!(ex1.HealthPolicy.is_null());
+        assert!(!ex2.EnableDeltaHealthEvaluation.as_bool());
+        assert!(ex2.UpgradeHealthPolicy.is_null());
+        assert!(ex3.ApplicationHealthPolicyMap.is_null());
+    }
+
+    #[test]
+    fn configuration_upgrade_description_fields() {
+        let mut arena = Arena::new();
+        let description = ClusterConfigurationUpgradeDescription::new("{}")
+            .health_check_wait(Duration::from_secs(30))
+            .health_check_stable(Duration::from_secs(60))
+            .health_check_retry_timeout(Duration::from_secs(90))
+            .upgrade_domain_timeout(Duration::from_secs(600))
+            .max_percent_unhealthy_applications(5)
+            .max_percent_unhealthy_nodes(10)
+            .upgrade_health_policy(ClusterUpgradeHealthPolicy {
+                max_percent_delta_unhealthy_nodes: 15,
+                max_percent_upgrade_domain_delta_unhealthy_nodes: 25,
+            });
+
+        let raw = description.to_raw(&mut arena);
+        assert_eq!(string(raw.ClusterConfig), "{}");
+        assert_eq!(raw.HealthCheckWaitDurationInSeconds, 30);
+        assert_eq!(raw.HealthCheckStableDurationInSeconds, 60);
+        assert_eq!(raw.HealthCheckRetryTimeoutInSeconds, 90);
+        assert_eq!(raw.UpgradeDomainTimeoutInSeconds, 600);
+        assert_eq!(raw.MaxPercentUnhealthyApplications, 5);
+        assert_eq!(raw.MaxPercentUnhealthyNodes, 10);
+        assert_eq!(raw.MaxPercentDeltaUnhealthyNodes, 15);
+        assert_eq!(raw.MaxPercentUpgradeDomainDeltaUnhealthyNodes, 25);
+        let ex1 = unsafe { &*(raw.Reserved as *const FABRIC_START_UPGRADE_DESCRIPTION_EX1) };
+        assert!(ex1.ApplicationHealthPolicyMap.is_null());
+
+        let raw = description
+            .application_health_policy("fabric:/app", ApplicationHealthPolicy::new())
+            .to_raw(&mut arena);
+        let ex1 = unsafe { &*(raw.Reserved as *const FABRIC_START_UPGRADE_DESCRIPTION_EX1) };
+        let map = unsafe { &*ex1.ApplicationHealthPolicyMap };
+        assert_eq!(map.Count, 1);
+    }
+
+    #[test]
+    fn partition_load_description_lists() {
+        let mut arena = Arena::new();
+        let partition_id = GUID::from_u128(0x42);
+        let partitions = [PartitionMetricLoad {
+            partition_id,
+            primary_replica_load: vec![MetricLoad {
+                metric_name: "Memory".to_string(),
+                current_load: Some(10),
+                predicted_load: None,
+            }],
+            secondary_replicas_or_instances_load: vec![],
+            secondary_replica_or_instance_load_per_node: vec![ReplicaMetricLoad {
+                node_name: "_Node_1".to_string(),
+                load: vec![MetricLoad {
+                    metric_name: "Cpu".to_string(),
+                    current_load: None,
+                    predicted_load: Some(7),
+                }],
+            }],
+        }];
+
+        let raw = partition_load_description(&mut arena, &partitions);
+        assert!(raw.ContinuationToken.is_null());
+        assert_eq!(raw.MaxResults, 0);
+        let list = unsafe { &*raw.PartitionMetricLoadDescriptionList };
+        let items = unsafe { list_items(list.Items, list.Count) };
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].PartitionId, partition_id);
+
+        let primary = unsafe { &*items[0].PrimaryReplicaLoadEntries };
+        let primary = unsafe { list_items(primary.Items, primary.Count) };
+        assert_eq!(primary.len(), 1);
+        assert_eq!(string(primary[0].MetricName), "Memory");
+        assert_eq!(primary[0].CurrentLoad, 10);
+        assert!(primary[0].IsCurrentLoadSpecified.as_bool());
+        assert!(!primary[0].IsPredictedLoadSpecified.as_bool());
+
+        let secondary = unsafe { &*items[0].SecondaryReplicasOrInstancesLoadEntries };
+        assert_eq!(secondary.Count, 0);
+        assert!(secondary.Items.is_null());
+
+        let per_node = unsafe { &*items[0].SecondaryReplicaOrInstanceLoadEntriesPerNode };
+        let per_node = unsafe { list_items(per_node.Items, per_node.Count) };
+        assert_eq!(per_node.len(), 1);
+        assert_eq!(string(per_node[0].NodeName), "_Node_1");
+        let load = unsafe { &*per_node[0].ReplicaOrInstanceLoadEntries };
+        let load = unsafe { list_items(load.Items, load.Count) };
+        assert_eq!(string(load[0].MetricName), "Cpu");
+        assert!(!load[0].IsCurrentLoadSpecified.as_bool());
+        assert_eq!(load[0].PredictedLoad, 7);
+        assert!(load[0].IsPredictedLoadSpecified.as_bool());
+    }
+
+    #[test]
+    fn partition_load_results_from_raw_list() {
+        let mut arena = Arena::new();
+        let failed = HRESULT(FabricErrorCode::PartitionNotFound as i32);
+        let items = arena.slice(vec![
+            FABRIC_UPDATE_PARTITION_LOAD_QUERY_RESULT_ITEM {
+                PartitionId: GUID::from_u128(1),
+                ErrorCode: HRESULT(0),
+                Reserved: ptr::null_mut(),
+            },
+            FABRIC_UPDATE_PARTITION_LOAD_QUERY_RESULT_ITEM {
+                PartitionId: GUID::from_u128(2),
+                ErrorCode: failed,
+                Reserved: ptr::null_mut(),
+            },
+        ]);
+        let list = FABRIC_UPDATE_PARTITION_LOAD_QUERY_RESULT_LIST {
+            Count: 2,
+            Items: items,
+        };
+
+        let results = unsafe { partition_load_results(&list) }.unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].partition_id, GUID::from_u128(1));
+        assert!(results[0].is_success());
+        assert_eq!(results[1].partition_id, GUID::from_u128(2));
+        assert_eq!(results[1].error_code, failed);
+        assert!(!results[1].is_success());
+
+        let empty = FABRIC_UPDATE_PARTITION_LOAD_QUERY_RESULT_LIST {
+            Count: 0,
+            Items: ptr::null_mut(),
+        };
+        assert!(unsafe { partition_load_results(&empty) }
+            .unwrap()
+            .is_empty());
+
+        assert!(matches!(
+            unsafe { partition_load_results(ptr::null()) },
+            Err(Error::NullPointer("UpdatePartitionLoad"))
+        ));
+    }
+}
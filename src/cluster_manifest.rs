@@ -0,0 +1,569 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use crate::{
+    error::Error,
+    xml::{self, Element},
+};
+
+/// A typed view of the cluster manifest returned by `get_cluster_manifest`.
+/// Only the parts services commonly read are kept: node types, the
+/// infrastructure section and fabric settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClusterManifest {
+    pub name: String,
+    pub version: String,
+    pub description: Option<String>,
+    pub node_types: Vec<NodeType>,
+    pub infrastructure: Infrastructure,
+    pub fabric_settings: Vec<SettingsSection>,
+}
+
+impl ClusterManifest {
+    pub fn parse(xml: &str) -> Result<Self, Error> {
+        let root = xml::parse(xml, Error::InvalidClusterManifest)?;
+        if root.name != "ClusterManifest" {
+            return Err(invalid(format!(
+                "expected a ClusterManifest root element, found '{}'",
+                root.name
+            )));
+        }
+
+        let node_types = root.child("NodeTypes").map_or(Ok(vec![]), |node_types| {
+            node_types
+                .children("NodeType")
+                .map(NodeType::parse)
+                .collect()
+        })?;
+        let infrastructure = root
+            .child("Infrastructure")
+            .ok_or_else(|| invalid("missing the Infrastructure element".to_string()))
+            .and_then(Infrastructure::parse)?;
+        let fabric_settings = root
+            .child("FabricSettings")
+            .map_or(Ok(vec![]), |settings| {
+                settings
+                    .children("Section")
+                    .map(SettingsSection::parse)
+                    .collect()
+            })?;
+
+        Ok(Self {
+            name: required(&root, "Name")?,
+            version: required(&root, "Version")?,
+            description: root.attribute("Description").map(str::to_string),
+            node_types,
+            infrastructure,
+            fabric_settings,
+        })
+    }
+
+    pub fn node_type(&self, name: &str) -> Option<&NodeType> {
+        self.node_types
+            .iter()
+            .find(|node_type| node_type.name == name)
+    }
+
+    pub fn section(&self, name: &str) -> Option<&SettingsSection> {
+        self.fabric_settings
+            .iter()
+            .find(|section| section.name == name)
+    }
+
+    /// Looks up a fabric setting by section and parameter name.
+    pub fn parameter(&self, section: &str, name: &str) -> Option<&str> {
+        self.section(section)
+            .and_then(|section| section.parameter(name))
+            .map(|parameter| parameter.value.as_str())
+    }
+}
+
+impl FromStr for ClusterManifest {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeType {
+    pub name: String,
+    pub endpoints: NodeTypeEndpoints,
+    pub placement_properties: BTreeMap<String, String>,
+    pub capacities: BTreeMap<String, u64>,
+}
+
+impl NodeType {
+    fn parse(element: &Element) -> Result<Self, Error> {
+        let placement_properties =
+            element
+                .child("PlacementProperties")
+                .map_or(Ok(BTreeMap::new()), |properties| {
+                    properties
+                        .children("Property")
+                        .map(|property| {
+                            Ok((required(property, "Name")?, required(property, "Value")?))
+                        })
+                        .collect::<Result<_, Error>>()
+                })?;
+        let capacities = element
+            .child("Capacities")
+            .map_or(Ok(BTreeMap::new()), |capacities| {
+                capacities
+                    .children("Capacity")
+                    .map(|capacity| Ok((required(capacity, "Name")?, number(capacity, "Value")?)))
+                    .collect::<Result<_, Error>>()
+            })?;
+
+        Ok(Self {
+            name: required(element, "Name")?,
+            endpoints: element
+                .child("Endpoints")
+                .map_or(Ok(NodeTypeEndpoints::default()), NodeTypeEndpoints::parse)?,
+            placement_properties,
+            capacities,
+        })
+    }
+}
+
+/// The ports nodes of a node type listen on. Endpoints the manifest leaves
+/// out are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NodeTypeEndpoints {
+    pub client_connection: Option<Endpoint>,
+    pub lease_driver: Option<Endpoint>,
+    pub cluster_connection: Option<Endpoint>,
+    pub service_connection: Option<Endpoint>,
+    pub http_gateway: Option<Endpoint>,
+    pub http_application_gateway: Option<Endpoint>,
+    pub default_replicator: Option<Endpoint>,
+    pub application_ports: Option<PortRange>,
+    pub ephemeral_ports: Option<PortRange>,
+}
+
+impl NodeTypeEndpoints {
+    fn parse(element: &Element) -> Result<Self, Error> {
+        let endpoint = |name| element.child(name).map(Endpoint::parse).transpose();
+        let port_range = |name| element.child(name).map(PortRange::parse).transpose();
+
+        Ok(Self {
+            client_connection: endpoint("ClientConnectionEndpoint")?,
+            lease_driver: endpoint("LeaseDriverEndpoint")?,
+            cluster_connection: endpoint("ClusterConnectionEndpoint")?,
+            service_connection: endpoint("ServiceConnectionEndpoint")?,
+            http_gateway: endpoint("HttpGatewayEndpoint")?,
+            http_application_gateway: endpoint("HttpApplicationGatewayEndpoint")?,
+            default_replicator: endpoint("DefaultReplicatorEndpoint")?,
+            application_ports: port_range("ApplicationEndpoints")?,
+            ephemeral_ports: port_range("EphemeralEndpoints")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoint {
+    pub port: u16,
+    /// Only set for endpoints that speak more than one protocol, such as the
+    /// HTTP gateways.
+    pub protocol: Option<String>,
+}
+
+impl Endpoint {
+    fn parse(element: &Element) -> Result<Self, Error> {
+        Ok(Self {
+            port: number(element, "Port")?,
+            protocol: element.attribute("Protocol").map(str::to_string),
+        })
+    }
+}
+
+/// An inclusive range of ports.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PortRange {
+    pub start: u16,
+    pub end: u16,
+}
+
+impl PortRange {
+    fn parse(element: &Element) -> Result<Self, Error> {
+        Ok(Self {
+            start: number(element, "StartPort")?,
+            end: number(element, "EndPort")?,
+        })
+    }
+}
+
+/// The environment the cluster runs in. Azure hosted infrastructures are
+/// reported as `Other` with the element name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Infrastructure {
+    WindowsServer {
+        is_scale_min: bool,
+        nodes: Vec<InfrastructureNode>,
+    },
+    Linux {
+        is_scale_min: bool,
+        nodes: Vec<InfrastructureNode>,
+    },
+    PaaS {
+        roles: Vec<PaaSRole>,
+        votes: Vec<PaaSVote>,
+    },
+    Other(String),
+}
+
+impl Infrastructure {
+    fn parse(element: &Element) -> Result<Self, Error> {
+        let kind = element
+            .children
+            .first()
+            .ok_or_else(|| invalid("the Infrastructure element is empty".to_string()))?;
+        let nodes = || -> Result<Vec<InfrastructureNode>, Error> {
+            kind.child("NodeList").map_or(Ok(vec![]), |nodes| {
+                nodes
+                    .children("Node")
+                    .map(InfrastructureNode::parse)
+                    .collect()
+            })
+        };
+
+        Ok(match kind.name.as_str() {
+            "WindowsServer" => Infrastructure::WindowsServer {
+                is_scale_min: flag(kind, "IsScaleMin")?,
+                nodes: nodes()?,
+            },
+            "Linux" => Infrastructure::Linux {
+                is_scale_min: flag(kind, "IsScaleMin")?,
+                nodes: nodes()?,
+            },
+            "PaaS" => Infrastructure::PaaS {
+                roles: kind.child("Roles").map_or(Ok(vec![]), |roles| {
+                    roles.children("Role").map(PaaSRole::parse).collect()
+                })?,
+                votes: kind.child("Votes").map_or(Ok(vec![]), |votes| {
+                    votes.children("Vote").map(PaaSVote::parse).collect()
+                })?,
+            },
+            other => Infrastructure::Other(other.to_string()),
+        })
+    }
+
+    /// The statically configured nodes, empty for infrastructures that don't
+    /// list them.
+    pub fn nodes(&self) -> &[InfrastructureNode] {
+        match self {
+            Infrastructure::WindowsServer { nodes, .. } | Infrastructure::Linux { nodes, .. } => {
+                nodes
+            }
+            _ => &[],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InfrastructureNode {
+    pub node_name: String,
+    pub ip_address_or_fqdn: String,
+    pub is_seed_node: bool,
+    pub node_type: String,
+    pub fault_domain: Option<String>,
+    pub upgrade_domain: Option<String>,
+}
+
+impl InfrastructureNode {
+    fn parse(element: &Element) -> Result<Self, Error> {
+        Ok(Self {
+            node_name: required(element, "NodeName")?,
+            ip_address_or_fqdn: required(element, "IPAddressOrFQDN")?,
+            is_seed_node: flag(element, "IsSeedNode")?,
+            node_type: required(element, "NodeTypeRef")?,
+            fault_domain: element.attribute("FaultDomain").map(str::to_string),
+            upgrade_domain: element.attribute("UpgradeDomain").map(str::to_string),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaaSRole {
+    pub role_name: String,
+    pub node_type: String,
+    pub node_count: u32,
+}
+
+impl PaaSRole {
+    fn parse(element: &Element) -> Result<Self, Error> {
+        Ok(Self {
+            role_name: required(element, "RoleName")?,
+            node_type: required(element, "NodeTypeRef")?,
+            node_count: number(element, "RoleNodeCount")?,
+        })
+    }
+}
+
+/// A seed node of a PaaS cluster.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaaSVote {
+    pub node_name: String,
+    pub ip_address_or_fqdn: String,
+    pub port: u16,
+}
+
+impl PaaSVote {
+    fn parse(element: &Element) -> Result<Self, Error> {
+        Ok(Self {
+            node_name: required(element, "NodeName")?,
+            ip_address_or_fqdn: required(element, "IPAddressOrFQDN")?,
+            port: number(element, "Port")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettingsSection {
+    pub name: String,
+    pub parameters: Vec<SettingsParameter>,
+}
+
+impl SettingsSection {
+    fn parse(element: &Element) -> Result<Self, Error> {
+        Ok(Self {
+            name: required(element, "Name")?,
+            parameters: element
+                .children("Parameter")
+                .map(SettingsParameter::parse)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    pub fn parameter(&self, name: &str) -> Option<&SettingsParameter> {
+        self.parameters
+            .iter()
+            .find(|parameter| parameter.name == name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettingsParameter {
+    pub name: String,
+    /// The value as written in the manifest. Encrypted values are left
+    /// encrypted.
+    pub value: String,
+    pub is_encrypted: bool,
+}
+
+impl SettingsParameter {
+    fn parse(element: &Element) -> Result<Self, Error> {
+        Ok(Self {
+            name: required(element, "Name")?,
+            value: required(element, "Value")?,
+            is_encrypted: flag(element, "IsEncrypted")?,
+        })
+    }
+}
+
+fn invalid(message: String) -> Error {
+    Error::InvalidClusterManifest(message)
+}
+
+fn required(element: &Element, name: &str) -> Result<String, Error> {
+    element.attribute(name).map(str::to_string).ok_or_else(|| {
+        invalid(format!(
+            "{} is missing the {} attribute",
+            element.name, name
+        ))
+    })
+}
+
+fn number<T: FromStr>(element: &Element, name: &str) -> Result<T, Error> {
+    let value = required(element, name)?;
+    value.parse().map_err(|_| {
+        invalid(format!(
+            "{}.{} isn't a valid number: '{}'",
+            element.name, name, value
+        ))
+    })
+}
+
+/// Reads an optional boolean attribute, which defaults to false.
+fn flag(element: &Element, name: &str) -> Result<bool, Error> {
+    match element.attribute(name) {
+        None => Ok(false),
+        Some(value) if value.eq_ignore_ascii_case("true") => Ok(true),
+        Some(value) if value.eq_ignore_ascii_case("false") => Ok(false),
+        Some(value) => Err(invalid(format!(
+            "{}.{} isn't a valid boolean: '{}'",
+            element.name, name, value
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEV_CLUSTER: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<!-- Generated by the local cluster setup -->
+<ClusterManifest xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" Name="DevClusterConfig" Version="1.0" Description="Dev &amp; test cluster" xmlns="http://schemas.microsoft.com/2011/01/fabric">
+  <NodeTypes>
+    <NodeType Name="NodeType0">
+      <Endpoints>
+        <ClientConnectionEndpoint Port="19000" />
+        <LeaseDriverEndpoint Port="19001" />
+        <ClusterConnectionEndpoint Port="19002" />
+        <HttpGatewayEndpoint Port="19080" Protocol="http" />
+        <ServiceConnectionEndpoint Port="19006" />
+        <ApplicationEndpoints StartPort="30001" EndPort="31000" />
+        <EphemeralEndpoints StartPort="49152" EndPort="65534" />
+      </Endpoints>
+      <PlacementProperties>
+        <Property Name="HasSSD" Value="true" />
+      </PlacementProperties>
+      <Capacities>
+        <Capacity Name="MemoryMB" Value="16384" />
+      </Capacities>
+    </NodeType>
+  </NodeTypes>
+  <Infrastructure>
+    <WindowsServer IsScaleMin="true">
+      <NodeList>
+        <Node NodeName="_Node_0" IPAddressOrFQDN="localhost" IsSeedNode="true" NodeTypeRef="NodeType0" FaultDomain="fd:/0" UpgradeDomain="0" />
+        <Node NodeName="_Node_1" IPAddressOrFQDN="localhost" NodeTypeRef="NodeType0" FaultDomain="fd:/1" UpgradeDomain="1" />
+      </NodeList>
+    </WindowsServer>
+  </Infrastructure>
+  <FabricSettings>
+    <Section Name="Security">
+      <Parameter Name="ClusterCredentialType" Value="None" />
+      <Parameter Name="ServerAuthCredentialType" Value="None" />
+    </Section>
+    <Section Name="FailoverManager">
+      <!-- Keep a single replica set in a dev cluster -->
+      <Parameter Name="TargetReplicaSetSize" Value="1" />
+      <Parameter Name="Secret" Value="MIIB&#x2F;wYJ" IsEncrypted="true" />
+    </Section>
+  </FabricSettings>
+</ClusterManifest>
+"#;
+
+    const PAAS_CLUSTER: &str = r#"<ClusterManifest Name="Azure" Version="2.1">
+  <NodeTypes>
+    <NodeType Name="FrontEnd" />
+  </NodeTypes>
+  <Infrastructure>
+    <PaaS>
+      <Roles>
+        <Role RoleName="FrontEnd" NodeTypeRef="FrontEnd" RoleNodeCount="5" />
+      </Roles>
+      <Votes>
+        <Vote NodeName="_FrontEnd_0" IPAddressOrFQDN="10.0.0.4" Port="1025" />
+      </Votes>
+    </PaaS>
+  </Infrastructure>
+</ClusterManifest>"#;
+
+    #[test]
+    fn parses_dev_cluster_manifest() {
+        let manifest = ClusterManifest::parse(DEV_CLUSTER).unwrap();
+
+        assert_eq!(manifest.name, "DevClusterConfig");
+        assert_eq!(manifest.version, "1.0");
+        assert_eq!(manifest.description.as_deref(), Some("Dev & test cluster"));
+
+        let node_type = manifest.node_type("NodeType0").unwrap();
+        assert_eq!(
+            node_type.endpoints.http_gateway,
+            Some(Endpoint {
+                port: 19080,
+                protocol: Some("http".to_string()),
+            })
+        );
+        assert_eq!(node_type.endpoints.http_application_gateway, None);
+        assert_eq!(
+            node_type.endpoints.application_ports,
+            Some(PortRange {
+                start: 30001,
+                end: 31000,
+            })
+        );
+        assert_eq!(node_type.placement_properties["HasSSD"], "true");
+        assert_eq!(node_type.capacities["MemoryMB"], 16384);
+
+        let nodes = manifest.infrastructure.nodes();
+        assert!(matches!(
+            manifest.infrastructure,
+            Infrastructure::WindowsServer {
+                is_scale_min: true,
+                ..
+            }
+        ));
+        assert_eq!(nodes.len(), 2);
+        assert!(nodes[0].is_seed_node);
+        assert!(!nodes[1].is_seed_node);
+        assert_eq!(nodes[1].fault_domain.as_deref(), Some("fd:/1"));
+
+        assert_eq!(
+            manifest.parameter("FailoverManager", "TargetReplicaSetSize"),
+            Some("1")
+        );
+        let secret = manifest
+            .section("FailoverManager")
+            .and_then(|section| section.parameter("Secret"))
+            .unwrap();
+        assert_eq!(secret.value, "MIIB/wYJ");
+        assert!(secret.is_encrypted);
+        assert_eq!(manifest.parameter("Security", "Missing"), None);
+    }
+
+    #[test]
+    fn parses_paas_cluster_manifest() {
+        let manifest: ClusterManifest = PAAS_CLUSTER.parse().unwrap();
+
+        assert_eq!(
+            manifest.node_types[0].endpoints,
+            NodeTypeEndpoints::default()
+        );
+        assert!(manifest.fabric_settings.is_empty());
+        assert!(manifest.infrastructure.nodes().is_empty());
+        assert_eq!(
+            manifest.infrastructure,
+            Infrastructure::PaaS {
+                roles: vec![PaaSRole {
+                    role_name: "FrontEnd".to_string(),
+                    node_type: "FrontEnd".to_string(),
+                    node_count: 5,
+                }],
+                votes: vec![PaaSVote {
+                    node_name: "_FrontEnd_0".to_string(),
+                    ip_address_or_fqdn: "10.0.0.4".to_string(),
+                    port: 1025,
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_manifests() {
+        let invalid = [
+            "",
+            "<ClusterManifest Name=\"A\" Version=\"1\">",
+            "<ClusterManifest Name=\"A\" Version=\"1\"></NodeTypes>",
+            "<ApplicationManifest />",
+            "<ClusterManifest Version=\"1\"><Infrastructure><Linux /></Infrastructure></ClusterManifest>",
+            "<ClusterManifest Name=\"A\" Version=\"1\" />",
+            "<ClusterManifest Name=\"A &bogus; \" Version=\"1\" />",
+        ];
+
+        for xml in invalid {
+            assert!(
+                matches!(
+                    ClusterManifest::parse(xml),
+                    Err(Error::InvalidClusterManifest(_))
+                ),
+                "{xml}"
+            );
+        }
+
+        let bad_port = DEV_CLUSTER.replace("Port=\"19000\"", "Port=\"70000\"");
+        assert!(ClusterManifest::parse(&bad_port).is_err());
+    }
+}
@@ -3,6 +3,7 @@
 use std::string::FromUtf16Error;
 
 use num_derive::{FromPrimitive, ToPrimitive};
+use num_traits::FromPrimitive as _;
 use thiserror::Error as ThisError;
 use windows::core::Error as WindowsError;
 
@@ -17,11 +18,54 @@ pub enum Error {
     #[error("Call abandoned: {0}")]
     Abandoned(&'static str),
 
+    #[error("Unexpected null pointer in {0}")]
+    NullPointer(&'static str),
+
+    #[error("No tokio runtime to run {0}")]
+    NoTokioRuntime(&'static str),
+
     #[error("Invalid service kind")]
     InvalidServiceKind,
 
     #[error("Invalid service partition kind")]
     InvalidServicePartitionKind,
+
+    #[error("Invalid service load metric weight")]
+    InvalidServiceLoadMetricWeight,
+
+    #[error("Invalid service correlation scheme")]
+    InvalidServiceCorrelationScheme,
+
+    #[error("Invalid scaling policy: {0}")]
+    InvalidScalingPolicy(&'static str),
+
+    #[error("Invalid health policy: {0}")]
+    InvalidHealthPolicy(&'static str),
+
+    #[error("Invalid application capacity: {0}")]
+    InvalidApplicationCapacity(&'static str),
+
+    #[error("Timed out waiting for {0}")]
+    TimedOut(&'static str),
+
+    #[error("Invalid cluster manifest: {0}")]
+    InvalidClusterManifest(String),
+
+    #[error("Invalid health report: {0}")]
+    InvalidHealthReport(&'static str),
+
+    #[error("Invalid property type")]
+    InvalidPropertyType,
+}
+
+impl Error {
+    /// Returns the SF error code carried by this error, if it came from SF.
+    pub fn fabric_error_code(&self) -> Option<FabricErrorCode> {
+        match self {
+            Error::Windows(e) => FabricErrorCode::from_u32(e.code().0 as u32),
+            _ => None,
+        }
+    }
 }
 
 #[repr(u32)]
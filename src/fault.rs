@@ -0,0 +1,513 @@
+use std::{ffi::c_void, ptr};
+
+use tokio::sync::mpsc;
+use windows::core::{ComInterface, GUID};
+
+use crate::{
+    agile::AgileRef,
+    arena::Arena,
+    callback::begin_async,
+    error::{Error, FabricErrorCode},
+    run_with_retry, wide_to_string, IFabricFaultManagementClient3, MakeClient,
+    FABRIC_MOVE_AUXILIARY_DESCRIPTION, FABRIC_MOVE_AUXILIARY_DESCRIPTION_KIND_USING_NODE_NAME,
+    FABRIC_MOVE_AUXILIARY_DESCRIPTION_USING_NODE_NAME, FABRIC_MOVE_INSTANCE_DESCRIPTION,
+    FABRIC_MOVE_INSTANCE_DESCRIPTION_KIND_USING_NODE_NAME,
+    FABRIC_MOVE_INSTANCE_DESCRIPTION_USING_NODE_NAME, FABRIC_MOVE_PRIMARY_DESCRIPTION2,
+    FABRIC_MOVE_PRIMARY_DESCRIPTION_KIND_USING_NODE_NAME,
+    FABRIC_MOVE_PRIMARY_DESCRIPTION_USING_NODE_NAME, FABRIC_MOVE_SECONDARY_DESCRIPTION2,
+    FABRIC_MOVE_SECONDARY_DESCRIPTION_KIND_USING_NODE_NAME,
+    FABRIC_MOVE_SECONDARY_DESCRIPTION_USING_NODE_NAME,
+};
+
+/// The outcome of a move request. SF refuses to move a replica onto a node
+/// that already hosts it in the requested role; that is reported as one of
+/// the `Already*` variants rather than as an error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoveOutcome {
+    Moved(MovedReplica),
+    AlreadyPrimary,
+    AlreadySecondary,
+    AlreadyInstance,
+    AlreadyAuxiliary,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MovedReplica {
+    pub service_name: String,
+    pub partition_id: GUID,
+    /// Not reported for primary moves.
+    pub current_node_name: Option<String>,
+    pub new_node_name: String,
+}
+
+/// Identifies the replica or instance to move. When `current_node_name` is
+/// `None` SF picks one, and when `new_node_name` is `None` SF picks the target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveDescription {
+    pub service_name: String,
+    pub partition_id: GUID,
+    pub current_node_name: Option<String>,
+    pub new_node_name: Option<String>,
+    pub ignore_constraints: bool,
+}
+
+impl MoveDescription {
+    pub fn new(service_name: &str, partition_id: GUID) -> Self {
+        Self {
+            service_name: service_name.to_string(),
+            partition_id,
+            current_node_name: None,
+            new_node_name: None,
+            ignore_constraints: false,
+        }
+    }
+
+    pub fn current_node_name(mut self, current_node_name: &str) -> Self {
+        self.current_node_name = Some(current_node_name.to_string());
+        self
+    }
+
+    pub fn new_node_name(mut self, new_node_name: &str) -> Self {
+        self.new_node_name = Some(new_node_name.to_string());
+        self
+    }
+
+    pub fn ignore_constraints(mut self, ignore_constraints: bool) -> Self {
+        self.ignore_constraints = ignore_constraints;
+        self
+    }
+
+    fn primary_to_raw(&self, arena: &mut Arena) -> FABRIC_MOVE_PRIMARY_DESCRIPTION2 {
+        let value = FABRIC_MOVE_PRIMARY_DESCRIPTION_USING_NODE_NAME {
+            NodeName: arena.optional_wide(self.new_node_name.as_deref()),
+            ServiceName: arena.uri(&self.service_name),
+            PartitionId: self.partition_id,
+            IgnoreConstraints: self.ignore_constraints.into(),
+            Reserved: ptr::null_mut(),
+        };
+
+        FABRIC_MOVE_PRIMARY_DESCRIPTION2 {
+            Kind: FABRIC_MOVE_PRIMARY_DESCRIPTION_KIND_USING_NODE_NAME,
+            Value: arena.alloc(value) as *mut c_void,
+        }
+    }
+
+    fn secondary_to_raw(&self, arena: &mut Arena) -> FABRIC_MOVE_SECONDARY_DESCRIPTION2 {
+        let value = FABRIC_MOVE_SECONDARY_DESCRIPTION_USING_NODE_NAME {
+            CurrentNodeName: arena.optional_wide(self.current_node_name.as_deref()),
+            NewNodeName: arena.optional_wide(self.new_node_name.as_deref()),
+            ServiceName: arena.uri(&self.service_name),
+            PartitionId: self.partition_id,
+            IgnoreConstraints: self.ignore_constraints.into(),
+            Reserved: ptr::null_mut(),
+        };
+
+        FABRIC_MOVE_SECONDARY_DESCRIPTION2 {
+            Kind: FABRIC_MOVE_SECONDARY_DESCRIPTION_KIND_USING_NODE_NAME,
+            Value: arena.alloc(value) as *mut c_void,
+        }
+    }
+
+    fn instance_to_raw(&self, arena: &mut Arena) -> FABRIC_MOVE_INSTANCE_DESCRIPTION {
+        let value = FABRIC_MOVE_INSTANCE_DESCRIPTION_USING_NODE_NAME {
+            CurrentNodeName: arena.optional_wide(self.current_node_name.as_deref()),
+            NewNodeName: arena.optional_wide(self.new_node_name.as_deref()),
+            ServiceName: arena.uri(&self.service_name),
+            PartitionId: self.partition_id,
+            IgnoreConstraints: self.ignore_constraints.into(),
+            Reserved: ptr::null_mut(),
+        };
+
+        FABRIC_MOVE_INSTANCE_DESCRIPTION {
+            Kind: FABRIC_MOVE_INSTANCE_DESCRIPTION_KIND_USING_NODE_NAME,
+            Value: arena.alloc(value) as *mut c_void,
+        }
+    }
+
+    fn auxiliary_to_raw(&self, arena: &mut Arena) -> FABRIC_MOVE_AUXILIARY_DESCRIPTION {
+        let value = FABRIC_MOVE_AUXILIARY_DESCRIPTION_USING_NODE_NAME {
+            CurrentNodeName: arena.optional_wide(self.current_node_name.as_deref()),
+            NewNodeName: arena.optional_wide(self.new_node_name.as_deref()),
+            ServiceName: arena.uri(&self.service_name),
+            PartitionId: self.partition_id,
+            IgnoreConstraints: self.ignore_constraints.into(),
+            Reserved: ptr::null_mut(),
+        };
+
+        FABRIC_MOVE_AUXILIARY_DESCRIPTION {
+            Kind: FABRIC_MOVE_AUXILIARY_DESCRIPTION_KIND_USING_NODE_NAME,
+            Value: arena.alloc(value) as *mut c_void,
+        }
+    }
+}
+
+/// Moves replicas and instances between nodes. This is a client of its own
+/// rather than part of `ServiceManagementClient` because the move operations
+/// are declared on `IFabricFaultManagementClient3`, not on the service
+/// management interfaces.
+#[derive(Debug, Clone)]
+pub struct FaultManagementClient {
+    client: AgileRef<IFabricFaultManagementClient3>,
+}
+
+impl MakeClient for FaultManagementClient {
+    type Interface = IFabricFaultManagementClient3;
+
+    fn make(client: Self::Interface) -> Result<Self, Error> {
+        Ok(Self {
+            client: AgileRef::new(client.cast()?)?,
+        })
+    }
+}
+
+impl FaultManagementClient {
+    pub fn new(client: IFabricFaultManagementClient3) -> Result<Self, Error> {
+        Ok(Self {
+            client: AgileRef::new(client.cast()?)?,
+        })
+    }
+
+    /// Moves the primary of a partition to `new_node_name`. The current node
+    /// name is ignored since a partition only has one primary.
+    pub async fn move_primary(
+        &self,
+        description: &MoveDescription,
+        timeout_ms: u32,
+    ) -> Result<MoveOutcome, Error> {
+        let res = run_with_retry("move_primary", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx = try_move_primary(client.resolve()?, description, timeout_ms)?;
+                rx.recv().await.ok_or(Error::Abandoned("MovePrimary"))?
+            }
+        })
+        .await;
+
+        move_outcome(
+            res,
+            FabricErrorCode::AlreadyPrimaryReplica,
+            MoveOutcome::AlreadyPrimary,
+        )
+    }
+
+    pub async fn move_secondary(
+        &self,
+        description: &MoveDescription,
+        timeout_ms: u32,
+    ) -> Result<MoveOutcome, Error> {
+        let res = run_with_retry("move_secondary", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx = try_move_secondary(client.resolve()?, description, timeout_ms)?;
+                rx.recv().await.ok_or(Error::Abandoned("MoveSecondary"))?
+            }
+        })
+        .await;
+
+        move_outcome(
+            res,
+            FabricErrorCode::AlreadySecondaryReplica,
+            MoveOutcome::AlreadySecondary,
+        )
+    }
+
+    /// Moves an instance of a stateless service.
+    pub async fn move_instance(
+        &self,
+        description: &MoveDescription,
+        timeout_ms: u32,
+    ) -> Result<MoveOutcome, Error> {
+        let res = run_with_retry("move_instance", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx = try_move_instance(client.resolve()?, description, timeout_ms)?;
+                rx.recv().await.ok_or(Error::Abandoned("MoveInstance"))?
+            }
+        })
+        .await;
+
+        move_outcome(
+            res,
+            FabricErrorCode::AlreadyInstance,
+            MoveOutcome::AlreadyInstance,
+        )
+    }
+
+    pub async fn move_auxiliary(
+        &self,
+        description: &MoveDescription,
+        timeout_ms: u32,
+    ) -> Result<MoveOutcome, Error> {
+        let res = run_with_retry("move_auxiliary", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx = try_move_auxiliary(client.resolve()?, description, timeout_ms)?;
+                rx.recv().await.ok_or(Error::Abandoned("MoveAuxiliary"))?
+            }
+        })
+        .await;
+
+        move_outcome(
+            res,
+            FabricErrorCode::AlreadyAuxiliaryReplica,
+            MoveOutcome::AlreadyAuxiliary,
+        )
+    }
+}
+
+fn move_outcome(
+    res: Result<MovedReplica, Error>,
+    already: FabricErrorCode,
+    outcome: MoveOutcome,
+) -> Result<MoveOutcome, Error> {
+    match res {
+        Ok(moved) => Ok(MoveOutcome::Moved(moved)),
+        Err(e) if e.fabric_error_code() == Some(already) => Ok(outcome),
+        Err(e) => Err(e),
+    }
+}
+
+fn try_move_primary(
+    client: IFabricFaultManagementClient3,
+    description: &MoveDescription,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<MovedReplica, Error>>, Error> {
+    let mut arena = Arena::new();
+    let description = description.primary_to_raw(&mut arena);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe { client.BeginMovePrimary(&description, timeout_ms, Some(callback)) },
+        move |context| {
+            let res = unsafe { end_client.EndMovePrimary(context) }?;
+            let res = unsafe { &*res.get_Result() };
+            Ok(MovedReplica {
+                service_name: unsafe { wide_to_string(res.ServiceName)? },
+                partition_id: res.PartitionId,
+                current_node_name: None,
+                new_node_name: unsafe { wide_to_string(res.NodeName.0)? },
+            })
+        },
+    )
+}
+
+fn try_move_secondary(
+    client: IFabricFaultManagementClient3,
+    description: &MoveDescription,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<MovedReplica, Error>>, Error> {
+    let mut arena = Arena::new();
+    let description = description.secondary_to_raw(&mut arena);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe { client.BeginMoveSecondary(&description, timeout_ms, Some(callback)) },
+        move |context| {
+            let res = unsafe { end_client.EndMoveSecondary(context) }?;
+            let res = unsafe { &*res.get_Result() };
+            Ok(MovedReplica {
+                service_name: unsafe { wide_to_string(res.ServiceName)? },
+                partition_id: res.PartitionId,
+                current_node_name: Some(unsafe { wide_to_string(res.CurrentNodeName.0)? }),
+                new_node_name: unsafe { wide_to_string(res.NewNodeName.0)? },
+            })
+        },
+    )
+}
+
+fn try_move_instance(
+    client: IFabricFaultManagementClient3,
+    description: &MoveDescription,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<MovedReplica, Error>>, Error> {
+    let mut arena = Arena::new();
+    let description = description.instance_to_raw(&mut arena);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe { client.BeginMoveInstance(&description, timeout_ms, Some(callback)) },
+        move |context| {
+            let res = unsafe { end_client.EndMoveInstance(context) }?;
+            let res = unsafe { &*res.get_Result() };
+            Ok(MovedReplica {
+                service_name: unsafe { wide_to_string(res.ServiceName)? },
+                partition_id: res.PartitionId,
+                current_node_name: Some(unsafe { wide_to_string(res.CurrentNodeName.0)? }),
+                new_node_name: unsafe { wide_to_string(res.NewNodeName.0)? },
+            })
+        },
+    )
+}
+
+fn try_move_auxiliary(
+    client: IFabricFaultManagementClient3,
+    description: &MoveDescription,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<MovedReplica, Error>>, Error> {
+    let mut arena = Arena::new();
+    let description = description.auxiliary_to_raw(&mut arena);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe { client.BeginMoveAuxiliary(&description, timeout_ms, Some(callback)) },
+        move |context| {
+            let res = unsafe { end_client.EndMoveAuxiliary(context) }?;
+            let res = unsafe { &*res.get_Result() };
+            Ok(MovedReplica {
+                service_name: unsafe { wide_to_string(res.ServiceName)? },
+                partition_id: res.PartitionId,
+                current_node_name: Some(unsafe { wide_to_string(res.CurrentNodeName.0)? }),
+                new_node_name: unsafe { wide_to_string(res.NewNodeName.0)? },
+            })
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use windows::core::HRESULT;
+
+    use super::*;
+
+    fn string(value: *const u16) -> String {
+        unsafe { wide_to_string(value) }.unwrap()
+    }
+
+    fn fabric_error(code: FabricErrorCode) -> Error {
+        Error::from(windows::core::Error::from(HRESULT(code as i32)))
+    }
+
+    #[test]
+    fn move_outcome_maps_already_in_place() {
+        let moved = MovedReplica {
+            service_name: "fabric:/app/store".to_string(),
+            partition_id: GUID::from_u128(1),
+            current_node_name: Some("_Node_0".to_string()),
+            new_node_name: "_Node_1".to_string(),
+        };
+
+        for (code, outcome) in [
+            (
+                FabricErrorCode::AlreadyPrimaryReplica,
+                MoveOutcome::AlreadyPrimary,
+            ),
+            (
+                FabricErrorCode::AlreadySecondaryReplica,
+                MoveOutcome::AlreadySecondary,
+            ),
+            (
+                FabricErrorCode::AlreadyInstance,
+                MoveOutcome::AlreadyInstance,
+            ),
+            (
+                FabricErrorCode::AlreadyAuxiliaryReplica,
+                MoveOutcome::AlreadyAuxiliary,
+            ),
+        ] {
+            assert_eq!(
+                move_outcome(Ok(moved.clone()), code, outcome.clone()).unwrap(),
+                MoveOutcome::Moved(moved.clone())
+            );
+            assert_eq!(
+                move_outcome(Err(fabric_error(code)), code, outcome.clone()).unwrap(),
+                outcome
+            );
+
+            let res = move_outcome(
+                Err(fabric_error(FabricErrorCode::PartitionNotFound)),
+                code,
+                outcome,
+            );
+            assert_eq!(
+                res.unwrap_err().fabric_error_code(),
+                Some(FabricErrorCode::PartitionNotFound)
+            );
+        }
+
+        let res = move_outcome(
+            Err(Error::Abandoned("MovePrimary")),
+            FabricErrorCode::AlreadyPrimaryReplica,
+            MoveOutcome::AlreadyPrimary,
+        );
+        assert!(matches!(res, Err(Error::Abandoned("MovePrimary"))));
+    }
+
+    #[test]
+    fn move_primary_sends_only_the_new_node() {
+        let mut arena = Arena::new();
+        let raw = MoveDescription::new("fabric:/app/store", GUID::from_u128(7))
+            .current_node_name("_Node_0")
+            .new_node_name("_Node_1")
+            .ignore_constraints(true)
+            .primary_to_raw(&mut arena);
+
+        assert_eq!(
+            raw.Kind,
+            FABRIC_MOVE_PRIMARY_DESCRIPTION_KIND_USING_NODE_NAME
+        );
+        let value =
+            unsafe { &*(raw.Value as *const FABRIC_MOVE_PRIMARY_DESCRIPTION_USING_NODE_NAME) };
+        assert_eq!(string(value.NodeName.0), "_Node_1");
+        assert_eq!(string(value.ServiceName), "fabric:/app/store");
+        assert_eq!(value.PartitionId, GUID::from_u128(7));
+        assert!(value.IgnoreConstraints.as_bool());
+    }
+
+    #[test]
+    fn move_secondary_instance_and_auxiliary() {
+        let mut arena = Arena::new();
+        let description = MoveDescription::new("fabric:/app/store", GUID::from_u128(7))
+            .current_node_name("_Node_0")
+            .new_node_name("_Node_1");
+
+        let raw = description.secondary_to_raw(&mut arena);
+        assert_eq!(
+            raw.Kind,
+            FABRIC_MOVE_SECONDARY_DESCRIPTION_KIND_USING_NODE_NAME
+        );
+        let value =
+            unsafe { &*(raw.Value as *const FABRIC_MOVE_SECONDARY_DESCRIPTION_USING_NODE_NAME) };
+        assert_eq!(string(value.CurrentNodeName.0), "_Node_0");
+        assert_eq!(string(value.NewNodeName.0), "_Node_1");
+        assert_eq!(string(value.ServiceName), "fabric:/app/store");
+        assert!(!value.IgnoreConstraints.as_bool());
+
+        let raw = description.instance_to_raw(&mut arena);
+        assert_eq!(
+            raw.Kind,
+            FABRIC_MOVE_INSTANCE_DESCRIPTION_KIND_USING_NODE_NAME
+        );
+        let value =
+            unsafe { &*(raw.Value as *const FABRIC_MOVE_INSTANCE_DESCRIPTION_USING_NODE_NAME) };
+        assert_eq!(string(value.CurrentNodeName.0), "_Node_0");
+        assert_eq!(string(value.NewNodeName.0), "_Node_1");
+        assert_eq!(value.PartitionId, GUID::from_u128(7));
+
+        let raw = description.auxiliary_to_raw(&mut arena);
+        assert_eq!(
+            raw.Kind,
+            FABRIC_MOVE_AUXILIARY_DESCRIPTION_KIND_USING_NODE_NAME
+        );
+        let value =
+            unsafe { &*(raw.Value as *const FABRIC_MOVE_AUXILIARY_DESCRIPTION_USING_NODE_NAME) };
+        assert_eq!(string(value.CurrentNodeName.0), "_Node_0");
+        assert_eq!(string(value.NewNodeName.0), "_Node_1");
+    }
+
+    #[test]
+    fn unset_node_names_are_null() {
+        let mut arena = Arena::new();
+        let description = MoveDescription::new("fabric:/app/web", GUID::from_u128(3));
+
+        let raw = description.primary_to_raw(&mut arena);
+        let value =
+            unsafe { &*(raw.Value as *const FABRIC_MOVE_PRIMARY_DESCRIPTION_USING_NODE_NAME) };
+        assert!(value.NodeName.is_null());
+
+        let raw = description.instance_to_raw(&mut arena);
+        let value =
+            unsafe { &*(raw.Value as *const FABRIC_MOVE_INSTANCE_DESCRIPTION_USING_NODE_NAME) };
+        assert!(value.CurrentNodeName.is_null());
+        assert!(value.NewNodeName.is_null());
+    }
+}
@@ -0,0 +1,2278 @@
+use std::{
+    collections::BTreeMap,
+    ffi::c_void,
+    ops::BitOr,
+    ptr,
+    time::{Duration, SystemTime},
+};
+
+use tokio::sync::mpsc;
+use windows::core::{ComInterface, GUID};
+
+use crate::{
+    agile::AgileRef, application_health_policy, application_health_policy_map, arena::Arena,
+    callback::begin_async, cluster_health_policy, error::Error, filetime_to_system_time,
+    list_items, run_with_retry, wide_to_string, ApplicationHealthPolicy, ClusterHealthChunk,
+    ClusterHealthChunkQueryDescription, ClusterHealthPolicy, HealthState, IFabricHealthClient4,
+    MakeClient, ServiceKind, FABRIC_APPLICATIONS_HEALTH_EVALUATION, FABRIC_APPLICATION_HEALTH,
+    FABRIC_APPLICATION_HEALTH_EVALUATION, FABRIC_APPLICATION_HEALTH_EX1,
+    FABRIC_APPLICATION_HEALTH_QUERY_DESCRIPTION, FABRIC_APPLICATION_HEALTH_REPORT,
+    FABRIC_APPLICATION_HEALTH_STATE, FABRIC_APPLICATION_HEALTH_STATES_FILTER,
+    FABRIC_APPLICATION_TYPE_APPLICATIONS_HEALTH_EVALUATION, FABRIC_AUTO_SEQUENCE_NUMBER,
+    FABRIC_CLUSTER_HEALTH, FABRIC_CLUSTER_HEALTH_EX1, FABRIC_CLUSTER_HEALTH_EX2,
+    FABRIC_CLUSTER_HEALTH_QUERY_DESCRIPTION, FABRIC_CLUSTER_HEALTH_REPORT,
+    FABRIC_DELTA_NODES_CHECK_HEALTH_EVALUATION, FABRIC_DEPLOYED_APPLICATIONS_HEALTH_EVALUATION,
+    FABRIC_DEPLOYED_APPLICATION_HEALTH_EVALUATION, FABRIC_DEPLOYED_APPLICATION_HEALTH_REPORT,
+    FABRIC_DEPLOYED_APPLICATION_HEALTH_STATE, FABRIC_DEPLOYED_APPLICATION_HEALTH_STATES_FILTER,
+    FABRIC_DEPLOYED_SERVICE_PACKAGES_HEALTH_EVALUATION,
+    FABRIC_DEPLOYED_SERVICE_PACKAGE_HEALTH_EVALUATION,
+    FABRIC_DEPLOYED_SERVICE_PACKAGE_HEALTH_REPORT, FABRIC_EVENT_HEALTH_EVALUATION,
+    FABRIC_HEALTH_EVALUATION, FABRIC_HEALTH_EVALUATION_KIND_APPLICATION,
+    FABRIC_HEALTH_EVALUATION_KIND_APPLICATIONS,
+    FABRIC_HEALTH_EVALUATION_KIND_APPLICATION_TYPE_APPLICATIONS,
+    FABRIC_HEALTH_EVALUATION_KIND_DELTA_NODES_CHECK,
+    FABRIC_HEALTH_EVALUATION_KIND_DEPLOYED_APPLICATION,
+    FABRIC_HEALTH_EVALUATION_KIND_DEPLOYED_APPLICATIONS,
+    FABRIC_HEALTH_EVALUATION_KIND_DEPLOYED_SERVICE_PACKAGE,
+    FABRIC_HEALTH_EVALUATION_KIND_DEPLOYED_SERVICE_PACKAGES, FABRIC_HEALTH_EVALUATION_KIND_EVENT,
+    FABRIC_HEALTH_EVALUATION_KIND_NODE, FABRIC_HEALTH_EVALUATION_KIND_NODES,
+    FABRIC_HEALTH_EVALUATION_KIND_NODE_TYPE_NODES, FABRIC_HEALTH_EVALUATION_KIND_PARTITION,
+    FABRIC_HEALTH_EVALUATION_KIND_PARTITIONS, FABRIC_HEALTH_EVALUATION_KIND_REPLICA,
+    FABRIC_HEALTH_EVALUATION_KIND_REPLICAS, FABRIC_HEALTH_EVALUATION_KIND_SERVICE,
+    FABRIC_HEALTH_EVALUATION_KIND_SERVICES, FABRIC_HEALTH_EVALUATION_KIND_SYSTEM_APPLICATION,
+    FABRIC_HEALTH_EVALUATION_KIND_UPGRADE_DOMAIN_DELTA_NODES_CHECK,
+    FABRIC_HEALTH_EVALUATION_KIND_UPGRADE_DOMAIN_DEPLOYED_APPLICATIONS,
+    FABRIC_HEALTH_EVALUATION_KIND_UPGRADE_DOMAIN_NODES, FABRIC_HEALTH_EVENT,
+    FABRIC_HEALTH_EVENTS_FILTER, FABRIC_HEALTH_EVENT_EX1, FABRIC_HEALTH_INFORMATION,
+    FABRIC_HEALTH_REPORT, FABRIC_HEALTH_REPORT_INFINITE_TTL, FABRIC_HEALTH_REPORT_KIND_APPLICATION,
+    FABRIC_HEALTH_REPORT_KIND_CLUSTER, FABRIC_HEALTH_REPORT_KIND_DEPLOYED_APPLICATION,
+    FABRIC_HEALTH_REPORT_KIND_DEPLOYED_SERVICE_PACKAGE, FABRIC_HEALTH_REPORT_KIND_NODE,
+    FABRIC_HEALTH_REPORT_KIND_PARTITION, FABRIC_HEALTH_REPORT_KIND_SERVICE,
+    FABRIC_HEALTH_REPORT_KIND_STATEFUL_SERVICE_REPLICA,
+    FABRIC_HEALTH_REPORT_KIND_STATELESS_SERVICE_INSTANCE, FABRIC_HEALTH_REPORT_SEND_OPTIONS,
+    FABRIC_HEALTH_STATE_FILTER_ALL, FABRIC_HEALTH_STATE_FILTER_DEFAULT,
+    FABRIC_HEALTH_STATE_FILTER_ERROR, FABRIC_HEALTH_STATE_FILTER_NONE,
+    FABRIC_HEALTH_STATE_FILTER_OK, FABRIC_HEALTH_STATE_FILTER_WARNING,
+    FABRIC_NODES_HEALTH_EVALUATION, FABRIC_NODE_HEALTH, FABRIC_NODE_HEALTH_EVALUATION,
+    FABRIC_NODE_HEALTH_EX1, FABRIC_NODE_HEALTH_QUERY_DESCRIPTION, FABRIC_NODE_HEALTH_REPORT,
+    FABRIC_NODE_HEALTH_STATE, FABRIC_NODE_HEALTH_STATES_FILTER,
+    FABRIC_NODE_TYPE_NODES_HEALTH_EVALUATION, FABRIC_PARTITIONS_HEALTH_EVALUATION,
+    FABRIC_PARTITION_HEALTH, FABRIC_PARTITION_HEALTH_EVALUATION, FABRIC_PARTITION_HEALTH_EX1,
+    FABRIC_PARTITION_HEALTH_QUERY_DESCRIPTION, FABRIC_PARTITION_HEALTH_REPORT,
+    FABRIC_PARTITION_HEALTH_STATE, FABRIC_PARTITION_HEALTH_STATES_FILTER,
+    FABRIC_REPLICAS_HEALTH_EVALUATION, FABRIC_REPLICA_HEALTH, FABRIC_REPLICA_HEALTH_EVALUATION,
+    FABRIC_REPLICA_HEALTH_QUERY_DESCRIPTION, FABRIC_REPLICA_HEALTH_STATE,
+    FABRIC_REPLICA_HEALTH_STATES_FILTER, FABRIC_SERVICES_HEALTH_EVALUATION, FABRIC_SERVICE_HEALTH,
+    FABRIC_SERVICE_HEALTH_EVALUATION, FABRIC_SERVICE_HEALTH_EX1,
+    FABRIC_SERVICE_HEALTH_QUERY_DESCRIPTION, FABRIC_SERVICE_HEALTH_REPORT,
+    FABRIC_SERVICE_HEALTH_STATE, FABRIC_SERVICE_HEALTH_STATES_FILTER,
+    FABRIC_STATEFUL_SERVICE_REPLICA_HEALTH, FABRIC_STATEFUL_SERVICE_REPLICA_HEALTH_EX1,
+    FABRIC_STATEFUL_SERVICE_REPLICA_HEALTH_REPORT, FABRIC_STATEFUL_SERVICE_REPLICA_HEALTH_STATE,
+    FABRIC_STATELESS_SERVICE_INSTANCE_HEALTH, FABRIC_STATELESS_SERVICE_INSTANCE_HEALTH_EX1,
+    FABRIC_STATELESS_SERVICE_INSTANCE_HEALTH_REPORT,
+    FABRIC_STATELESS_SERVICE_INSTANCE_HEALTH_STATE, FABRIC_SYSTEM_APPLICATION_HEALTH_EVALUATION,
+    FABRIC_UPGRADE_DOMAIN_DELTA_NODES_CHECK_HEALTH_EVALUATION,
+    FABRIC_UPGRADE_DOMAIN_DEPLOYED_APPLICATIONS_HEALTH_EVALUATION,
+    FABRIC_UPGRADE_DOMAIN_NODES_HEALTH_EVALUATION,
+};
+
+/// Converts a possibly null SF `Count`/`Items` list into owned values. Must
+/// be used inside an `unsafe` block.
+macro_rules! read_list {
+    ($list:expr) => {
+        match $list.as_ref() {
+            Some(list) => list_items(list.Items, list.Count)
+                .iter()
+                .map(TryFrom::try_from)
+                .collect::<Result<Vec<_>, Error>>(),
+            None => Ok(vec![]),
+        }
+    };
+}
+
+/// Selects events or children by health state. Filters combine with `|`,
+/// e.g. `HealthStateFilter::WARNING | HealthStateFilter::ERROR`. `DEFAULT`
+/// leaves the choice to SF.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct HealthStateFilter(u32);
+
+impl HealthStateFilter {
+    pub const DEFAULT: Self = Self(FABRIC_HEALTH_STATE_FILTER_DEFAULT.0 as u32);
+    pub const NONE: Self = Self(FABRIC_HEALTH_STATE_FILTER_NONE.0 as u32);
+    pub const OK: Self = Self(FABRIC_HEALTH_STATE_FILTER_OK.0 as u32);
+    pub const WARNING: Self = Self(FABRIC_HEALTH_STATE_FILTER_WARNING.0 as u32);
+    pub const ERROR: Self = Self(FABRIC_HEALTH_STATE_FILTER_ERROR.0 as u32);
+    pub const ALL: Self = Self(FABRIC_HEALTH_STATE_FILTER_ALL.0 as u32);
+
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+}
+
+impl BitOr for HealthStateFilter {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+fn events_filter(
+    arena: &mut Arena,
+    filter: HealthStateFilter,
+) -> *const FABRIC_HEALTH_EVENTS_FILTER {
+    arena.alloc(FABRIC_HEALTH_EVENTS_FILTER {
+        HealthStateFilter: filter.bits(),
+        Reserved: ptr::null_mut(),
+    })
+}
+
+/// Describes a cluster health query. SF uses the health policy from the
+/// cluster manifest when `health_policy` isn't set.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClusterHealthQueryDescription {
+    pub health_policy: Option<ClusterHealthPolicy>,
+    /// Health policies for specific applications, keyed by application name.
+    pub application_health_policies: BTreeMap<String, ApplicationHealthPolicy>,
+    pub events_filter: HealthStateFilter,
+    pub nodes_filter: HealthStateFilter,
+    pub applications_filter: HealthStateFilter,
+}
+
+impl ClusterHealthQueryDescription {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn health_policy(mut self, health_policy: ClusterHealthPolicy) -> Self {
+        self.health_policy = Some(health_policy);
+        self
+    }
+
+    pub fn application_health_policy(
+        mut self,
+        application_name: &str,
+        health_policy: ApplicationHealthPolicy,
+    ) -> Self {
+        self.application_health_policies
+            .insert(application_name.to_string(), health_policy);
+        self
+    }
+
+    pub fn events_filter(mut self, filter: HealthStateFilter) -> Self {
+        self.events_filter = filter;
+        self
+    }
+
+    pub fn nodes_filter(mut self, filter: HealthStateFilter) -> Self {
+        self.nodes_filter = filter;
+        self
+    }
+
+    pub fn applications_filter(mut self, filter: HealthStateFilter) -> Self {
+        self.applications_filter = filter;
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        if let Some(health_policy) = &self.health_policy {
+            health_policy.validate()?;
+        }
+        self.application_health_policies
+            .values()
+            .try_for_each(ApplicationHealthPolicy::validate)
+    }
+
+    fn to_raw(&self, arena: &mut Arena) -> FABRIC_CLUSTER_HEALTH_QUERY_DESCRIPTION {
+        let application_health_policies = if self.application_health_policies.is_empty() {
+            ptr::null()
+        } else {
+            application_health_policy_map(arena, &self.application_health_policies)
+        };
+
+        FABRIC_CLUSTER_HEALTH_QUERY_DESCRIPTION {
+            HealthPolicy: cluster_health_policy(arena, self.health_policy.as_ref()),
+            ApplicationHealthPolicyMap: application_health_policies,
+            EventsFilter: events_filter(arena, self.events_filter),
+            NodesFilter: arena.alloc(FABRIC_NODE_HEALTH_STATES_FILTER {
+                HealthStateFilter: self.nodes_filter.bits(),
+                Reserved: ptr::null_mut(),
+            }),
+            ApplicationsFilter: arena.alloc(FABRIC_APPLICATION_HEALTH_STATES_FILTER {
+                HealthStateFilter: self.applications_filter.bits(),
+                Reserved: ptr::null_mut(),
+            }),
+            Reserved: ptr::null_mut(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeHealthQueryDescription {
+    pub node_name: String,
+    pub health_policy: Option<ClusterHealthPolicy>,
+    pub events_filter: HealthStateFilter,
+}
+
+impl NodeHealthQueryDescription {
+    pub fn new(node_name: &str) -> Self {
+        Self {
+            node_name: node_name.to_string(),
+            health_policy: None,
+            events_filter: HealthStateFilter::DEFAULT,
+        }
+    }
+
+    pub fn health_policy(mut self, health_policy: ClusterHealthPolicy) -> Self {
+        self.health_policy = Some(health_policy);
+        self
+    }
+
+    pub fn events_filter(mut self, filter: HealthStateFilter) -> Self {
+        self.events_filter = filter;
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        self.health_policy
+            .as_ref()
+            .map_or(Ok(()), ClusterHealthPolicy::validate)
+    }
+
+    fn to_raw(&self, arena: &mut Arena) -> FABRIC_NODE_HEALTH_QUERY_DESCRIPTION {
+        FABRIC_NODE_HEALTH_QUERY_DESCRIPTION {
+            NodeName: arena.wide(&self.node_name),
+            HealthPolicy: cluster_health_policy(arena, self.health_policy.as_ref()),
+            EventsFilter: events_filter(arena, self.events_filter),
+            Reserved: ptr::null_mut(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApplicationHealthQueryDescription {
+    pub application_name: String,
+    pub health_policy: Option<ApplicationHealthPolicy>,
+    pub events_filter: HealthStateFilter,
+    pub services_filter: HealthStateFilter,
+    pub deployed_applications_filter: HealthStateFilter,
+}
+
+impl ApplicationHealthQueryDescription {
+    pub fn new(application_name: &str) -> Self {
+        Self {
+            application_name: application_name.to_string(),
+            health_policy: None,
+            events_filter: HealthStateFilter::DEFAULT,
+            services_filter: HealthStateFilter::DEFAULT,
+            deployed_applications_filter: HealthStateFilter::DEFAULT,
+        }
+    }
+
+    pub fn health_policy(mut self, health_policy: ApplicationHealthPolicy) -> Self {
+        self.health_policy = Some(health_policy);
+        self
+    }
+
+    pub fn events_filter(mut self, filter: HealthStateFilter) -> Self {
+        self.events_filter = filter;
+        self
+    }
+
+    pub fn services_filter(mut self, filter: HealthStateFilter) -> Self {
+        self.services_filter = filter;
+        self
+    }
+
+    pub fn deployed_applications_filter(mut self, filter: HealthStateFilter) -> Self {
+        self.deployed_applications_filter = filter;
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        self.health_policy
+            .as_ref()
+            .map_or(Ok(()), ApplicationHealthPolicy::validate)
+    }
+
+    fn to_raw(&self, arena: &mut Arena) -> FABRIC_APPLICATION_HEALTH_QUERY_DESCRIPTION {
+        FABRIC_APPLICATION_HEALTH_QUERY_DESCRIPTION {
+            ApplicationName: arena.uri(&self.application_name),
+            HealthPolicy: application_health_policy(arena, self.health_policy.as_ref()),
+            EventsFilter: events_filter(arena, self.events_filter),
+            ServicesFilter: arena.alloc(FABRIC_SERVICE_HEALTH_STATES_FILTER {
+                HealthStateFilter: self.services_filter.bits(),
+                Reserved: ptr::null_mut(),
+            }),
+            DeployedApplicationsFilter: arena.alloc(
+                FABRIC_DEPLOYED_APPLICATION_HEALTH_STATES_FILTER {
+                    HealthStateFilter: self.deployed_applications_filter.bits(),
+                    Reserved: ptr::null_mut(),
+                },
+            ),
+            Reserved: ptr::null_mut(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceHealthQueryDescription {
+    pub service_name: String,
+    pub health_policy: Option<ApplicationHealthPolicy>,
+    pub events_filter: HealthStateFilter,
+    pub partitions_filter: HealthStateFilter,
+}
+
+impl ServiceHealthQueryDescription {
+    pub fn new(service_name: &str) -> Self {
+        Self {
+            service_name: service_name.to_string(),
+            health_policy: None,
+            events_filter: HealthStateFilter::DEFAULT,
+            partitions_filter: HealthStateFilter::DEFAULT,
+        }
+    }
+
+    pub fn health_policy(mut self, health_policy: ApplicationHealthPolicy) -> Self {
+        self.health_policy = Some(health_policy);
+        self
+    }
+
+    pub fn events_filter(mut self, filter: HealthStateFilter) -> Self {
+        self.events_filter = filter;
+        self
+    }
+
+    pub fn partitions_filter(mut self, filter: HealthStateFilter) -> Self {
+        self.partitions_filter = filter;
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        self.health_policy
+            .as_ref()
+            .map_or(Ok(()), ApplicationHealthPolicy::validate)
+    }
+
+    fn to_raw(&self, arena: &mut Arena) -> FABRIC_SERVICE_HEALTH_QUERY_DESCRIPTION {
+        FABRIC_SERVICE_HEALTH_QUERY_DESCRIPTION {
+            ServiceName: arena.uri(&self.service_name),
+            HealthPolicy: application_health_policy(arena, self.health_policy.as_ref()),
+            EventsFilter: events_filter(arena, self.events_filter),
+            PartitionsFilter: arena.alloc(FABRIC_PARTITION_HEALTH_STATES_FILTER {
+                HealthStateFilter: self.partitions_filter.bits(),
+                Reserved: ptr::null_mut(),
+            }),
+            Reserved: ptr::null_mut(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartitionHealthQueryDescription {
+    pub partition_id: GUID,
+    pub health_policy: Option<ApplicationHealthPolicy>,
+    pub events_filter: HealthStateFilter,
+    pub replicas_filter: HealthStateFilter,
+}
+
+impl PartitionHealthQueryDescription {
+    pub fn new(partition_id: GUID) -> Self {
+        Self {
+            partition_id,
+            health_policy: None,
+            events_filter: HealthStateFilter::DEFAULT,
+            replicas_filter: HealthStateFilter::DEFAULT,
+        }
+    }
+
+    pub fn health_policy(mut self, health_policy: ApplicationHealthPolicy) -> Self {
+        self.health_policy = Some(health_policy);
+        self
+    }
+
+    pub fn events_filter(mut self, filter: HealthStateFilter) -> Self {
+        self.events_filter = filter;
+        self
+    }
+
+    pub fn replicas_filter(mut self, filter: HealthStateFilter) -> Self {
+        self.replicas_filter = filter;
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        self.health_policy
+            .as_ref()
+            .map_or(Ok(()), ApplicationHealthPolicy::validate)
+    }
+
+    fn to_raw(&self, arena: &mut Arena) -> FABRIC_PARTITION_HEALTH_QUERY_DESCRIPTION {
+        FABRIC_PARTITION_HEALTH_QUERY_DESCRIPTION {
+            PartitionId: self.partition_id,
+            HealthPolicy: application_health_policy(arena, self.health_policy.as_ref()),
+            EventsFilter: events_filter(arena, self.events_filter),
+            ReplicasFilter: arena.alloc(FABRIC_REPLICA_HEALTH_STATES_FILTER {
+                HealthStateFilter: self.replicas_filter.bits(),
+                Reserved: ptr::null_mut(),
+            }),
+            Reserved: ptr::null_mut(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplicaHealthQueryDescription {
+    pub partition_id: GUID,
+    pub replica_or_instance_id: i64,
+    pub health_policy: Option<ApplicationHealthPolicy>,
+    pub events_filter: HealthStateFilter,
+}
+
+impl ReplicaHealthQueryDescription {
+    pub fn new(partition_id: GUID, replica_or_instance_id: i64) -> Self {
+        Self {
+            partition_id,
+            replica_or_instance_id,
+            health_policy: None,
+            events_filter: HealthStateFilter::DEFAULT,
+        }
+    }
+
+    pub fn health_policy(mut self, health_policy: ApplicationHealthPolicy) -> Self {
+        self.health_policy = Some(health_policy);
+        self
+    }
+
+    pub fn events_filter(mut self, filter: HealthStateFilter) -> Self {
+        self.events_filter = filter;
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        self.health_policy
+            .as_ref()
+            .map_or(Ok(()), ApplicationHealthPolicy::validate)
+    }
+
+    fn to_raw(&self, arena: &mut Arena) -> FABRIC_REPLICA_HEALTH_QUERY_DESCRIPTION {
+        FABRIC_REPLICA_HEALTH_QUERY_DESCRIPTION {
+            PartitionId: self.partition_id,
+            ReplicaOrInstanceId: self.replica_or_instance_id,
+            HealthPolicy: application_health_policy(arena, self.health_policy.as_ref()),
+            EventsFilter: events_filter(arena, self.events_filter),
+            Reserved: ptr::null_mut(),
+        }
+    }
+}
+
+/// A health report as SF stores it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthInformation {
+    /// Identifies the watchdog or component that reported.
+    pub source_id: String,
+    /// The aspect of the entity being reported on. SF keeps one report per
+    /// source and property.
+    pub property: String,
+    pub health_state: HealthState,
+    /// `None` means the report never expires.
+    pub time_to_live: Option<Duration>,
+    pub description: String,
+    pub sequence_number: i64,
+    pub remove_when_expired: bool,
+}
+
+impl TryFrom<&FABRIC_HEALTH_INFORMATION> for HealthInformation {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_HEALTH_INFORMATION) -> Result<Self, Self::Error> {
+        let time_to_live = if value.TimeToLiveSeconds == FABRIC_HEALTH_REPORT_INFINITE_TTL {
+            None
+        } else {
+            Some(Duration::from_secs(value.TimeToLiveSeconds as u64))
+        };
+
+        Ok(Self {
+            source_id: unsafe { wide_to_string(value.SourceId.0)? },
+            property: unsafe { wide_to_string(value.Property.0)? },
+            health_state: HealthState::from(value.State),
+            time_to_live,
+            description: unsafe { wide_to_string(value.Description.0)? },
+            sequence_number: value.SequenceNumber,
+            remove_when_expired: value.RemoveWhenExpired.as_bool(),
+        })
+    }
+}
+
+impl HealthInformation {
+    /// Creates a report that never expires and lets SF assign the sequence
+    /// number.
+    pub fn new(source_id: &str, property: &str, health_state: HealthState) -> Self {
+        Self {
+            source_id: source_id.to_string(),
+            property: property.to_string(),
+            health_state,
+            time_to_live: None,
+            description: String::new(),
+            sequence_number: FABRIC_AUTO_SEQUENCE_NUMBER as i64,
+            remove_when_expired: false,
+        }
+    }
+
+    pub fn time_to_live(mut self, time_to_live: Duration) -> Self {
+        self.time_to_live = Some(time_to_live);
+        self
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = description.to_string();
+        self
+    }
+
+    /// SF drops reports whose sequence number isn't greater than the last
+    /// one it accepted for the same source and property.
+    pub fn sequence_number(mut self, sequence_number: i64) -> Self {
+        self.sequence_number = sequence_number;
+        self
+    }
+
+    pub fn remove_when_expired(mut self, remove_when_expired: bool) -> Self {
+        self.remove_when_expired = remove_when_expired;
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.source_id.is_empty() {
+            return Err(Error::InvalidHealthReport("source id must not be empty"));
+        }
+        if self.property.is_empty() {
+            return Err(Error::InvalidHealthReport("property must not be empty"));
+        }
+        if !matches!(
+            self.health_state,
+            HealthState::Ok | HealthState::Warning | HealthState::Error
+        ) {
+            return Err(Error::InvalidHealthReport(
+                "health state must be Ok, Warning or Error",
+            ));
+        }
+        if self.description.encode_utf16().count() > MAX_HEALTH_DESCRIPTION_LENGTH {
+            return Err(Error::InvalidHealthReport(
+                "description is longer than 4096 characters",
+            ));
+        }
+        if let Some(time_to_live) = self.time_to_live {
+            if time_to_live.as_secs() == 0 || time_to_live.as_secs() >= u32::MAX as u64 {
+                return Err(Error::InvalidHealthReport(
+                    "time to live must be between one second and u32::MAX seconds",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn to_raw(&self, arena: &mut Arena) -> *const FABRIC_HEALTH_INFORMATION {
+        let time_to_live = self
+            .time_to_live
+            .map_or(FABRIC_HEALTH_REPORT_INFINITE_TTL, |time_to_live| {
+                time_to_live.as_secs() as u32
+            });
+        let information = FABRIC_HEALTH_INFORMATION {
+            SourceId: arena.wide(&self.source_id),
+            Property: arena.wide(&self.property),
+            TimeToLiveSeconds: time_to_live,
+            State: self.health_state.into(),
+            Description: arena.wide(&self.description),
+            SequenceNumber: self.sequence_number,
+            RemoveWhenExpired: self.remove_when_expired.into(),
+            Reserved: ptr::null_mut(),
+        };
+
+        arena.alloc(information)
+    }
+}
+
+/// The longest description SF accepts in a health report, in UTF-16 code
+/// units.
+pub const MAX_HEALTH_DESCRIPTION_LENGTH: usize = 4096;
+
+/// A health report together with the entity it is about.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HealthReport {
+    Cluster {
+        health_information: HealthInformation,
+    },
+    Node {
+        node_name: String,
+        health_information: HealthInformation,
+    },
+    Application {
+        application_name: String,
+        health_information: HealthInformation,
+    },
+    DeployedApplication {
+        application_name: String,
+        node_name: String,
+        health_information: HealthInformation,
+    },
+    DeployedServicePackage {
+        application_name: String,
+        service_manifest_name: String,
+        node_name: String,
+        health_information: HealthInformation,
+    },
+    Service {
+        service_name: String,
+        health_information: HealthInformation,
+    },
+    Partition {
+        partition_id: GUID,
+        health_information: HealthInformation,
+    },
+    StatefulReplica {
+        partition_id: GUID,
+        replica_id: i64,
+        health_information: HealthInformation,
+    },
+    StatelessInstance {
+        partition_id: GUID,
+        instance_id: i64,
+        health_information: HealthInformation,
+    },
+}
+
+impl HealthReport {
+    pub fn health_information(&self) -> &HealthInformation {
+        match self {
+            HealthReport::Cluster { health_information }
+            | HealthReport::Node {
+                health_information, ..
+            }
+            | HealthReport::Application {
+                health_information, ..
+            }
+            | HealthReport::DeployedApplication {
+                health_information, ..
+            }
+            | HealthReport::DeployedServicePackage {
+                health_information, ..
+            }
+            | HealthReport::Service {
+                health_information, ..
+            }
+            | HealthReport::Partition {
+                health_information, ..
+            }
+            | HealthReport::StatefulReplica {
+                health_information, ..
+            }
+            | HealthReport::StatelessInstance {
+                health_information, ..
+            } => health_information,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        self.health_information().validate()
+    }
+
+    fn to_raw(&self, arena: &mut Arena) -> FABRIC_HEALTH_REPORT {
+        let information = self.health_information().to_raw(arena);
+        let (kind, value) = match self {
+            HealthReport::Cluster { .. } => (
+                FABRIC_HEALTH_REPORT_KIND_CLUSTER,
+                arena.alloc(FABRIC_CLUSTER_HEALTH_REPORT {
+                    HealthInformation: information,
+                    Reserved: ptr::null_mut(),
+                }) as *mut c_void,
+            ),
+            HealthReport::Node { node_name, .. } => {
+                let report = FABRIC_NODE_HEALTH_REPORT {
+                    NodeName: arena.wide(node_name),
+                    HealthInformation: information,
+                    Reserved: ptr::null_mut(),
+                };
+                (
+                    FABRIC_HEALTH_REPORT_KIND_NODE,
+                    arena.alloc(report) as *mut c_void,
+                )
+            }
+            HealthReport::Application {
+                application_name, ..
+            } => {
+                let report = FABRIC_APPLICATION_HEALTH_REPORT {
+                    ApplicationName: arena.uri(application_name),
+                    HealthInformation: information,
+                    Reserved: ptr::null_mut(),
+                };
+                (
+                    FABRIC_HEALTH_REPORT_KIND_APPLICATION,
+                    arena.alloc(report) as *mut c_void,
+                )
+            }
+            HealthReport::DeployedApplication {
+                application_name,
+                node_name,
+                ..
+            } => {
+                let report = FABRIC_DEPLOYED_APPLICATION_HEALTH_REPORT {
+                    ApplicationName: arena.uri(application_name),
+                    NodeName: arena.wide(node_name),
+                    HealthInformation: information,
+                    Reserved: ptr::null_mut(),
+                };
+                (
+                    FABRIC_HEALTH_REPORT_KIND_DEPLOYED_APPLICATION,
+                    arena.alloc(report) as *mut c_void,
+                )
+            }
+            HealthReport::DeployedServicePackage {
+                application_name,
+                service_manifest_name,
+                node_name,
+                ..
+            } => {
+                let report = FABRIC_DEPLOYED_SERVICE_PACKAGE_HEALTH_REPORT {
+                    ApplicationName: arena.uri(application_name),
+                    ServiceManifestName: arena.wide(service_manifest_name),
+                    NodeName: arena.wide(node_name),
+                    HealthInformation: information,
+                    Reserved: ptr::null_mut(),
+                };
+                (
+                    FABRIC_HEALTH_REPORT_KIND_DEPLOYED_SERVICE_PACKAGE,
+                    arena.alloc(report) as *mut c_void,
+                )
+            }
+            HealthReport::Service { service_name, .. } => {
+                let report = FABRIC_SERVICE_HEALTH_REPORT {
+                    ServiceName: arena.uri(service_name),
+                    HealthInformation: information,
+                    Reserved: ptr::null_mut(),
+                };
+                (
+                    FABRIC_HEALTH_REPORT_KIND_SERVICE,
+                    arena.alloc(report) as *mut c_void,
+                )
+            }
+            HealthReport::Partition { partition_id, .. } => (
+                FABRIC_HEALTH_REPORT_KIND_PARTITION,
+                arena.alloc(FABRIC_PARTITION_HEALTH_REPORT {
+                    PartitionId: *partition_id,
+                    HealthInformation: information,
+                    Reserved: ptr::null_mut(),
+                }) as *mut c_void,
+            ),
+            HealthReport::StatefulReplica {
+                partition_id,
+                replica_id,
+                ..
+            } => (
+                FABRIC_HEALTH_REPORT_KIND_STATEFUL_SERVICE_REPLICA,
+                arena.alloc(FABRIC_STATEFUL_SERVICE_REPLICA_HEALTH_REPORT {
+                    PartitionId: *partition_id,
+                    ReplicaId: *replica_id,
+                    HealthInformation: information,
+                    Reserved: ptr::null_mut(),
+                }) as *mut c_void,
+            ),
+            HealthReport::StatelessInstance {
+                partition_id,
+                instance_id,
+                ..
+            } => (
+                FABRIC_HEALTH_REPORT_KIND_STATELESS_SERVICE_INSTANCE,
+                arena.alloc(FABRIC_STATELESS_SERVICE_INSTANCE_HEALTH_REPORT {
+                    PartitionId: *partition_id,
+                    InstanceId: *instance_id,
+                    HealthInformation: information,
+                    Reserved: ptr::null_mut(),
+                }) as *mut c_void,
+            ),
+        };
+
+        FABRIC_HEALTH_REPORT {
+            Kind: kind,
+            Value: value,
+        }
+    }
+}
+
+/// Controls how the health client sends a report. By default reports are
+/// batched and sent periodically; `immediate` sends this one right away.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct HealthReportSendOptions {
+    pub immediate: bool,
+}
+
+impl HealthReportSendOptions {
+    fn to_raw(self) -> FABRIC_HEALTH_REPORT_SEND_OPTIONS {
+        FABRIC_HEALTH_REPORT_SEND_OPTIONS {
+            Immediate: self.immediate.into(),
+            Reserved: ptr::null_mut(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthEvent {
+    pub health_information: HealthInformation,
+    pub source_utc_timestamp: Option<SystemTime>,
+    pub last_modified_utc_timestamp: Option<SystemTime>,
+    pub is_expired: bool,
+    pub last_ok_transition_at: Option<SystemTime>,
+    pub last_warning_transition_at: Option<SystemTime>,
+    pub last_error_transition_at: Option<SystemTime>,
+}
+
+impl TryFrom<&FABRIC_HEALTH_EVENT> for HealthEvent {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_HEALTH_EVENT) -> Result<Self, Self::Error> {
+        let health_information = unsafe { value.HealthInformation.as_ref() }
+            .ok_or(Error::NullPointer("FABRIC_HEALTH_EVENT"))?;
+        let health_information = HealthInformation::try_from(health_information)?;
+        let ex1 = unsafe { (value.Reserved as *const FABRIC_HEALTH_EVENT_EX1).as_ref() };
+
+        Ok(Self {
+            health_information,
+            source_utc_timestamp: filetime_to_system_time(&value.SourceUtcTimestamp),
+            last_modified_utc_timestamp: filetime_to_system_time(&value.LastModifiedUtcTimestamp),
+            is_expired: value.IsExpired.as_bool(),
+            last_ok_transition_at: ex1
+                .and_then(|ex1| filetime_to_system_time(&ex1.LastOkTransitionAt)),
+            last_warning_transition_at: ex1
+                .and_then(|ex1| filetime_to_system_time(&ex1.LastWarningTransitionAt)),
+            last_error_transition_at: ex1
+                .and_then(|ex1| filetime_to_system_time(&ex1.LastErrorTransitionAt)),
+        })
+    }
+}
+
+/// Explains why SF considers an entity unhealthy. Evaluations nest: an
+/// unhealthy cluster points at its unhealthy applications, which point at
+/// their unhealthy services and so on down to the offending events.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthEvaluation {
+    pub description: String,
+    pub aggregated_health_state: HealthState,
+    pub kind: HealthEvaluationKind,
+    pub unhealthy_evaluations: Vec<HealthEvaluation>,
+}
+
+/// What a `HealthEvaluation` evaluated. Aggregate kinds carry the number of
+/// children evaluated and the policy limit they were held to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HealthEvaluationKind {
+    Event {
+        unhealthy_event: Option<HealthEvent>,
+        consider_warning_as_error: bool,
+    },
+    Replicas {
+        total_count: u32,
+        max_percent_unhealthy_replicas_per_partition: u8,
+    },
+    Partitions {
+        total_count: u32,
+        max_percent_unhealthy_partitions_per_service: u8,
+    },
+    DeployedServicePackages {
+        total_count: u32,
+    },
+    DeployedApplications {
+        total_count: u32,
+        max_percent_unhealthy_deployed_applications: u8,
+    },
+    Services {
+        service_type_name: String,
+        total_count: u32,
+        max_percent_unhealthy_services: u8,
+    },
+    Nodes {
+        total_count: u32,
+        max_percent_unhealthy_nodes: u8,
+    },
+    Applications {
+        total_count: u32,
+        max_percent_unhealthy_applications: u8,
+    },
+    SystemApplication,
+    UpgradeDomainDeployedApplications {
+        upgrade_domain_name: String,
+        total_count: u32,
+        max_percent_unhealthy_deployed_applications: u8,
+    },
+    UpgradeDomainNodes {
+        upgrade_domain_name: String,
+        total_count: u32,
+        max_percent_unhealthy_nodes: u8,
+    },
+    Node {
+        node_name: String,
+    },
+    Replica {
+        partition_id: GUID,
+        replica_or_instance_id: i64,
+    },
+    Partition {
+        partition_id: GUID,
+    },
+    Service {
+        service_name: String,
+    },
+    DeployedServicePackage {
+        application_name: String,
+        service_manifest_name: String,
+        node_name: String,
+    },
+    DeployedApplication {
+        application_name: String,
+        node_name: String,
+    },
+    Application {
+        application_name: String,
+    },
+    DeltaNodesCheck {
+        baseline_error_count: u32,
+        baseline_total_count: u32,
+        total_count: u32,
+        max_percent_delta_unhealthy_nodes: u8,
+    },
+    UpgradeDomainDeltaNodesCheck {
+        upgrade_domain_name: String,
+        baseline_error_count: u32,
+        baseline_total_count: u32,
+        total_count: u32,
+        max_percent_upgrade_domain_delta_unhealthy_nodes: u8,
+    },
+    ApplicationTypeApplications {
+        application_type_name: String,
+        total_count: u32,
+        max_percent_unhealthy_applications: u8,
+    },
+    NodeTypeNodes {
+        node_type_name: String,
+        total_count: u32,
+        max_percent_unhealthy_nodes: u8,
+    },
+    /// An evaluation kind this crate doesn't know about yet.
+    Unknown,
+}
+
+impl TryFrom<&FABRIC_HEALTH_EVALUATION> for HealthEvaluation {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_HEALTH_EVALUATION) -> Result<Self, Self::Error> {
+        unsafe fn raw<'a, T>(value: *mut c_void) -> &'a T {
+            &*(value as *const T)
+        }
+
+        if value.Value.is_null() {
+            return Ok(Self::unknown());
+        }
+
+        let (description, state, evaluations, kind) = unsafe {
+            match value.Kind {
+                FABRIC_HEALTH_EVALUATION_KIND_EVENT => {
+                    let v = raw::<FABRIC_EVENT_HEALTH_EVALUATION>(value.Value);
+                    let unhealthy_event = v
+                        .UnhealthyEvent
+                        .as_ref()
+                        .map(HealthEvent::try_from)
+                        .transpose()?;
+                    let kind = HealthEvaluationKind::Event {
+                        unhealthy_event,
+                        consider_warning_as_error: v.ConsiderWarningAsError.as_bool(),
+                    };
+                    (v.Description, v.AggregatedHealthState, ptr::null(), kind)
+                }
+                FABRIC_HEALTH_EVALUATION_KIND_REPLICAS => {
+                    let v = raw::<FABRIC_REPLICAS_HEALTH_EVALUATION>(value.Value);
+                    let kind = HealthEvaluationKind::Replicas {
+                        total_count: v.TotalCount,
+                        max_percent_unhealthy_replicas_per_partition: v
+                            .MaxPercentUnhealthyReplicasPerPartition,
+                    };
+                    (
+                        v.Description,
+                        v.AggregatedHealthState,
+                        v.UnhealthyEvaluations,
+                        kind,
+                    )
+                }
+                FABRIC_HEALTH_EVALUATION_KIND_PARTITIONS => {
+                    let v = raw::<FABRIC_PARTITIONS_HEALTH_EVALUATION>(value.Value);
+                    let kind = HealthEvaluationKind::Partitions {
+                        total_count: v.TotalCount,
+                        max_percent_unhealthy_partitions_per_service: v
+                            .MaxPercentUnhealthyPartitionsPerService,
+                    };
+                    (
+                        v.Description,
+                        v.AggregatedHealthState,
+                        v.UnhealthyEvaluations,
+                        kind,
+                    )
+                }
+                FABRIC_HEALTH_EVALUATION_KIND_DEPLOYED_SERVICE_PACKAGES => {
+                    let v = raw::<FABRIC_DEPLOYED_SERVICE_PACKAGES_HEALTH_EVALUATION>(value.Value);
+                    let kind = HealthEvaluationKind::DeployedServicePackages {
+                        total_count: v.TotalCount,
+                    };
+                    (
+                        v.Description,
+                        v.AggregatedHealthState,
+                        v.UnhealthyEvaluations,
+                        kind,
+                    )
+                }
+                FABRIC_HEALTH_EVALUATION_KIND_DEPLOYED_APPLICATIONS => {
+                    let v = raw::<FABRIC_DEPLOYED_APPLICATIONS_HEALTH_EVALUATION>(value.Value);
+                    let kind = HealthEvaluationKind::DeployedApplications {
+                        total_count: v.TotalCount,
+                        max_percent_unhealthy_deployed_applications: v
+                            .MaxPercentUnhealthyDeployedApplications,
+                    };
+                    (
+                        v.Description,
+                        v.AggregatedHealthState,
+                        v.UnhealthyEvaluations,
+                        kind,
+                    )
+                }
+                FABRIC_HEALTH_EVALUATION_KIND_SERVICES => {
+                    let v = raw::<FABRIC_SERVICES_HEALTH_EVALUATION>(value.Value);
+                    let kind = HealthEvaluationKind::Services {
+                        service_type_name: wide_to_string(v.ServiceTypeName.0)?,
+                        total_count: v.TotalCount,
+                        max_percent_unhealthy_services: v.MaxPercentUnhealthyServices,
+                    };
+                    (
+                        v.Description,
+                        v.AggregatedHealthState,
+                        v.UnhealthyEvaluations,
+                        kind,
+                    )
+                }
+                FABRIC_HEALTH_EVALUATION_KIND_NODES => {
+                    let v = raw::<FABRIC_NODES_HEALTH_EVALUATION>(value.Value);
+                    let kind = HealthEvaluationKind::Nodes {
+                        total_count: v.TotalCount,
+                        max_percent_unhealthy_nodes: v.MaxPercentUnhealthyNodes,
+                    };
+                    (
+                        v.Description,
+                        v.AggregatedHealthState,
+                        v.UnhealthyEvaluations,
+                        kind,
+                    )
+                }
+                FABRIC_HEALTH_EVALUATION_KIND_APPLICATIONS => {
+                    let v = raw::<FABRIC_APPLICATIONS_HEALTH_EVALUATION>(value.Value);
+                    let kind = HealthEvaluationKind::Applications {
+                        total_count: v.TotalCount,
+                        max_percent_unhealthy_applications: v.MaxPercentUnhealthyApplications,
+                    };
+                    (
+                        v.Description,
+                        v.AggregatedHealthState,
+                        v.UnhealthyEvaluations,
+                        kind,
+                    )
+                }
+                FABRIC_HEALTH_EVALUATION_KIND_SYSTEM_APPLICATION => {
+                    let v = raw::<FABRIC_SYSTEM_APPLICATION_HEALTH_EVALUATION>(value.Value);
+                    let kind = HealthEvaluationKind::SystemApplication;
+                    (
+                        v.Description,
+                        v.AggregatedHealthState,
+                        v.UnhealthyEvaluations,
+                        kind,
+                    )
+                }
+                FABRIC_HEALTH_EVALUATION_KIND_UPGRADE_DOMAIN_DEPLOYED_APPLICATIONS => {
+                    let v = raw::<FABRIC_UPGRADE_DOMAIN_DEPLOYED_APPLICATIONS_HEALTH_EVALUATION>(
+                        value.Value,
+                    );
+                    let kind = HealthEvaluationKind::UpgradeDomainDeployedApplications {
+                        upgrade_domain_name: wide_to_string(v.UpgradeDomainName.0)?,
+                        total_count: v.TotalCount,
+                        max_percent_unhealthy_deployed_applications: v
+                            .MaxPercentUnhealthyDeployedApplications,
+                    };
+                    (
+                        v.Description,
+                        v.AggregatedHealthState,
+                        v.UnhealthyEvaluations,
+                        kind,
+                    )
+                }
+                FABRIC_HEALTH_EVALUATION_KIND_UPGRADE_DOMAIN_NODES => {
+                    let v = raw::<FABRIC_UPGRADE_DOMAIN_NODES_HEALTH_EVALUATION>(value.Value);
+                    let kind = HealthEvaluationKind::UpgradeDomainNodes {
+                        upgrade_domain_name: wide_to_string(v.UpgradeDomainName.0)?,
+                        total_count: v.TotalCount,
+                        max_percent_unhealthy_nodes: v.MaxPercentUnhealthyNodes,
+                    };
+                    (
+                        v.Description,
+                        v.AggregatedHealthState,
+                        v.UnhealthyEvaluations,
+                        kind,
+                    )
+                }
+                FABRIC_HEALTH_EVALUATION_KIND_NODE => {
+                    let v = raw::<FABRIC_NODE_HEALTH_EVALUATION>(value.Value);
+                    let kind = HealthEvaluationKind::Node {
+                        node_name: wide_to_string(v.NodeName.0)?,
+                    };
+                    (
+                        v.Description,
+                        v.AggregatedHealthState,
+                        v.UnhealthyEvaluations,
+                        kind,
+                    )
+                }
+                FABRIC_HEALTH_EVALUATION_KIND_REPLICA => {
+                    let v = raw::<FABRIC_REPLICA_HEALTH_EVALUATION>(value.Value);
+                    let kind = HealthEvaluationKind::Replica {
+                        partition_id: v.PartitionId,
+                        replica_or_instance_id: v.ReplicaOrInstanceId,
+                    };
+                    (
+                        v.Description,
+                        v.AggregatedHealthState,
+                        v.UnhealthyEvaluations,
+                        kind,
+                    )
+                }
+                FABRIC_HEALTH_EVALUATION_KIND_PARTITION => {
+                    let v = raw::<FABRIC_PARTITION_HEALTH_EVALUATION>(value.Value);
+                    let kind = HealthEvaluationKind::Partition {
+                        partition_id: v.PartitionId,
+                    };
+                    (
+                        v.Description,
+                        v.AggregatedHealthState,
+                        v.UnhealthyEvaluations,
+                        kind,
+                    )
+                }
+                FABRIC_HEALTH_EVALUATION_KIND_SERVICE => {
+                    let v = raw::<FABRIC_SERVICE_HEALTH_EVALUATION>(value.Value);
+                    let kind = HealthEvaluationKind::Service {
+                        service_name: wide_to_string(v.ServiceName)?,
+                    };
+                    (
+                        v.Description,
+                        v.AggregatedHealthState,
+                        v.UnhealthyEvaluations,
+                        kind,
+                    )
+                }
+                FABRIC_HEALTH_EVALUATION_KIND_DEPLOYED_SERVICE_PACKAGE => {
+                    let v = raw::<FABRIC_DEPLOYED_SERVICE_PACKAGE_HEALTH_EVALUATION>(value.Value);
+                    let kind = HealthEvaluationKind::DeployedServicePackage {
+                        application_name: wide_to_string(v.ApplicationName)?,
+                        service_manifest_name: wide_to_string(v.ServiceManifestName.0)?,
+                        node_name: wide_to_string(v.NodeName.0)?,
+                    };
+                    (
+                        v.Description,
+                        v.AggregatedHealthState,
+                        v.UnhealthyEvaluations,
+                        kind,
+                    )
+                }
+                FABRIC_HEALTH_EVALUATION_KIND_DEPLOYED_APPLICATION => {
+                    let v = raw::<FABRIC_DEPLOYED_APPLICATION_HEALTH_EVALUATION>(value.Value);
+                    let kind = HealthEvaluationKind::DeployedApplication {
+                        application_name: wide_to_string(v.ApplicationName)?,
+                        node_name: wide_to_string(v.NodeName.0)?,
+                    };
+                    (
+                        v.Description,
+                        v.AggregatedHealthState,
+                        v.UnhealthyEvaluations,
+                        kind,
+                    )
+                }
+                FABRIC_HEALTH_EVALUATION_KIND_APPLICATION => {
+                    let v = raw::<FABRIC_APPLICATION_HEALTH_EVALUATION>(value.Value);
+                    let kind = HealthEvaluationKind::Application {
+                        application_name: wide_to_string(v.ApplicationName)?,
+                    };
+                    (
+                        v.Description,
+                        v.AggregatedHealthState,
+                        v.UnhealthyEvaluations,
+                        kind,
+                    )
+                }
+                FABRIC_HEALTH_EVALUATION_KIND_DELTA_NODES_CHECK => {
+                    let v = raw::<FABRIC_DELTA_NODES_CHECK_HEALTH_EVALUATION>(value.Value);
+                    let kind = HealthEvaluationKind::DeltaNodesCheck {
+                        baseline_error_count: v.BaselineErrorCount,
+                        baseline_total_count: v.BaselineTotalCount,
+                        total_count: v.TotalCount,
+                        max_percent_delta_unhealthy_nodes: v.MaxPercentDeltaUnhealthyNodes,
+                    };
+                    (
+                        v.Description,
+                        v.AggregatedHealthState,
+                        v.UnhealthyEvaluations,
+                        kind,
+                    )
+                }
+                FABRIC_HEALTH_EVALUATION_KIND_UPGRADE_DOMAIN_DELTA_NODES_CHECK => {
+                    let v = raw::<FABRIC_UPGRADE_DOMAIN_DELTA_NODES_CHECK_HEALTH_EVALUATION>(
+                        value.Value,
+                    );
+                    let kind = HealthEvaluationKind::UpgradeDomainDeltaNodesCheck {
+                        upgrade_domain_name: wide_to_string(v.UpgradeDomainName.0)?,
+                        baseline_error_count: v.BaselineErrorCount,
+                        baseline_total_count: v.BaselineTotalCount,
+                        total_count: v.TotalCount,
+                        max_percent_upgrade_domain_delta_unhealthy_nodes: v
+                            .MaxPercentUpgradeDomainDeltaUnhealthyNodes,
+                    };
+                    (
+                        v.Description,
+                        v.AggregatedHealthState,
+                        v.UnhealthyEvaluations,
+                        kind,
+                    )
+                }
+                FABRIC_HEALTH_EVALUATION_KIND_APPLICATION_TYPE_APPLICATIONS => {
+                    let v =
+                        raw::<FABRIC_APPLICATION_TYPE_APPLICATIONS_HEALTH_EVALUATION>(value.Value);
+                    let kind = HealthEvaluationKind::ApplicationTypeApplications {
+                        application_type_name: wide_to_string(v.ApplicationTypeName.0)?,
+                        total_count: v.TotalCount,
+                        max_percent_unhealthy_applications: v.MaxPercentUnhealthyApplications,
+                    };
+                    (
+                        v.Description,
+                        v.AggregatedHealthState,
+                        v.UnhealthyEvaluations,
+                        kind,
+                    )
+                }
+                FABRIC_HEALTH_EVALUATION_KIND_NODE_TYPE_NODES => {
+                    let v = raw::<FABRIC_NODE_TYPE_NODES_HEALTH_EVALUATION>(value.Value);
+                    let kind = HealthEvaluationKind::NodeTypeNodes {
+                        node_type_name: wide_to_string(v.NodeTypeName.0)?,
+                        total_count: v.TotalCount,
+                        max_percent_unhealthy_nodes: v.MaxPercentUnhealthyNodes,
+                    };
+                    (
+                        v.Description,
+                        v.AggregatedHealthState,
+                        v.UnhealthyEvaluations,
+                        kind,
+                    )
+                }
+                _ => return Ok(Self::unknown()),
+            }
+        };
+
+        Ok(Self {
+            description: unsafe { wide_to_string(description.0)? },
+            aggregated_health_state: HealthState::from(state),
+            kind,
+            unhealthy_evaluations: unsafe { read_list!(evaluations)? },
+        })
+    }
+}
+
+impl HealthEvaluation {
+    fn unknown() -> Self {
+        Self {
+            description: String::new(),
+            aggregated_health_state: HealthState::Invalid,
+            kind: HealthEvaluationKind::Unknown,
+            unhealthy_evaluations: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeHealthState {
+    pub node_name: String,
+    pub aggregated_health_state: HealthState,
+}
+
+impl TryFrom<&FABRIC_NODE_HEALTH_STATE> for NodeHealthState {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_NODE_HEALTH_STATE) -> Result<Self, Self::Error> {
+        Ok(Self {
+            node_name: unsafe { wide_to_string(value.NodeName.0)? },
+            aggregated_health_state: HealthState::from(value.AggregatedHealthState),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApplicationHealthState {
+    pub application_name: String,
+    pub aggregated_health_state: HealthState,
+}
+
+impl TryFrom<&FABRIC_APPLICATION_HEALTH_STATE> for ApplicationHealthState {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_APPLICATION_HEALTH_STATE) -> Result<Self, Self::Error> {
+        Ok(Self {
+            application_name: unsafe { wide_to_string(value.ApplicationName)? },
+            aggregated_health_state: HealthState::from(value.AggregatedHealthState),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceHealthState {
+    pub service_name: String,
+    pub aggregated_health_state: HealthState,
+}
+
+impl TryFrom<&FABRIC_SERVICE_HEALTH_STATE> for ServiceHealthState {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_SERVICE_HEALTH_STATE) -> Result<Self, Self::Error> {
+        Ok(Self {
+            service_name: unsafe { wide_to_string(value.ServiceName)? },
+            aggregated_health_state: HealthState::from(value.AggregatedHealthState),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeployedApplicationHealthState {
+    pub application_name: String,
+    pub node_name: String,
+    pub aggregated_health_state: HealthState,
+}
+
+impl TryFrom<&FABRIC_DEPLOYED_APPLICATION_HEALTH_STATE> for DeployedApplicationHealthState {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_DEPLOYED_APPLICATION_HEALTH_STATE) -> Result<Self, Self::Error> {
+        Ok(Self {
+            application_name: unsafe { wide_to_string(value.ApplicationName)? },
+            node_name: unsafe { wide_to_string(value.NodeName.0)? },
+            aggregated_health_state: HealthState::from(value.AggregatedHealthState),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartitionHealthState {
+    pub partition_id: GUID,
+    pub aggregated_health_state: HealthState,
+}
+
+impl TryFrom<&FABRIC_PARTITION_HEALTH_STATE> for PartitionHealthState {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_PARTITION_HEALTH_STATE) -> Result<Self, Self::Error> {
+        Ok(Self {
+            partition_id: value.PartitionId,
+            aggregated_health_state: HealthState::from(value.AggregatedHealthState),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplicaHealthState {
+    pub kind: ServiceKind,
+    pub partition_id: GUID,
+    pub replica_or_instance_id: i64,
+    pub aggregated_health_state: HealthState,
+}
+
+impl TryFrom<&FABRIC_REPLICA_HEALTH_STATE> for ReplicaHealthState {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_REPLICA_HEALTH_STATE) -> Result<Self, Self::Error> {
+        let kind = ServiceKind::from(value.Kind);
+        match kind {
+            ServiceKind::Stateful => {
+                let replica = unsafe {
+                    &*(value.Value as *const FABRIC_STATEFUL_SERVICE_REPLICA_HEALTH_STATE)
+                };
+                Ok(Self {
+                    kind,
+                    partition_id: replica.PartitionId,
+                    replica_or_instance_id: replica.ReplicaId,
+                    aggregated_health_state: HealthState::from(replica.AggregatedHealthState),
+                })
+            }
+            ServiceKind::Stateless => {
+                let instance = unsafe {
+                    &*(value.Value as *const FABRIC_STATELESS_SERVICE_INSTANCE_HEALTH_STATE)
+                };
+                Ok(Self {
+                    kind,
+                    partition_id: instance.PartitionId,
+                    replica_or_instance_id: instance.InstanceId,
+                    aggregated_health_state: HealthState::from(instance.AggregatedHealthState),
+                })
+            }
+            _ => Err(Error::InvalidServiceKind),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClusterHealth {
+    pub aggregated_health_state: HealthState,
+    pub node_health_states: Vec<NodeHealthState>,
+    pub application_health_states: Vec<ApplicationHealthState>,
+    pub health_events: Vec<HealthEvent>,
+    pub unhealthy_evaluations: Vec<HealthEvaluation>,
+}
+
+impl TryFrom<&FABRIC_CLUSTER_HEALTH> for ClusterHealth {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_CLUSTER_HEALTH) -> Result<Self, Self::Error> {
+        let ex1 = unsafe { (value.Reserved as *const FABRIC_CLUSTER_HEALTH_EX1).as_ref() };
+        let ex2 = ex1
+            .and_then(|ex1| unsafe { (ex1.Reserved as *const FABRIC_CLUSTER_HEALTH_EX2).as_ref() });
+
+        Ok(Self {
+            aggregated_health_state: HealthState::from(value.AggregatedHealthState),
+            node_health_states: match ex1 {
+                Some(ex1) => unsafe { read_list!(ex1.NodeHealthStates)? },
+                None => vec![],
+            },
+            application_health_states: match ex1 {
+                Some(ex1) => unsafe { read_list!(ex1.ApplicationHealthStates)? },
+                None => vec![],
+            },
+            health_events: match ex1 {
+                Some(ex1) => unsafe { read_list!(ex1.HealthEvents)? },
+                None => vec![],
+            },
+            unhealthy_evaluations: match ex2 {
+                Some(ex2) => unsafe { read_list!(ex2.UnhealthyEvaluations)? },
+                None => vec![],
+            },
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeHealth {
+    pub node_name: String,
+    pub aggregated_health_state: HealthState,
+    pub health_events: Vec<HealthEvent>,
+    pub unhealthy_evaluations: Vec<HealthEvaluation>,
+}
+
+impl TryFrom<&FABRIC_NODE_HEALTH> for NodeHealth {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_NODE_HEALTH) -> Result<Self, Self::Error> {
+        let ex1 = unsafe { (value.Reserved as *const FABRIC_NODE_HEALTH_EX1).as_ref() };
+
+        Ok(Self {
+            node_name: unsafe { wide_to_string(value.NodeName.0)? },
+            aggregated_health_state: HealthState::from(value.AggregatedHealthState),
+            health_events: unsafe { read_list!(value.HealthEvents)? },
+            unhealthy_evaluations: match ex1 {
+                Some(ex1) => unsafe { read_list!(ex1.UnhealthyEvaluations)? },
+                None => vec![],
+            },
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApplicationHealth {
+    pub application_name: String,
+    pub aggregated_health_state: HealthState,
+    pub health_events: Vec<HealthEvent>,
+    pub service_health_states: Vec<ServiceHealthState>,
+    pub deployed_application_health_states: Vec<DeployedApplicationHealthState>,
+    pub unhealthy_evaluations: Vec<HealthEvaluation>,
+}
+
+impl TryFrom<&FABRIC_APPLICATION_HEALTH> for ApplicationHealth {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_APPLICATION_HEALTH) -> Result<Self, Self::Error> {
+        let ex1 = unsafe { (value.Reserved as *const FABRIC_APPLICATION_HEALTH_EX1).as_ref() };
+
+        Ok(Self {
+            application_name: unsafe { wide_to_string(value.ApplicationName)? },
+            aggregated_health_state: HealthState::from(value.AggregatedHealthState),
+            health_events: unsafe { read_list!(value.HealthEvents)? },
+            service_health_states: unsafe { read_list!(value.ServiceHealthStates)? },
+            deployed_application_health_states: unsafe {
+                read_list!(value.DeployedApplicationHealthStates)?
+            },
+            unhealthy_evaluations: match ex1 {
+                Some(ex1) => unsafe { read_list!(ex1.UnhealthyEvaluations)? },
+                None => vec![],
+            },
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceHealth {
+    pub service_name: String,
+    pub aggregated_health_state: HealthState,
+    pub health_events: Vec<HealthEvent>,
+    pub partition_health_states: Vec<PartitionHealthState>,
+    pub unhealthy_evaluations: Vec<HealthEvaluation>,
+}
+
+impl TryFrom<&FABRIC_SERVICE_HEALTH> for ServiceHealth {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_SERVICE_HEALTH) -> Result<Self, Self::Error> {
+        let ex1 = unsafe { (value.Reserved as *const FABRIC_SERVICE_HEALTH_EX1).as_ref() };
+
+        Ok(Self {
+            service_name: unsafe { wide_to_string(value.ServiceName)? },
+            aggregated_health_state: HealthState::from(value.AggregatedHealthState),
+            health_events: unsafe { read_list!(value.HealthEvents)? },
+            partition_health_states: unsafe { read_list!(value.PartitionHealthStates)? },
+            unhealthy_evaluations: match ex1 {
+                Some(ex1) => unsafe { read_list!(ex1.UnhealthyEvaluations)? },
+                None => vec![],
+            },
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartitionHealth {
+    pub partition_id: GUID,
+    pub aggregated_health_state: HealthState,
+    pub health_events: Vec<HealthEvent>,
+    pub replica_health_states: Vec<ReplicaHealthState>,
+    pub unhealthy_evaluations: Vec<HealthEvaluation>,
+}
+
+impl TryFrom<&FABRIC_PARTITION_HEALTH> for PartitionHealth {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_PARTITION_HEALTH) -> Result<Self, Self::Error> {
+        let ex1 = unsafe { (value.Reserved as *const FABRIC_PARTITION_HEALTH_EX1).as_ref() };
+
+        Ok(Self {
+            partition_id: value.PartitionId,
+            aggregated_health_state: HealthState::from(value.AggregatedHealthState),
+            health_events: unsafe { read_list!(value.HealthEvents)? },
+            replica_health_states: unsafe { read_list!(value.ReplicaHealthStates)? },
+            unhealthy_evaluations: match ex1 {
+                Some(ex1) => unsafe { read_list!(ex1.UnhealthyEvaluations)? },
+                None => vec![],
+            },
+        })
+    }
+}
+
+/// The health of a stateful replica or a stateless instance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplicaHealth {
+    pub kind: ServiceKind,
+    pub partition_id: GUID,
+    pub replica_or_instance_id: i64,
+    pub aggregated_health_state: HealthState,
+    pub health_events: Vec<HealthEvent>,
+    pub unhealthy_evaluations: Vec<HealthEvaluation>,
+}
+
+impl TryFrom<&FABRIC_REPLICA_HEALTH> for ReplicaHealth {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_REPLICA_HEALTH) -> Result<Self, Self::Error> {
+        let kind = ServiceKind::from(value.Kind);
+        match kind {
+            ServiceKind::Stateful => {
+                let replica =
+                    unsafe { &*(value.Value as *const FABRIC_STATEFUL_SERVICE_REPLICA_HEALTH) };
+                let ex1 = unsafe {
+                    (replica.Reserved as *const FABRIC_STATEFUL_SERVICE_REPLICA_HEALTH_EX1).as_ref()
+                };
+                Ok(Self {
+                    kind,
+                    partition_id: replica.PartitionId,
+                    replica_or_instance_id: replica.ReplicaId,
+                    aggregated_health_state: HealthState::from(replica.AggregatedHealthState),
+                    health_events: unsafe { read_list!(replica.HealthEvents)? },
+                    unhealthy_evaluations: match ex1 {
+                        Some(ex1) => unsafe { read_list!(ex1.UnhealthyEvaluations)? },
+                        None => vec![],
+                    },
+                })
+            }
+            ServiceKind::Stateless => {
+                let instance =
+                    unsafe { &*(value.Value as *const FABRIC_STATELESS_SERVICE_INSTANCE_HEALTH) };
+                let ex1 = unsafe {
+                    (instance.Reserved as *const FABRIC_STATELESS_SERVICE_INSTANCE_HEALTH_EX1)
+                        .as_ref()
+                };
+                Ok(Self {
+                    kind,
+                    partition_id: instance.PartitionId,
+                    replica_or_instance_id: instance.InstanceId,
+                    aggregated_health_state: HealthState::from(instance.AggregatedHealthState),
+                    health_events: unsafe { read_list!(instance.HealthEvents)? },
+                    unhealthy_evaluations: match ex1 {
+                        Some(ex1) => unsafe { read_list!(ex1.UnhealthyEvaluations)? },
+                        None => vec![],
+                    },
+                })
+            }
+            _ => Err(Error::InvalidServiceKind),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HealthClient {
+    client: AgileRef<IFabricHealthClient4>,
+}
+
+impl MakeClient for HealthClient {
+    type Interface = IFabricHealthClient4;
+
+    fn make(client: Self::Interface) -> Result<Self, Error> {
+        Ok(Self {
+            client: AgileRef::new(client.cast()?)?,
+        })
+    }
+}
+
+impl HealthClient {
+    pub fn new(client: IFabricHealthClient4) -> Result<Self, Error> {
+        Ok(Self {
+            client: AgileRef::new(client.cast()?)?,
+        })
+    }
+
+    /// Queues `report` with the health client. Reports are sent to the
+    /// health store in batches unless `send_options` asks for an immediate
+    /// send; an `Ok` result only means the report was accepted locally.
+    pub fn report_health(
+        &self,
+        report: &HealthReport,
+        send_options: Option<HealthReportSendOptions>,
+    ) -> Result<(), Error> {
+        report.validate()?;
+        let client = self.client.resolve()?;
+        let mut arena = Arena::new();
+        let report = report.to_raw(&mut arena);
+
+        match send_options {
+            Some(send_options) => unsafe { client.ReportHealth2(&report, &send_options.to_raw()) }?,
+            None => unsafe { client.ReportHealth(&report) }?,
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_cluster_health(
+        &self,
+        description: &ClusterHealthQueryDescription,
+        timeout_ms: u32,
+    ) -> Result<ClusterHealth, Error> {
+        description.validate()?;
+        run_with_retry("get_cluster_health", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx = try_get_cluster_health(client.resolve()?, description, timeout_ms)?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("GetClusterHealth"))?
+            }
+        })
+        .await
+    }
+
+    pub async fn get_node_health(
+        &self,
+        description: &NodeHealthQueryDescription,
+        timeout_ms: u32,
+    ) -> Result<NodeHealth, Error> {
+        description.validate()?;
+        run_with_retry("get_node_health", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx = try_get_node_health(client.resolve()?, description, timeout_ms)?;
+                rx.recv().await.ok_or(Error::Abandoned("GetNodeHealth"))?
+            }
+        })
+        .await
+    }
+
+    pub async fn get_application_health(
+        &self,
+        description: &ApplicationHealthQueryDescription,
+        timeout_ms: u32,
+    ) -> Result<ApplicationHealth, Error> {
+        description.validate()?;
+        run_with_retry("get_application_health", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx =
+                    try_get_application_health(client.resolve()?, description, timeout_ms)?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("GetApplicationHealth"))?
+            }
+        })
+        .await
+    }
+
+    pub async fn get_service_health(
+        &self,
+        description: &ServiceHealthQueryDescription,
+        timeout_ms: u32,
+    ) -> Result<ServiceHealth, Error> {
+        description.validate()?;
+        run_with_retry("get_service_health", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx = try_get_service_health(client.resolve()?, description, timeout_ms)?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("GetServiceHealth"))?
+            }
+        })
+        .await
+    }
+
+    pub async fn get_partition_health(
+        &self,
+        description: &PartitionHealthQueryDescription,
+        timeout_ms: u32,
+    ) -> Result<PartitionHealth, Error> {
+        description.validate()?;
+        run_with_retry("get_partition_health", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx = try_get_partition_health(client.resolve()?, description, timeout_ms)?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("GetPartitionHealth"))?
+            }
+        })
+        .await
+    }
+
+    pub async fn get_replica_health(
+        &self,
+        description: &ReplicaHealthQueryDescription,
+        timeout_ms: u32,
+    ) -> Result<ReplicaHealth, Error> {
+        description.validate()?;
+        run_with_retry("get_replica_health", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx = try_get_replica_health(client.resolve()?, description, timeout_ms)?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("GetReplicaHealth"))?
+            }
+        })
+        .await
+    }
+
+    /// Fetches the cluster's health together with the nodes, applications
+    /// and descendants selected by `description`'s filters, in one call.
+    pub async fn get_cluster_health_chunk(
+        &self,
+        description: &ClusterHealthChunkQueryDescription,
+        timeout_ms: u32,
+    ) -> Result<ClusterHealthChunk, Error> {
+        description.validate()?;
+        run_with_retry("get_cluster_health_chunk", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx =
+                    try_get_cluster_health_chunk(client.resolve()?, description, timeout_ms)?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("GetClusterHealthChunk"))?
+            }
+        })
+        .await
+    }
+}
+
+fn try_get_cluster_health(
+    client: IFabricHealthClient4,
+    description: &ClusterHealthQueryDescription,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<ClusterHealth, Error>>, Error> {
+    let mut arena = Arena::new();
+    let description = description.to_raw(&mut arena);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginGetClusterHealth2(&description, timeout_ms, Some(callback))
+        },
+        move |context| {
+            let res = unsafe { end_client.EndGetClusterHealth2(context) }?;
+            ClusterHealth::try_from(unsafe { &*res.get_ClusterHealth() })
+        },
+    )
+}
+
+fn try_get_node_health(
+    client: IFabricHealthClient4,
+    description: &NodeHealthQueryDescription,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<NodeHealth, Error>>, Error> {
+    let mut arena = Arena::new();
+    let description = description.to_raw(&mut arena);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe { client.BeginGetNodeHealth2(&description, timeout_ms, Some(callback)) },
+        move |context| {
+            let res = unsafe { end_client.EndGetNodeHealth2(context) }?;
+            NodeHealth::try_from(unsafe { &*res.get_NodeHealth() })
+        },
+    )
+}
+
+fn try_get_application_health(
+    client: IFabricHealthClient4,
+    description: &ApplicationHealthQueryDescription,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<ApplicationHealth, Error>>, Error> {
+    let mut arena = Arena::new();
+    let description = description.to_raw(&mut arena);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginGetApplicationHealth2(&description, timeout_ms, Some(callback))
+        },
+        move |context| {
+            let res = unsafe { end_client.EndGetApplicationHealth2(context) }?;
+            ApplicationHealth::try_from(unsafe { &*res.get_ApplicationHealth() })
+        },
+    )
+}
+
+fn try_get_service_health(
+    client: IFabricHealthClient4,
+    description: &ServiceHealthQueryDescription,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<ServiceHealth, Error>>, Error> {
+    let mut arena = Arena::new();
+    let description = description.to_raw(&mut arena);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginGetServiceHealth2(&description, timeout_ms, Some(callback))
+        },
+        move |context| {
+            let res = unsafe { end_client.EndGetServiceHealth2(context) }?;
+            ServiceHealth::try_from(unsafe { &*res.get_ServiceHealth() })
+        },
+    )
+}
+
+fn try_get_partition_health(
+    client: IFabricHealthClient4,
+    description: &PartitionHealthQueryDescription,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<PartitionHealth, Error>>, Error> {
+    let mut arena = Arena::new();
+    let description = description.to_raw(&mut arena);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginGetPartitionHealth2(&description, timeout_ms, Some(callback))
+        },
+        move |context| {
+            let res = unsafe { end_client.EndGetPartitionHealth2(context) }?;
+            PartitionHealth::try_from(unsafe { &*res.get_PartitionHealth() })
+        },
+    )
+}
+
+fn try_get_replica_health(
+    client: IFabricHealthClient4,
+    description: &ReplicaHealthQueryDescription,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<ReplicaHealth, Error>>, Error> {
+    let mut arena = Arena::new();
+    let description = description.to_raw(&mut arena);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginGetReplicaHealth2(&description, timeout_ms, Some(callback))
+        },
+        move |context| {
+            let res = unsafe { end_client.EndGetReplicaHealth2(context) }?;
+            ReplicaHealth::try_from(unsafe { &*res.get_ReplicaHealth() })
+        },
+    )
+}
+
+fn try_get_cluster_health_chunk(
+    client: IFabricHealthClient4,
+    description: &ClusterHealthChunkQueryDescription,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<ClusterHealthChunk, Error>>, Error> {
+    let mut arena = Arena::new();
+    let description = description.to_raw(&mut arena);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginGetClusterHealthChunk(&description, timeout_ms, Some(callback))
+        },
+        move |context| {
+            let res = unsafe { end_client.EndGetClusterHealthChunk(context) }?;
+            ClusterHealthChunk::try_from(unsafe { &*res.get_ClusterHealthChunk() })
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn health_state_filters_combine() {
+        assert_eq!(HealthStateFilter::default(), HealthStateFilter::DEFAULT);
+        assert_eq!(HealthStateFilter::DEFAULT.bits(), 0);
+        assert_eq!(
+            (HealthStateFilter::WARNING | HealthStateFilter::ERROR).bits(),
+            FABRIC_HEALTH_STATE_FILTER_WARNING.0 as u32 | FABRIC_HEALTH_STATE_FILTER_ERROR.0 as u32
+        );
+        assert_eq!(
+            HealthStateFilter::OK | HealthStateFilter::OK,
+            HealthStateFilter::OK
+        );
+        assert_eq!(
+            (HealthStateFilter::ALL | HealthStateFilter::ERROR).bits(),
+            FABRIC_HEALTH_STATE_FILTER_ALL.0 as u32
+        );
+    }
+
+    #[test]
+    fn health_queries_validate_their_policies() {
+        let bad_cluster_policy = ClusterHealthPolicy::new().max_percent_unhealthy_nodes(101);
+        let bad_application_policy =
+            ApplicationHealthPolicy::new().max_percent_unhealthy_deployed_applications(101);
+        let partition_id = GUID::zeroed();
+
+        assert!(ClusterHealthQueryDescription::new().validate().is_ok());
+        assert!(NodeHealthQueryDescription::new("_Node_0")
+            .validate()
+            .is_ok());
+        assert!(ReplicaHealthQueryDescription::new(partition_id, 1)
+            .health_policy(ApplicationHealthPolicy::new())
+            .validate()
+            .is_ok());
+
+        let invalid = [
+            ClusterHealthQueryDescription::new()
+                .health_policy(bad_cluster_policy.clone())
+                .validate(),
+            ClusterHealthQueryDescription::new()
+                .application_health_policy("fabric:/app", bad_application_policy.clone())
+                .validate(),
+            NodeHealthQueryDescription::new("_Node_0")
+                .health_policy(bad_cluster_policy)
+                .validate(),
+            ApplicationHealthQueryDescription::new("fabric:/app")
+                .health_policy(bad_application_policy.clone())
+                .validate(),
+            ServiceHealthQueryDescription::new("fabric:/app/web")
+                .health_policy(bad_application_policy.clone())
+                .validate(),
+            PartitionHealthQueryDescription::new(partition_id)
+                .health_policy(bad_application_policy.clone())
+                .validate(),
+            ReplicaHealthQueryDescription::new(partition_id, 1)
+                .health_policy(bad_application_policy)
+                .validate(),
+        ];
+        for result in invalid {
+            assert!(matches!(result, Err(Error::InvalidHealthPolicy(_))));
+        }
+    }
+
+    #[test]
+    fn cluster_health_query_to_raw() {
+        let description = ClusterHealthQueryDescription::new()
+            .nodes_filter(HealthStateFilter::ERROR)
+            .applications_filter(HealthStateFilter::WARNING | HealthStateFilter::ERROR);
+        let mut arena = Arena::new();
+        let raw = description.to_raw(&mut arena);
+
+        assert!(raw.HealthPolicy.is_null());
+        assert!(raw.ApplicationHealthPolicyMap.is_null());
+        assert_eq!(unsafe { &*raw.EventsFilter }.HealthStateFilter, 0);
+        assert_eq!(
+            unsafe { &*raw.NodesFilter }.HealthStateFilter,
+            HealthStateFilter::ERROR.bits()
+        );
+        assert_eq!(
+            unsafe { &*raw.ApplicationsFilter }.HealthStateFilter,
+            description.applications_filter.bits()
+        );
+
+        let raw = description
+            .health_policy(ClusterHealthPolicy::new())
+            .to_raw(&mut arena);
+        assert!(!raw.HealthPolicy.is_null());
+    }
+
+    #[test]
+    fn invalid_health_reports_are_rejected() {
+        let information = HealthInformation::new("watchdog", "Disk", HealthState::Warning);
+        assert!(information.validate().is_ok());
+
+        let description = "x".repeat(MAX_HEALTH_DESCRIPTION_LENGTH);
+        assert!(information
+            .clone()
+            .description(&description)
+            .validate()
+            .is_ok());
+
+        let invalid = [
+            information.clone().description(&(description + "x")),
+            HealthInformation::new("", "Disk", HealthState::Ok),
+            HealthInformation::new("watchdog", "", HealthState::Ok),
+            HealthInformation::new("watchdog", "Disk", HealthState::Unknown),
+            information.clone().time_to_live(Duration::from_millis(500)),
+        ];
+        for information in invalid {
+            let report = HealthReport::Node {
+                node_name: "_Node_0".to_string(),
+                health_information: information,
+            };
+            assert!(matches!(
+                report.validate(),
+                Err(Error::InvalidHealthReport(_))
+            ));
+        }
+    }
+
+    fn information() -> HealthInformation {
+        HealthInformation::new("watchdog", "Disk", HealthState::Warning)
+            .time_to_live(Duration::from_secs(300))
+            .description("disk is 90% full")
+    }
+
+    fn string(value: *const u16) -> String {
+        unsafe { wide_to_string(value) }.unwrap()
+    }
+
+    fn assert_information(information: *const FABRIC_HEALTH_INFORMATION) {
+        let information = unsafe { &*information };
+        assert_eq!(string(information.SourceId.0), "watchdog");
+        assert_eq!(string(information.Property.0), "Disk");
+        assert_eq!(HealthState::from(information.State), HealthState::Warning);
+        assert_eq!(information.TimeToLiveSeconds, 300);
+        assert_eq!(string(information.Description.0), "disk is 90% full");
+        assert_eq!(
+            information.SequenceNumber,
+            FABRIC_AUTO_SEQUENCE_NUMBER as i64
+        );
+    }
+
+    #[test]
+    fn cluster_report_to_raw() {
+        let mut arena = Arena::new();
+        let raw = HealthReport::Cluster {
+            health_information: information(),
+        }
+        .to_raw(&mut arena);
+
+        assert_eq!(raw.Kind, FABRIC_HEALTH_REPORT_KIND_CLUSTER);
+        let report = unsafe { &*(raw.Value as *const FABRIC_CLUSTER_HEALTH_REPORT) };
+        assert_information(report.HealthInformation);
+    }
+
+    #[test]
+    fn node_report_to_raw() {
+        let mut arena = Arena::new();
+        let raw = HealthReport::Node {
+            node_name: "_Node_0".to_string(),
+            health_information: information(),
+        }
+        .to_raw(&mut arena);
+
+        assert_eq!(raw.Kind, FABRIC_HEALTH_REPORT_KIND_NODE);
+        let report = unsafe { &*(raw.Value as *const FABRIC_NODE_HEALTH_REPORT) };
+        assert_eq!(string(report.NodeName.0), "_Node_0");
+        assert_information(report.HealthInformation);
+    }
+
+    #[test]
+    fn application_report_to_raw() {
+        let mut arena = Arena::new();
+        let raw = HealthReport::Application {
+            application_name: "fabric:/app".to_string(),
+            health_information: information(),
+        }
+        .to_raw(&mut arena);
+
+        assert_eq!(raw.Kind, FABRIC_HEALTH_REPORT_KIND_APPLICATION);
+        let report = unsafe { &*(raw.Value as *const FABRIC_APPLICATION_HEALTH_REPORT) };
+        assert_eq!(string(report.ApplicationName), "fabric:/app");
+        assert_information(report.HealthInformation);
+    }
+
+    #[test]
+    fn deployed_application_report_to_raw() {
+        let mut arena = Arena::new();
+        let raw = HealthReport::DeployedApplication {
+            application_name: "fabric:/app".to_string(),
+            node_name: "_Node_0".to_string(),
+            health_information: information(),
+        }
+        .to_raw(&mut arena);
+
+        assert_eq!(raw.Kind, FABRIC_HEALTH_REPORT_KIND_DEPLOYED_APPLICATION);
+        let report = unsafe { &*(raw.Value as *const FABRIC_DEPLOYED_APPLICATION_HEALTH_REPORT) };
+        assert_eq!(string(report.ApplicationName), "fabric:/app");
+        assert_eq!(string(report.NodeName.0), "_Node_0");
+        assert_information(report.HealthInformation);
+    }
+
+    #[test]
+    fn deployed_service_package_report_to_raw() {
+        let mut arena = Arena::new();
+        let raw = HealthReport::DeployedServicePackage {
+            application_name: "fabric:/app".to_string(),
+            service_manifest_name: "WebPkg".to_string(),
+            node_name: "_Node_0".to_string(),
+            health_information: information(),
+        }
+        .to_raw(&mut arena);
+
+        assert_eq!(raw.Kind, FABRIC_HEALTH_REPORT_KIND_DEPLOYED_SERVICE_PACKAGE);
+        let report =
+            unsafe { &*(raw.Value as *const FABRIC_DEPLOYED_SERVICE_PACKAGE_HEALTH_REPORT) };
+        assert_eq!(string(report.ApplicationName), "fabric:/app");
+        assert_eq!(string(report.ServiceManifestName.0), "WebPkg");
+        assert_eq!(string(report.NodeName.0), "_Node_0");
+        assert_information(report.HealthInformation);
+    }
+
+    #[test]
+    fn service_report_to_raw() {
+        let mut arena = Arena::new();
+        let raw = HealthReport::Service {
+            service_name: "fabric:/app/web".to_string(),
+            health_information: information(),
+        }
+        .to_raw(&mut arena);
+
+        assert_eq!(raw.Kind, FABRIC_HEALTH_REPORT_KIND_SERVICE);
+        let report = unsafe { &*(raw.Value as *const FABRIC_SERVICE_HEALTH_REPORT) };
+        assert_eq!(string(report.ServiceName), "fabric:/app/web");
+        assert_information(report.HealthInformation);
+    }
+
+    #[test]
+    fn partition_report_to_raw() {
+        let mut arena = Arena::new();
+        let partition_id = GUID::from_u128(0x42);
+        let raw = HealthReport::Partition {
+            partition_id,
+            health_information: information(),
+        }
+        .to_raw(&mut arena);
+
+        assert_eq!(raw.Kind, FABRIC_HEALTH_REPORT_KIND_PARTITION);
+        let report = unsafe { &*(raw.Value as *const FABRIC_PARTITION_HEALTH_REPORT) };
+        assert_eq!(report.PartitionId, partition_id);
+        assert_information(report.HealthInformation);
+    }
+
+    #[test]
+    fn stateful_replica_report_to_raw() {
+        let mut arena = Arena::new();
+        let partition_id = GUID::from_u128(0x42);
+        let raw = HealthReport::StatefulReplica {
+            partition_id,
+            replica_id: 7,
+            health_information: information(),
+        }
+        .to_raw(&mut arena);
+
+        assert_eq!(raw.Kind, FABRIC_HEALTH_REPORT_KIND_STATEFUL_SERVICE_REPLICA);
+        let report =
+            unsafe { &*(raw.Value as *const FABRIC_STATEFUL_SERVICE_REPLICA_HEALTH_REPORT) };
+        assert_eq!(report.PartitionId, partition_id);
+        assert_eq!(report.ReplicaId, 7);
+        assert_information(report.HealthInformation);
+    }
+
+    #[test]
+    fn stateless_instance_report_to_raw() {
+        let mut arena = Arena::new();
+        let partition_id = GUID::from_u128(0x42);
+        let raw = HealthReport::StatelessInstance {
+            partition_id,
+            instance_id: 9,
+            health_information: information(),
+        }
+        .to_raw(&mut arena);
+
+        assert_eq!(
+            raw.Kind,
+            FABRIC_HEALTH_REPORT_KIND_STATELESS_SERVICE_INSTANCE
+        );
+        let report =
+            unsafe { &*(raw.Value as *const FABRIC_STATELESS_SERVICE_INSTANCE_HEALTH_REPORT) };
+        assert_eq!(report.PartitionId, partition_id);
+        assert_eq!(report.InstanceId, 9);
+        assert_information(report.HealthInformation);
+    }
+
+    #[test]
+    fn send_options_to_raw() {
+        let raw = HealthReportSendOptions { immediate: true }.to_raw();
+        assert!(raw.Immediate.as_bool());
+        assert!(raw.Reserved.is_null());
+
+        let raw = HealthReportSendOptions::default().to_raw();
+        assert!(!raw.Immediate.as_bool());
+    }
+
+    #[test]
+    fn health_event_round_trips_and_rejects_null_information() {
+        let mut arena = Arena::new();
+        let raw = FABRIC_HEALTH_EVENT {
+            HealthInformation: information().to_raw(&mut arena),
+            IsExpired: true.into(),
+            ..Default::default()
+        };
+        let event = HealthEvent::try_from(&raw).unwrap();
+        assert_eq!(event.health_information, information());
+        assert!(event.is_expired);
+        assert_eq!(event.source_utc_timestamp, None);
+        assert_eq!(event.last_ok_transition_at, None);
+
+        let raw = FABRIC_HEALTH_EVENT::default();
+        assert!(matches!(
+            HealthEvent::try_from(&raw),
+            Err(Error::NullPointer("FABRIC_HEALTH_EVENT"))
+        ));
+    }
+}
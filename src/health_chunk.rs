@@ -0,0 +1,1003 @@
+use std::{collections::BTreeMap, ptr};
+
+use windows::core::GUID;
+
+use crate::{
+    application_health_policy_map, arena::Arena, cluster_health_policy, error::Error, list_items,
+    wide_to_string, ApplicationHealthPolicy, ClusterHealthPolicy, HealthState, HealthStateFilter,
+    FABRIC_APPLICATION_HEALTH_STATE_CHUNK, FABRIC_APPLICATION_HEALTH_STATE_CHUNK_EX1,
+    FABRIC_APPLICATION_HEALTH_STATE_FILTER, FABRIC_APPLICATION_HEALTH_STATE_FILTER_LIST,
+    FABRIC_CLUSTER_HEALTH_CHUNK, FABRIC_CLUSTER_HEALTH_CHUNK_QUERY_DESCRIPTION,
+    FABRIC_DEPLOYED_APPLICATION_HEALTH_STATE_CHUNK,
+    FABRIC_DEPLOYED_APPLICATION_HEALTH_STATE_FILTER,
+    FABRIC_DEPLOYED_APPLICATION_HEALTH_STATE_FILTER_LIST,
+    FABRIC_DEPLOYED_SERVICE_PACKAGE_HEALTH_STATE_CHUNK,
+    FABRIC_DEPLOYED_SERVICE_PACKAGE_HEALTH_STATE_CHUNK_EX1,
+    FABRIC_DEPLOYED_SERVICE_PACKAGE_HEALTH_STATE_FILTER,
+    FABRIC_DEPLOYED_SERVICE_PACKAGE_HEALTH_STATE_FILTER_LIST, FABRIC_INVALID_REPLICA_ID,
+    FABRIC_NODE_HEALTH_STATE_CHUNK, FABRIC_NODE_HEALTH_STATE_FILTER,
+    FABRIC_NODE_HEALTH_STATE_FILTER_LIST, FABRIC_PARTITION_HEALTH_STATE_CHUNK,
+    FABRIC_PARTITION_HEALTH_STATE_FILTER, FABRIC_PARTITION_HEALTH_STATE_FILTER_LIST,
+    FABRIC_REPLICA_HEALTH_STATE_CHUNK, FABRIC_REPLICA_HEALTH_STATE_FILTER,
+    FABRIC_REPLICA_HEALTH_STATE_FILTER_LIST, FABRIC_SERVICE_HEALTH_STATE_CHUNK,
+    FABRIC_SERVICE_HEALTH_STATE_FILTER, FABRIC_SERVICE_HEALTH_STATE_FILTER_LIST,
+};
+
+/// Builds a `*_FILTER_LIST` from `filters`, or null when there aren't any.
+macro_rules! filter_list {
+    ($arena:expr, $filters:expr, $list:ident) => {
+        if $filters.is_empty() {
+            ptr::null()
+        } else {
+            let items = $filters
+                .iter()
+                .map(|filter| filter.to_raw($arena))
+                .collect::<Vec<_>>();
+            let count = items.len() as u32;
+            let items = $arena.slice(items);
+            $arena.alloc($list {
+                Count: count,
+                Items: items,
+            }) as *const _
+        }
+    };
+}
+
+/// Converts a possibly null `*_HEALTH_STATE_CHUNK_LIST`. Must be used inside
+/// an `unsafe` block.
+macro_rules! chunk_list {
+    ($list:expr) => {
+        match $list.as_ref() {
+            Some(list) => HealthStateChunkList {
+                items: list_items(list.Items, list.Count)
+                    .iter()
+                    .map(TryFrom::try_from)
+                    .collect::<Result<Vec<_>, Error>>()?,
+                total_count: list.TotalCount,
+            },
+            None => HealthStateChunkList::default(),
+        }
+    };
+}
+
+/// Describes a cluster health chunk query. Only the entities matched by a
+/// filter are returned; children are only returned when their parent's
+/// filter has child filters that match them. With no filters at all the
+/// result carries just the cluster's aggregated health state.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClusterHealthChunkQueryDescription {
+    pub health_policy: Option<ClusterHealthPolicy>,
+    /// Health policies for specific applications, keyed by application name.
+    pub application_health_policies: BTreeMap<String, ApplicationHealthPolicy>,
+    pub node_filters: Vec<NodeHealthStateFilter>,
+    pub application_filters: Vec<ApplicationHealthStateFilter>,
+}
+
+impl ClusterHealthChunkQueryDescription {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn health_policy(mut self, health_policy: ClusterHealthPolicy) -> Self {
+        self.health_policy = Some(health_policy);
+        self
+    }
+
+    pub fn application_health_policy(
+        mut self,
+        application_name: &str,
+        health_policy: ApplicationHealthPolicy,
+    ) -> Self {
+        self.application_health_policies
+            .insert(application_name.to_string(), health_policy);
+        self
+    }
+
+    pub fn node_filter(mut self, filter: NodeHealthStateFilter) -> Self {
+        self.node_filters.push(filter);
+        self
+    }
+
+    pub fn application_filter(mut self, filter: ApplicationHealthStateFilter) -> Self {
+        self.application_filters.push(filter);
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        if let Some(health_policy) = &self.health_policy {
+            health_policy.validate()?;
+        }
+        self.application_health_policies
+            .values()
+            .try_for_each(ApplicationHealthPolicy::validate)
+    }
+
+    pub(crate) fn to_raw(
+        &self,
+        arena: &mut Arena,
+    ) -> FABRIC_CLUSTER_HEALTH_CHUNK_QUERY_DESCRIPTION {
+        let health_policy = cluster_health_policy(arena, self.health_policy.as_ref());
+        let application_health_policies = if self.application_health_policies.is_empty() {
+            ptr::null()
+        } else {
+            application_health_policy_map(arena, &self.application_health_policies)
+        };
+
+        FABRIC_CLUSTER_HEALTH_CHUNK_QUERY_DESCRIPTION {
+            ClusterHealthPolicy: health_policy,
+            ApplicationHealthPolicyMap: application_health_policies,
+            ApplicationFilters: filter_list!(
+                arena,
+                self.application_filters,
+                FABRIC_APPLICATION_HEALTH_STATE_FILTER_LIST
+            ),
+            NodeFilters: filter_list!(
+                arena,
+                self.node_filters,
+                FABRIC_NODE_HEALTH_STATE_FILTER_LIST
+            ),
+            Reserved: ptr::null_mut(),
+        }
+    }
+}
+
+/// Matches nodes by health state, or a single node by name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NodeHealthStateFilter {
+    pub health_state_filter: HealthStateFilter,
+    pub node_name: Option<String>,
+}
+
+impl NodeHealthStateFilter {
+    pub fn new(health_state_filter: HealthStateFilter) -> Self {
+        Self {
+            health_state_filter,
+            ..Default::default()
+        }
+    }
+
+    pub fn node_name(mut self, node_name: &str) -> Self {
+        self.node_name = Some(node_name.to_string());
+        self
+    }
+
+    fn to_raw(&self, arena: &mut Arena) -> FABRIC_NODE_HEALTH_STATE_FILTER {
+        FABRIC_NODE_HEALTH_STATE_FILTER {
+            HealthStateFilter: self.health_state_filter.bits(),
+            NodeNameFilter: arena.optional_wide(self.node_name.as_deref()),
+            Reserved: ptr::null_mut(),
+        }
+    }
+}
+
+/// Matches applications by health state, or a single application by name,
+/// and selects which of their services and deployed applications to return.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApplicationHealthStateFilter {
+    pub health_state_filter: HealthStateFilter,
+    pub application_name: Option<String>,
+    pub service_filters: Vec<ServiceHealthStateFilter>,
+    pub deployed_application_filters: Vec<DeployedApplicationHealthStateFilter>,
+}
+
+impl ApplicationHealthStateFilter {
+    pub fn new(health_state_filter: HealthStateFilter) -> Self {
+        Self {
+            health_state_filter,
+            ..Default::default()
+        }
+    }
+
+    pub fn application_name(mut self, application_name: &str) -> Self {
+        self.application_name = Some(application_name.to_string());
+        self
+    }
+
+    pub fn service_filter(mut self, filter: ServiceHealthStateFilter) -> Self {
+        self.service_filters.push(filter);
+        self
+    }
+
+    pub fn deployed_application_filter(
+        mut self,
+        filter: DeployedApplicationHealthStateFilter,
+    ) -> Self {
+        self.deployed_application_filters.push(filter);
+        self
+    }
+
+    fn to_raw(&self, arena: &mut Arena) -> FABRIC_APPLICATION_HEALTH_STATE_FILTER {
+        FABRIC_APPLICATION_HEALTH_STATE_FILTER {
+            HealthStateFilter: self.health_state_filter.bits(),
+            ApplicationNameFilter: self
+                .application_name
+                .as_deref()
+                .map_or(ptr::null_mut(), |name| arena.uri(name)),
+            ServiceFilters: filter_list!(
+                arena,
+                self.service_filters,
+                FABRIC_SERVICE_HEALTH_STATE_FILTER_LIST
+            ),
+            DeployedApplicationFilters: filter_list!(
+                arena,
+                self.deployed_application_filters,
+                FABRIC_DEPLOYED_APPLICATION_HEALTH_STATE_FILTER_LIST
+            ),
+            Reserved: ptr::null_mut(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServiceHealthStateFilter {
+    pub health_state_filter: HealthStateFilter,
+    pub service_name: Option<String>,
+    pub partition_filters: Vec<PartitionHealthStateFilter>,
+}
+
+impl ServiceHealthStateFilter {
+    pub fn new(health_state_filter: HealthStateFilter) -> Self {
+        Self {
+            health_state_filter,
+            ..Default::default()
+        }
+    }
+
+    pub fn service_name(mut self, service_name: &str) -> Self {
+        self.service_name = Some(service_name.to_string());
+        self
+    }
+
+    pub fn partition_filter(mut self, filter: PartitionHealthStateFilter) -> Self {
+        self.partition_filters.push(filter);
+        self
+    }
+
+    fn to_raw(&self, arena: &mut Arena) -> FABRIC_SERVICE_HEALTH_STATE_FILTER {
+        FABRIC_SERVICE_HEALTH_STATE_FILTER {
+            HealthStateFilter: self.health_state_filter.bits(),
+            ServiceNameFilter: self
+                .service_name
+                .as_deref()
+                .map_or(ptr::null_mut(), |name| arena.uri(name)),
+            PartitionFilters: filter_list!(
+                arena,
+                self.partition_filters,
+                FABRIC_PARTITION_HEALTH_STATE_FILTER_LIST
+            ),
+            Reserved: ptr::null_mut(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PartitionHealthStateFilter {
+    pub health_state_filter: HealthStateFilter,
+    pub partition_id: Option<GUID>,
+    pub replica_filters: Vec<ReplicaHealthStateFilter>,
+}
+
+impl PartitionHealthStateFilter {
+    pub fn new(health_state_filter: HealthStateFilter) -> Self {
+        Self {
+            health_state_filter,
+            ..Default::default()
+        }
+    }
+
+    pub fn partition_id(mut self, partition_id: GUID) -> Self {
+        self.partition_id = Some(partition_id);
+        self
+    }
+
+    pub fn replica_filter(mut self, filter: ReplicaHealthStateFilter) -> Self {
+        self.replica_filters.push(filter);
+        self
+    }
+
+    fn to_raw(&self, arena: &mut Arena) -> FABRIC_PARTITION_HEALTH_STATE_FILTER {
+        FABRIC_PARTITION_HEALTH_STATE_FILTER {
+            HealthStateFilter: self.health_state_filter.bits(),
+            PartitionIdFilter: self.partition_id.unwrap_or_else(GUID::zeroed),
+            ReplicaFilters: filter_list!(
+                arena,
+                self.replica_filters,
+                FABRIC_REPLICA_HEALTH_STATE_FILTER_LIST
+            ),
+            Reserved: ptr::null_mut(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplicaHealthStateFilter {
+    pub health_state_filter: HealthStateFilter,
+    pub replica_or_instance_id: Option<i64>,
+}
+
+impl ReplicaHealthStateFilter {
+    pub fn new(health_state_filter: HealthStateFilter) -> Self {
+        Self {
+            health_state_filter,
+            ..Default::default()
+        }
+    }
+
+    pub fn replica_or_instance_id(mut self, replica_or_instance_id: i64) -> Self {
+        self.replica_or_instance_id = Some(replica_or_instance_id);
+        self
+    }
+
+    fn to_raw(&self, _arena: &mut Arena) -> FABRIC_REPLICA_HEALTH_STATE_FILTER {
+        FABRIC_REPLICA_HEALTH_STATE_FILTER {
+            HealthStateFilter: self.health_state_filter.bits(),
+            ReplicaOrInstanceIdFilter: self
+                .replica_or_instance_id
+                .unwrap_or(FABRIC_INVALID_REPLICA_ID as i64),
+            Reserved: ptr::null_mut(),
+        }
+    }
+}
+
+/// Matches the nodes an application is deployed on, by health state or by
+/// node name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeployedApplicationHealthStateFilter {
+    pub health_state_filter: HealthStateFilter,
+    pub node_name: Option<String>,
+    pub deployed_service_package_filters: Vec<DeployedServicePackageHealthStateFilter>,
+}
+
+impl DeployedApplicationHealthStateFilter {
+    pub fn new(health_state_filter: HealthStateFilter) -> Self {
+        Self {
+            health_state_filter,
+            ..Default::default()
+        }
+    }
+
+    pub fn node_name(mut self, node_name: &str) -> Self {
+        self.node_name = Some(node_name.to_string());
+        self
+    }
+
+    pub fn deployed_service_package_filter(
+        mut self,
+        filter: DeployedServicePackageHealthStateFilter,
+    ) -> Self {
+        self.deployed_service_package_filters.push(filter);
+        self
+    }
+
+    fn to_raw(&self, arena: &mut Arena) -> FABRIC_DEPLOYED_APPLICATION_HEALTH_STATE_FILTER {
+        FABRIC_DEPLOYED_APPLICATION_HEALTH_STATE_FILTER {
+            HealthStateFilter: self.health_state_filter.bits(),
+            NodeNameFilter: arena.optional_wide(self.node_name.as_deref()),
+            DeployedServicePackageFilters: filter_list!(
+                arena,
+                self.deployed_service_package_filters,
+                FABRIC_DEPLOYED_SERVICE_PACKAGE_HEALTH_STATE_FILTER_LIST
+            ),
+            Reserved: ptr::null_mut(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeployedServicePackageHealthStateFilter {
+    pub health_state_filter: HealthStateFilter,
+    pub service_manifest_name: Option<String>,
+}
+
+impl DeployedServicePackageHealthStateFilter {
+    pub fn new(health_state_filter: HealthStateFilter) -> Self {
+        Self {
+            health_state_filter,
+            ..Default::default()
+        }
+    }
+
+    pub fn service_manifest_name(mut self, service_manifest_name: &str) -> Self {
+        self.service_manifest_name = Some(service_manifest_name.to_string());
+        self
+    }
+
+    fn to_raw(&self, arena: &mut Arena) -> FABRIC_DEPLOYED_SERVICE_PACKAGE_HEALTH_STATE_FILTER {
+        FABRIC_DEPLOYED_SERVICE_PACKAGE_HEALTH_STATE_FILTER {
+            HealthStateFilter: self.health_state_filter.bits(),
+            ServiceManifestNameFilter: arena.optional_wide(self.service_manifest_name.as_deref()),
+            Reserved: ptr::null_mut(),
+        }
+    }
+}
+
+/// The chunks that matched a filter. `total_count` is the number of children
+/// the parent has, matched or not.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthStateChunkList<T> {
+    pub items: Vec<T>,
+    pub total_count: u32,
+}
+
+impl<T> Default for HealthStateChunkList<T> {
+    fn default() -> Self {
+        Self {
+            items: vec![],
+            total_count: 0,
+        }
+    }
+}
+
+/// The part of the cluster's health selected by a
+/// `ClusterHealthChunkQueryDescription`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClusterHealthChunk {
+    pub health_state: HealthState,
+    pub node_health_state_chunks: HealthStateChunkList<NodeHealthStateChunk>,
+    pub application_health_state_chunks: HealthStateChunkList<ApplicationHealthStateChunk>,
+}
+
+impl TryFrom<&FABRIC_CLUSTER_HEALTH_CHUNK> for ClusterHealthChunk {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_CLUSTER_HEALTH_CHUNK) -> Result<Self, Self::Error> {
+        Ok(Self {
+            health_state: HealthState::from(value.HealthState),
+            node_health_state_chunks: unsafe { chunk_list!(value.NodeHealthStateChunks) },
+            application_health_state_chunks: unsafe {
+                chunk_list!(value.ApplicationHealthStateChunks)
+            },
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeHealthStateChunk {
+    pub node_name: String,
+    pub health_state: HealthState,
+}
+
+impl TryFrom<&FABRIC_NODE_HEALTH_STATE_CHUNK> for NodeHealthStateChunk {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_NODE_HEALTH_STATE_CHUNK) -> Result<Self, Self::Error> {
+        Ok(Self {
+            node_name: unsafe { wide_to_string(value.NodeName.0)? },
+            health_state: HealthState::from(value.HealthState),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApplicationHealthStateChunk {
+    pub application_name: String,
+    pub application_type_name: Option<String>,
+    pub health_state: HealthState,
+    pub service_health_state_chunks: HealthStateChunkList<ServiceHealthStateChunk>,
+    pub deployed_application_health_state_chunks:
+        HealthStateChunkList<DeployedApplicationHealthStateChunk>,
+}
+
+impl TryFrom<&FABRIC_APPLICATION_HEALTH_STATE_CHUNK> for ApplicationHealthStateChunk {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_APPLICATION_HEALTH_STATE_CHUNK) -> Result<Self, Self::Error> {
+        let ex1 = unsafe {
+            (value.Reserved as *const FABRIC_APPLICATION_HEALTH_STATE_CHUNK_EX1).as_ref()
+        };
+        let application_type_name = match ex1 {
+            Some(ex1) => Some(unsafe { wide_to_string(ex1.ApplicationTypeName)? }),
+            None => None,
+        };
+
+        Ok(Self {
+            application_name: unsafe { wide_to_string(value.ApplicationName)? },
+            application_type_name,
+            health_state: HealthState::from(value.HealthState),
+            service_health_state_chunks: unsafe { chunk_list!(value.ServiceHealthStateChunks) },
+            deployed_application_health_state_chunks: unsafe {
+                chunk_list!(value.DeployedApplicationHealthStateChunks)
+            },
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceHealthStateChunk {
+    pub service_name: String,
+    pub health_state: HealthState,
+    pub partition_health_state_chunks: HealthStateChunkList<PartitionHealthStateChunk>,
+}
+
+impl TryFrom<&FABRIC_SERVICE_HEALTH_STATE_CHUNK> for ServiceHealthStateChunk {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_SERVICE_HEALTH_STATE_CHUNK) -> Result<Self, Self::Error> {
+        Ok(Self {
+            service_name: unsafe { wide_to_string(value.ServiceName)? },
+            health_state: HealthState::from(value.HealthState),
+            partition_health_state_chunks: unsafe { chunk_list!(value.PartitionHealthStateChunks) },
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartitionHealthStateChunk {
+    pub partition_id: GUID,
+    pub health_state: HealthState,
+    pub replica_health_state_chunks: HealthStateChunkList<ReplicaHealthStateChunk>,
+}
+
+impl TryFrom<&FABRIC_PARTITION_HEALTH_STATE_CHUNK> for PartitionHealthStateChunk {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_PARTITION_HEALTH_STATE_CHUNK) -> Result<Self, Self::Error> {
+        Ok(Self {
+            partition_id: value.PartitionId,
+            health_state: HealthState::from(value.HealthState),
+            replica_health_state_chunks: unsafe { chunk_list!(value.ReplicaHealthStateChunks) },
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplicaHealthStateChunk {
+    pub replica_or_instance_id: i64,
+    pub health_state: HealthState,
+}
+
+impl TryFrom<&FABRIC_REPLICA_HEALTH_STATE_CHUNK> for ReplicaHealthStateChunk {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_REPLICA_HEALTH_STATE_CHUNK) -> Result<Self, Self::Error> {
+        Ok(Self {
+            replica_or_instance_id: value.ReplicaOrInstanceId,
+            health_state: HealthState::from(value.HealthState),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeployedApplicationHealthStateChunk {
+    pub node_name: String,
+    pub health_state: HealthState,
+    pub deployed_service_package_health_state_chunks:
+        HealthStateChunkList<DeployedServicePackageHealthStateChunk>,
+}
+
+impl TryFrom<&FABRIC_DEPLOYED_APPLICATION_HEALTH_STATE_CHUNK>
+    for DeployedApplicationHealthStateChunk
+{
+    type Error = Error;
+
+    fn try_from(
+        value: &FABRIC_DEPLOYED_APPLICATION_HEALTH_STATE_CHUNK,
+    ) -> Result<Self, Self::Error> {
+        Ok(Self {
+            node_name: unsafe { wide_to_string(value.NodeName.0)? },
+            health_state: HealthState::from(value.HealthState),
+            deployed_service_package_health_state_chunks: unsafe {
+                chunk_list!(value.DeployedServicePackageHealthStateChunks)
+            },
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeployedServicePackageHealthStateChunk {
+    pub service_manifest_name: String,
+    pub service_package_activation_id: Option<String>,
+    pub health_state: HealthState,
+}
+
+impl TryFrom<&FABRIC_DEPLOYED_SERVICE_PACKAGE_HEALTH_STATE_CHUNK>
+    for DeployedServicePackageHealthStateChunk
+{
+    type Error = Error;
+
+    fn try_from(
+        value: &FABRIC_DEPLOYED_SERVICE_PACKAGE_HEALTH_STATE_CHUNK,
+    ) -> Result<Self, Self::Error> {
+        let ex1 = unsafe {
+            (value.Reserved as *const FABRIC_DEPLOYED_SERVICE_PACKAGE_HEALTH_STATE_CHUNK_EX1)
+                .as_ref()
+        };
+        let service_package_activation_id = match ex1 {
+            Some(ex1) => Some(unsafe { wide_to_string(ex1.ServicePackageActivationId.0)? }),
+            None => None,
+        };
+
+        Ok(Self {
+            service_manifest_name: unsafe { wide_to_string(value.ServiceManifestName.0)? },
+            service_package_activation_id,
+            health_state: HealthState::from(value.HealthState),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        FABRIC_APPLICATION_HEALTH_STATE_CHUNK_LIST,
+        FABRIC_DEPLOYED_APPLICATION_HEALTH_STATE_CHUNK_LIST,
+        FABRIC_DEPLOYED_SERVICE_PACKAGE_HEALTH_STATE_CHUNK_LIST,
+        FABRIC_NODE_HEALTH_STATE_CHUNK_LIST, FABRIC_PARTITION_HEALTH_STATE_CHUNK_LIST,
+        FABRIC_REPLICA_HEALTH_STATE_CHUNK_LIST, FABRIC_SERVICE_HEALTH_STATE_CHUNK_LIST,
+    };
+
+    fn string(value: *const u16) -> String {
+        unsafe { wide_to_string(value) }.unwrap()
+    }
+
+    #[test]
+    fn nested_filters_to_raw() {
+        let description = ClusterHealthChunkQueryDescription::new()
+            .node_filter(NodeHealthStateFilter::new(HealthStateFilter::ERROR).node_name("node1"))
+            .node_filter(NodeHealthStateFilter::new(HealthStateFilter::WARNING))
+            .application_filter(
+                ApplicationHealthStateFilter::new(HealthStateFilter::ALL)
+                    .application_name("fabric:/app")
+                    .service_filter(
+                        ServiceHealthStateFilter::new(HealthStateFilter::ERROR)
+                            .service_name("fabric:/app/svc")
+                            .partition_filter(
+                                PartitionHealthStateFilter::new(HealthStateFilter::ALL)
+                                    .partition_id(GUID::from_u128(7))
+                                    .replica_filter(
+                                        ReplicaHealthStateFilter::new(HealthStateFilter::OK)
+                                            .replica_or_instance_id(42),
+                                    )
+                                    .replica_filter(ReplicaHealthStateFilter::new(
+                                        HealthStateFilter::ERROR,
+                                    )),
+                            ),
+                    )
+                    .deployed_application_filter(
+                        DeployedApplicationHealthStateFilter::new(HealthStateFilter::WARNING)
+                            .node_name("node2")
+                            .deployed_service_package_filter(
+                                DeployedServicePackageHealthStateFilter::new(
+                                    HealthStateFilter::ALL,
+                                )
+                                .service_manifest_name("manifest"),
+                            ),
+                    ),
+            );
+        let mut arena = Arena::new();
+        let raw = description.to_raw(&mut arena);
+
+        assert!(raw.ClusterHealthPolicy.is_null());
+        assert!(raw.ApplicationHealthPolicyMap.is_null());
+
+        let nodes = unsafe { &*raw.NodeFilters };
+        let nodes = unsafe { list_items(nodes.Items, nodes.Count) };
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[0].HealthStateFilter, HealthStateFilter::ERROR.bits());
+        assert_eq!(string(nodes[0].NodeNameFilter.0), "node1");
+        assert_eq!(
+            nodes[1].HealthStateFilter,
+            HealthStateFilter::WARNING.bits()
+        );
+        assert!(nodes[1].NodeNameFilter.is_null());
+
+        let applications = unsafe { &*raw.ApplicationFilters };
+        let applications = unsafe { list_items(applications.Items, applications.Count) };
+        assert_eq!(applications.len(), 1);
+        let application = &applications[0];
+        assert_eq!(application.HealthStateFilter, HealthStateFilter::ALL.bits());
+        assert_eq!(string(application.ApplicationNameFilter), "fabric:/app");
+
+        let services = unsafe { &*application.ServiceFilters };
+        let services = unsafe { list_items(services.Items, services.Count) };
+        assert_eq!(services.len(), 1);
+        assert_eq!(string(services[0].ServiceNameFilter), "fabric:/app/svc");
+
+        let partitions = unsafe { &*services[0].PartitionFilters };
+        let partitions = unsafe { list_items(partitions.Items, partitions.Count) };
+        assert_eq!(partitions.len(), 1);
+        assert_eq!(partitions[0].PartitionIdFilter, GUID::from_u128(7));
+
+        let replicas = unsafe { &*partitions[0].ReplicaFilters };
+        let replicas = unsafe { list_items(replicas.Items, replicas.Count) };
+        assert_eq!(replicas.len(), 2);
+        assert_eq!(replicas[0].HealthStateFilter, HealthStateFilter::OK.bits());
+        assert_eq!(replicas[0].ReplicaOrInstanceIdFilter, 42);
+        assert_eq!(
+            replicas[1].ReplicaOrInstanceIdFilter,
+            FABRIC_INVALID_REPLICA_ID as i64
+        );
+
+        let deployed_applications = unsafe { &*application.DeployedApplicationFilters };
+        let deployed_applications =
+            unsafe { list_items(deployed_applications.Items, deployed_applications.Count) };
+        assert_eq!(deployed_applications.len(), 1);
+        assert_eq!(string(deployed_applications[0].NodeNameFilter.0), "node2");
+
+        let packages = unsafe { &*deployed_applications[0].DeployedServicePackageFilters };
+        let packages = unsafe { list_items(packages.Items, packages.Count) };
+        assert_eq!(packages.len(), 1);
+        assert_eq!(string(packages[0].ServiceManifestNameFilter.0), "manifest");
+    }
+
+    #[test]
+    fn empty_filters_are_null_lists() {
+        let description = ClusterHealthChunkQueryDescription::new()
+            .application_filter(ApplicationHealthStateFilter::new(HealthStateFilter::ALL))
+            .application_filter(
+                ApplicationHealthStateFilter::new(HealthStateFilter::ALL).service_filter(
+                    ServiceHealthStateFilter::new(HealthStateFilter::ALL)
+                        .partition_filter(PartitionHealthStateFilter::new(HealthStateFilter::ALL)),
+                ),
+            );
+        let mut arena = Arena::new();
+        let raw = description.to_raw(&mut arena);
+
+        assert!(raw.NodeFilters.is_null());
+        let applications = unsafe { &*raw.ApplicationFilters };
+        let applications = unsafe { list_items(applications.Items, applications.Count) };
+        assert!(applications[0].ApplicationNameFilter.is_null());
+        assert!(applications[0].ServiceFilters.is_null());
+        assert!(applications[0].DeployedApplicationFilters.is_null());
+
+        let services = unsafe { &*applications[1].ServiceFilters };
+        let services = unsafe { list_items(services.Items, services.Count) };
+        assert!(services[0].ServiceNameFilter.is_null());
+        let partitions = unsafe { &*services[0].PartitionFilters };
+        let partitions = unsafe { list_items(partitions.Items, partitions.Count) };
+        assert_eq!(partitions[0].PartitionIdFilter, GUID::zeroed());
+        assert!(partitions[0].ReplicaFilters.is_null());
+
+        let raw = ClusterHealthChunkQueryDescription::new().to_raw(&mut arena);
+        assert!(raw.ApplicationFilters.is_null());
+        assert!(raw.NodeFilters.is_null());
+    }
+
+    #[test]
+    fn chunk_lists_round_trip() {
+        let mut arena = Arena::new();
+
+        let nodes = vec![
+            FABRIC_NODE_HEALTH_STATE_CHUNK {
+                NodeName: arena.wide("node1"),
+                HealthState: HealthState::Ok.into(),
+                ..Default::default()
+            },
+            FABRIC_NODE_HEALTH_STATE_CHUNK {
+                NodeName: arena.wide("node2"),
+                HealthState: HealthState::Error.into(),
+                ..Default::default()
+            },
+        ];
+        let nodes = arena.slice(nodes);
+        let nodes = arena.alloc(FABRIC_NODE_HEALTH_STATE_CHUNK_LIST {
+            Count: 2,
+            Items: nodes,
+            TotalCount: 3,
+            ..Default::default()
+        });
+
+        let replicas = vec![FABRIC_REPLICA_HEALTH_STATE_CHUNK {
+            ReplicaOrInstanceId: 42,
+            HealthState: HealthState::Warning.into(),
+            ..Default::default()
+        }];
+        let replicas = arena.slice(replicas);
+        let replicas = arena.alloc(FABRIC_REPLICA_HEALTH_STATE_CHUNK_LIST {
+            Count: 1,
+            Items: replicas,
+            TotalCount: 3,
+            ..Default::default()
+        });
+        let partitions = vec![FABRIC_PARTITION_HEALTH_STATE_CHUNK {
+            PartitionId: GUID::from_u128(7),
+            HealthState: HealthState::Warning.into(),
+            ReplicaHealthStateChunks: replicas,
+            ..Default::default()
+        }];
+        let partitions = arena.slice(partitions);
+        let partitions = arena.alloc(FABRIC_PARTITION_HEALTH_STATE_CHUNK_LIST {
+            Count: 1,
+            Items: partitions,
+            TotalCount: 1,
+            ..Default::default()
+        });
+        let empty_partitions = arena.alloc(FABRIC_PARTITION_HEALTH_STATE_CHUNK_LIST {
+            Count: 0,
+            Items: ptr::null(),
+            TotalCount: 4,
+            ..Default::default()
+        });
+        let services = vec![
+            FABRIC_SERVICE_HEALTH_STATE_CHUNK {
+                ServiceName: arena.uri("fabric:/app/svc1"),
+                HealthState: HealthState::Warning.into(),
+                PartitionHealthStateChunks: partitions,
+                ..Default::default()
+            },
+            FABRIC_SERVICE_HEALTH_STATE_CHUNK {
+                ServiceName: arena.uri("fabric:/app/svc2"),
+                HealthState: HealthState::Ok.into(),
+                PartitionHealthStateChunks: empty_partitions,
+                ..Default::default()
+            },
+        ];
+        let services = arena.slice(services);
+        let services = arena.alloc(FABRIC_SERVICE_HEALTH_STATE_CHUNK_LIST {
+            Count: 2,
+            Items: services,
+            TotalCount: 2,
+            ..Default::default()
+        });
+
+        let package_ex1 = FABRIC_DEPLOYED_SERVICE_PACKAGE_HEALTH_STATE_CHUNK_EX1 {
+            ServicePackageActivationId: arena.wide("activation"),
+            ..Default::default()
+        };
+        let package_ex1 = arena.alloc(package_ex1);
+        let packages = vec![
+            FABRIC_DEPLOYED_SERVICE_PACKAGE_HEALTH_STATE_CHUNK {
+                ServiceManifestName: arena.wide("manifest1"),
+                HealthState: HealthState::Ok.into(),
+                Reserved: package_ex1 as *mut _,
+            },
+            FABRIC_DEPLOYED_SERVICE_PACKAGE_HEALTH_STATE_CHUNK {
+                ServiceManifestName: arena.wide("manifest2"),
+                HealthState: HealthState::Ok.into(),
+                ..Default::default()
+            },
+        ];
+        let packages = arena.slice(packages);
+        let packages = arena.alloc(FABRIC_DEPLOYED_SERVICE_PACKAGE_HEALTH_STATE_CHUNK_LIST {
+            Count: 2,
+            Items: packages,
+            TotalCount: 2,
+            ..Default::default()
+        });
+        let deployed_applications = vec![FABRIC_DEPLOYED_APPLICATION_HEALTH_STATE_CHUNK {
+            NodeName: arena.wide("node1"),
+            HealthState: HealthState::Ok.into(),
+            DeployedServicePackageHealthStateChunks: packages,
+            ..Default::default()
+        }];
+        let deployed_applications = arena.slice(deployed_applications);
+        let deployed_applications =
+            arena.alloc(FABRIC_DEPLOYED_APPLICATION_HEALTH_STATE_CHUNK_LIST {
+                Count: 1,
+                Items: deployed_applications,
+                TotalCount: 2,
+                ..Default::default()
+            });
+
+        let application_ex1 = FABRIC_APPLICATION_HEALTH_STATE_CHUNK_EX1 {
+            ApplicationTypeName: arena.uri("AppType"),
+            ..Default::default()
+        };
+        let application_ex1 = arena.alloc(application_ex1);
+        let applications = vec![FABRIC_APPLICATION_HEALTH_STATE_CHUNK {
+            ApplicationName: arena.uri("fabric:/app"),
+            HealthState: HealthState::Warning.into(),
+            ServiceHealthStateChunks: services,
+            DeployedApplicationHealthStateChunks: deployed_applications,
+            Reserved: application_ex1 as *mut _,
+        }];
+        let applications = arena.slice(applications);
+        let applications = arena.alloc(FABRIC_APPLICATION_HEALTH_STATE_CHUNK_LIST {
+            Count: 1,
+            Items: applications,
+            TotalCount: 1,
+            ..Default::default()
+        });
+
+        let raw = FABRIC_CLUSTER_HEALTH_CHUNK {
+            HealthState: HealthState::Error.into(),
+            NodeHealthStateChunks: nodes,
+            ApplicationHealthStateChunks: applications,
+            ..Default::default()
+        };
+        let chunk = ClusterHealthChunk::try_from(&raw).unwrap();
+
+        assert_eq!(
+            chunk,
+            ClusterHealthChunk {
+                health_state: HealthState::Error,
+                node_health_state_chunks: HealthStateChunkList {
+                    items: vec![
+                        NodeHealthStateChunk {
+                            node_name: "node1".to_string(),
+                            health_state: HealthState::Ok,
+                        },
+                        NodeHealthStateChunk {
+                            node_name: "node2".to_string(),
+                            health_state: HealthState::Error,
+                        },
+                    ],
+                    total_count: 3,
+                },
+                application_health_state_chunks: HealthStateChunkList {
+                    items: vec![ApplicationHealthStateChunk {
+                        application_name: "fabric:/app".to_string(),
+                        application_type_name: Some("AppType".to_string()),
+                        health_state: HealthState::Warning,
+                        service_health_state_chunks: HealthStateChunkList {
+                            items: vec![
+                                ServiceHealthStateChunk {
+                                    service_name: "fabric:/app/svc1".to_string(),
+                                    health_state: HealthState::Warning,
+                                    partition_health_state_chunks: HealthStateChunkList {
+                                        items: vec![PartitionHealthStateChunk {
+                                            partition_id: GUID::from_u128(7),
+                                            health_state: HealthState::Warning,
+                                            replica_health_state_chunks: HealthStateChunkList {
+                                                items: vec![ReplicaHealthStateChunk {
+                                                    replica_or_instance_id: 42,
+                                                    health_state: HealthState::Warning,
+                                                }],
+                                                total_count: 3,
+                                            },
+                                        }],
+                                        total_count: 1,
+                                    },
+                                },
+                                ServiceHealthStateChunk {
+                                    service_name: "fabric:/app/svc2".to_string(),
+                                    health_state: HealthState::Ok,
+                                    partition_health_state_chunks: HealthStateChunkList {
+                                        items: vec![],
+                                        total_count: 4,
+                                    },
+                                },
+                            ],
+                            total_count: 2,
+                        },
+                        deployed_application_health_state_chunks: HealthStateChunkList {
+                            items: vec![DeployedApplicationHealthStateChunk {
+                                node_name: "node1".to_string(),
+                                health_state: HealthState::Ok,
+                                deployed_service_package_health_state_chunks:
+                                    HealthStateChunkList {
+                                        items: vec![
+                                            DeployedServicePackageHealthStateChunk {
+                                                service_manifest_name: "manifest1".to_string(),
+                                                service_package_activation_id: Some(
+                                                    "activation".to_string()
+                                                ),
+                                                health_state: HealthState::Ok,
+                                            },
+                                            DeployedServicePackageHealthStateChunk {
+                                                service_manifest_name: "manifest2".to_string(),
+                                                service_package_activation_id: None,
+                                                health_state: HealthState::Ok,
+                                            },
+                                        ],
+                                        total_count: 2,
+                                    },
+                            }],
+                            total_count: 2,
+                        },
+                    }],
+                    total_count: 1,
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn null_chunk_lists_are_empty() {
+        let raw = FABRIC_CLUSTER_HEALTH_CHUNK {
+            HealthState: HealthState::Ok.into(),
+            ..Default::default()
+        };
+        let chunk = ClusterHealthChunk::try_from(&raw).unwrap();
+
+        assert_eq!(chunk.health_state, HealthState::Ok);
+        assert_eq!(
+            chunk.node_health_state_chunks,
+            HealthStateChunkList::default()
+        );
+        assert_eq!(
+            chunk.application_health_state_chunks,
+            HealthStateChunkList::default()
+        );
+    }
+}
@@ -0,0 +1,324 @@
+use std::{collections::BTreeMap, ffi::c_void, ptr};
+
+use crate::{
+    arena::Arena, error::Error, FABRIC_APPLICATION_HEALTH_POLICY,
+    FABRIC_APPLICATION_HEALTH_POLICY_MAP, FABRIC_APPLICATION_HEALTH_POLICY_MAP_ITEM,
+    FABRIC_APPLICATION_TYPE_HEALTH_POLICY_MAP, FABRIC_APPLICATION_TYPE_HEALTH_POLICY_MAP_ITEM,
+    FABRIC_CLUSTER_HEALTH_POLICY, FABRIC_CLUSTER_HEALTH_POLICY_EX1,
+    FABRIC_CLUSTER_HEALTH_POLICY_EX2, FABRIC_CLUSTER_UPGRADE_HEALTH_POLICY,
+    FABRIC_NODE_TYPE_HEALTH_POLICY_MAP, FABRIC_NODE_TYPE_HEALTH_POLICY_MAP_ITEM,
+    FABRIC_SERVICE_TYPE_HEALTH_POLICY, FABRIC_SERVICE_TYPE_HEALTH_POLICY_MAP,
+    FABRIC_SERVICE_TYPE_HEALTH_POLICY_MAP_ITEM,
+};
+
+/// How many unhealthy children of a service type SF tolerates before it
+/// reports the service type as unhealthy. All values are percentages.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct ServiceTypeHealthPolicy {
+    pub max_percent_unhealthy_services: u8,
+    pub max_percent_unhealthy_partitions_per_service: u8,
+    pub max_percent_unhealthy_replicas_per_partition: u8,
+}
+
+impl ServiceTypeHealthPolicy {
+    fn validate(&self) -> Result<(), Error> {
+        validate_percent(self.max_percent_unhealthy_services)?;
+        validate_percent(self.max_percent_unhealthy_partitions_per_service)?;
+        validate_percent(self.max_percent_unhealthy_replicas_per_partition)
+    }
+
+    fn to_raw(self) -> FABRIC_SERVICE_TYPE_HEALTH_POLICY {
+        FABRIC_SERVICE_TYPE_HEALTH_POLICY {
+            MaxPercentUnhealthyServices: self.max_percent_unhealthy_services,
+            MaxPercentUnhealthyPartitionsPerService: self
+                .max_percent_unhealthy_partitions_per_service,
+            MaxPercentUnhealthyReplicasPerPartition: self
+                .max_percent_unhealthy_replicas_per_partition,
+            Reserved: ptr::null_mut(),
+        }
+    }
+}
+
+/// The policy SF evaluates an application's health against.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApplicationHealthPolicy {
+    pub consider_warning_as_error: bool,
+    pub max_percent_unhealthy_deployed_applications: u8,
+    /// Applies to service types without an entry in
+    /// `service_type_health_policies`.
+    pub default_service_type_health_policy: Option<ServiceTypeHealthPolicy>,
+    pub service_type_health_policies: BTreeMap<String, ServiceTypeHealthPolicy>,
+}
+
+impl ApplicationHealthPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn consider_warning_as_error(mut self, consider_warning_as_error: bool) -> Self {
+        self.consider_warning_as_error = consider_warning_as_error;
+        self
+    }
+
+    pub fn max_percent_unhealthy_deployed_applications(mut self, percent: u8) -> Self {
+        self.max_percent_unhealthy_deployed_applications = percent;
+        self
+    }
+
+    pub fn default_service_type_health_policy(mut self, policy: ServiceTypeHealthPolicy) -> Self {
+        self.default_service_type_health_policy = Some(policy);
+        self
+    }
+
+    pub fn service_type_health_policy(
+        mut self,
+        service_type_name: &str,
+        policy: ServiceTypeHealthPolicy,
+    ) -> Self {
+        self.service_type_health_policies
+            .insert(service_type_name.to_string(), policy);
+        self
+    }
+
+    /// Checks that every percentage is between 0 and 100.
+    pub fn validate(&self) -> Result<(), Error> {
+        validate_percent(self.max_percent_unhealthy_deployed_applications)?;
+        if let Some(policy) = &self.default_service_type_health_policy {
+            policy.validate()?;
+        }
+        self.service_type_health_policies
+            .values()
+            .try_for_each(ServiceTypeHealthPolicy::validate)
+    }
+
+    pub(crate) fn to_raw(&self, arena: &mut Arena) -> FABRIC_APPLICATION_HEALTH_POLICY {
+        let default_policy = self
+            .default_service_type_health_policy
+            .map_or(ptr::null(), |policy| {
+                arena.alloc(policy.to_raw()) as *const _
+            });
+        let items = self
+            .service_type_health_policies
+            .iter()
+            .map(
+                |(name, policy)| FABRIC_SERVICE_TYPE_HEALTH_POLICY_MAP_ITEM {
+                    ServiceTypeName: arena.wide(name),
+                    ServiceTypeHealthPolicy: arena.alloc(policy.to_raw()),
+                },
+            )
+            .collect::<Vec<_>>();
+        let count = items.len() as u32;
+        let items = arena.slice(items);
+        let policy_map = arena.alloc(FABRIC_SERVICE_TYPE_HEALTH_POLICY_MAP {
+            Count: count,
+            Items: items,
+        });
+
+        FABRIC_APPLICATION_HEALTH_POLICY {
+            ConsiderWarningAsError: self.consider_warning_as_error.into(),
+            MaxPercentUnhealthyDeployedApplications: self
+                .max_percent_unhealthy_deployed_applications,
+            DefaultServiceTypeHealthPolicy: default_policy,
+            ServiceTypeHealthPolicyMap: policy_map,
+            Reserved: ptr::null_mut(),
+        }
+    }
+}
+
+/// The policy SF evaluates the cluster's health against.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClusterHealthPolicy {
+    pub consider_warning_as_error: bool,
+    pub max_percent_unhealthy_nodes: u8,
+    pub max_percent_unhealthy_applications: u8,
+    /// Per application type overrides of `max_percent_unhealthy_applications`.
+    pub application_type_health_policies: BTreeMap<String, u8>,
+    /// Per node type overrides of `max_percent_unhealthy_nodes`.
+    pub node_type_health_policies: BTreeMap<String, u8>,
+}
+
+impl ClusterHealthPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn consider_warning_as_error(mut self, consider_warning_as_error: bool) -> Self {
+        self.consider_warning_as_error = consider_warning_as_error;
+        self
+    }
+
+    pub fn max_percent_unhealthy_nodes(mut self, percent: u8) -> Self {
+        self.max_percent_unhealthy_nodes = percent;
+        self
+    }
+
+    pub fn max_percent_unhealthy_applications(mut self, percent: u8) -> Self {
+        self.max_percent_unhealthy_applications = percent;
+        self
+    }
+
+    pub fn application_type_health_policy(
+        mut self,
+        application_type_name: &str,
+        percent: u8,
+    ) -> Self {
+        self.application_type_health_policies
+            .insert(application_type_name.to_string(), percent);
+        self
+    }
+
+    pub fn node_type_health_policy(mut self, node_type_name: &str, percent: u8) -> Self {
+        self.node_type_health_policies
+            .insert(node_type_name.to_string(), percent);
+        self
+    }
+
+    /// Checks that every percentage is between 0 and 100.
+    pub fn validate(&self) -> Result<(), Error> {
+        validate_percent(self.max_percent_unhealthy_nodes)?;
+        validate_percent(self.max_percent_unhealthy_applications)?;
+        self.application_type_health_policies
+            .values()
+            .chain(self.node_type_health_policies.values())
+            .try_for_each(|percent| validate_percent(*percent))
+    }
+
+    pub(crate) fn to_raw(&self, arena: &mut Arena) -> FABRIC_CLUSTER_HEALTH_POLICY {
+        let application_types = self
+            .application_type_health_policies
+            .iter()
+            .map(
+                |(name, percent)| FABRIC_APPLICATION_TYPE_HEALTH_POLICY_MAP_ITEM {
+                    ApplicationTypeName: arena.wide(name),
+                    MaxPercentUnhealthyApplications: *percent,
+                },
+            )
+            .collect::<Vec<_>>();
+        let count = application_types.len() as u32;
+        let items = arena.slice(application_types);
+        let application_types = arena.alloc(FABRIC_APPLICATION_TYPE_HEALTH_POLICY_MAP {
+            Count: count,
+            Items: items,
+        });
+
+        let node_types = self
+            .node_type_health_policies
+            .iter()
+            .map(|(name, percent)| FABRIC_NODE_TYPE_HEALTH_POLICY_MAP_ITEM {
+                NodeTypeName: arena.wide(name),
+                MaxPercentUnhealthyNodes: *percent,
+            })
+            .collect::<Vec<_>>();
+        let count = node_types.len() as u32;
+        let items = arena.slice(node_types);
+        let node_types = arena.alloc(FABRIC_NODE_TYPE_HEALTH_POLICY_MAP {
+            Count: count,
+            Items: items,
+        });
+
+        let ex2 = arena.alloc(FABRIC_CLUSTER_HEALTH_POLICY_EX2 {
+            NodeTypeHealthPolicyMap: node_types,
+            Reserved: ptr::null_mut(),
+        });
+        let ex1 = arena.alloc(FABRIC_CLUSTER_HEALTH_POLICY_EX1 {
+            ApplicationTypeHealthPolicyMap: application_types,
+            Reserved: ex2 as *mut c_void,
+        });
+
+        FABRIC_CLUSTER_HEALTH_POLICY {
+            ConsiderWarningAsError: self.consider_warning_as_error.into(),
+            MaxPercentUnhealthyNodes: self.max_percent_unhealthy_nodes,
+            MaxPercentUnhealthyApplications: self.max_percent_unhealthy_applications,
+            Reserved: ex1 as *mut c_void,
+        }
+    }
+}
+
+/// Limits on how much node health may degrade during a cluster upgrade,
+/// relative to the health at the start of the upgrade.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct ClusterUpgradeHealthPolicy {
+    pub max_percent_delta_unhealthy_nodes: u8,
+    pub max_percent_upgrade_domain_delta_unhealthy_nodes: u8,
+}
+
+impl ClusterUpgradeHealthPolicy {
+    pub fn validate(&self) -> Result<(), Error> {
+        validate_percent(self.max_percent_delta_unhealthy_nodes)?;
+        validate_percent(self.max_percent_upgrade_domain_delta_unhealthy_nodes)
+    }
+
+    pub(crate) fn to_raw(self) -> FABRIC_CLUSTER_UPGRADE_HEALTH_POLICY {
+        FABRIC_CLUSTER_UPGRADE_HEALTH_POLICY {
+            MaxPercentDeltaUnhealthyNodes: self.max_percent_delta_unhealthy_nodes,
+            MaxPercentUpgradeDomainDeltaUnhealthyNodes: self
+                .max_percent_upgrade_domain_delta_unhealthy_nodes,
+            Reserved: ptr::null_mut(),
+        }
+    }
+}
+
+/// Stores an optional cluster health policy in `arena`. `None` maps to null,
+/// which tells SF to use the policy from the cluster manifest.
+pub(crate) fn cluster_health_policy(
+    arena: &mut Arena,
+    health_policy: Option<&ClusterHealthPolicy>,
+) -> *const FABRIC_CLUSTER_HEALTH_POLICY {
+    match health_policy {
+        Some(health_policy) => {
+            let health_policy = health_policy.to_raw(arena);
+            arena.alloc(health_policy)
+        }
+        None => ptr::null(),
+    }
+}
+
+/// Stores an optional application health policy in `arena`. `None` maps to
+/// null, which tells SF to use the policy from the application manifest.
+pub(crate) fn application_health_policy(
+    arena: &mut Arena,
+    health_policy: Option<&ApplicationHealthPolicy>,
+) -> *const FABRIC_APPLICATION_HEALTH_POLICY {
+    match health_policy {
+        Some(health_policy) => {
+            let health_policy = health_policy.to_raw(arena);
+            arena.alloc(health_policy)
+        }
+        None => ptr::null(),
+    }
+}
+
+/// Builds the per application health policy map used by cluster upgrades and
+/// cluster health queries. Callers pass null instead when `policies` is empty.
+pub(crate) fn application_health_policy_map(
+    arena: &mut Arena,
+    policies: &BTreeMap<String, ApplicationHealthPolicy>,
+) -> *const FABRIC_APPLICATION_HEALTH_POLICY_MAP {
+    let items = policies
+        .iter()
+        .map(|(application_name, policy)| {
+            let policy = policy.to_raw(arena);
+            FABRIC_APPLICATION_HEALTH_POLICY_MAP_ITEM {
+                ApplicationName: arena.uri(application_name),
+                HealthPolicy: arena.alloc(policy),
+            }
+        })
+        .collect::<Vec<_>>();
+    let count = items.len() as u32;
+    let items = arena.slice(items);
+
+    arena.alloc(FABRIC_APPLICATION_HEALTH_POLICY_MAP {
+        Count: count,
+        Items: items,
+    })
+}
+
+pub(crate) fn validate_percent(percent: u8) -> Result<(), Error> {
+    if percent > 100 {
+        return Err(Error::InvalidHealthPolicy(
+            "unhealthy percentages must be between 0 and 100",
+        ));
+    }
+
+    Ok(())
+}
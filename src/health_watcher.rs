@@ -0,0 +1,385 @@
+use std::{future::Future, pin::Pin, sync::Arc, time::Duration};
+
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use windows::core::GUID;
+
+use crate::{
+    error::Error, in_tokio_runtime, ApplicationHealthQueryDescription,
+    ClusterHealthQueryDescription, HealthClient, HealthEvent, HealthState, HealthStateFilter,
+    NodeHealthQueryDescription, PartitionHealthQueryDescription, ReplicaHealthQueryDescription,
+    ServiceHealthQueryDescription,
+};
+
+/// The entity a `HealthWatcher` polls.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HealthEntity {
+    Cluster,
+    Node(String),
+    Application(String),
+    Service(String),
+    Partition(GUID),
+    Replica {
+        partition_id: GUID,
+        replica_or_instance_id: i64,
+    },
+}
+
+/// An entity's aggregated health state and events at one point in time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthSnapshot {
+    pub aggregated_health_state: HealthState,
+    pub health_events: Vec<HealthEvent>,
+}
+
+/// Where a `HealthWatcher` gets its snapshots from. `HealthClient`
+/// implements it; tests can substitute a fake.
+pub trait HealthSource: Send + Sync + 'static {
+    fn get_health<'a>(
+        &'a self,
+        entity: &'a HealthEntity,
+        timeout_ms: u32,
+    ) -> Pin<Box<dyn Future<Output = Result<HealthSnapshot, Error>> + Send + 'a>>;
+}
+
+impl HealthSource for HealthClient {
+    fn get_health<'a>(
+        &'a self,
+        entity: &'a HealthEntity,
+        timeout_ms: u32,
+    ) -> Pin<Box<dyn Future<Output = Result<HealthSnapshot, Error>> + Send + 'a>> {
+        Box::pin(async move {
+            let (aggregated_health_state, health_events) = match entity {
+                HealthEntity::Cluster => {
+                    let description =
+                        ClusterHealthQueryDescription::new().events_filter(HealthStateFilter::ALL);
+                    let health = self.get_cluster_health(&description, timeout_ms).await?;
+                    (health.aggregated_health_state, health.health_events)
+                }
+                HealthEntity::Node(node_name) => {
+                    let description = NodeHealthQueryDescription::new(node_name)
+                        .events_filter(HealthStateFilter::ALL);
+                    let health = self.get_node_health(&description, timeout_ms).await?;
+                    (health.aggregated_health_state, health.health_events)
+                }
+                HealthEntity::Application(application_name) => {
+                    let description = ApplicationHealthQueryDescription::new(application_name)
+                        .events_filter(HealthStateFilter::ALL);
+                    let health = self
+                        .get_application_health(&description, timeout_ms)
+                        .await?;
+                    (health.aggregated_health_state, health.health_events)
+                }
+                HealthEntity::Service(service_name) => {
+                    let description = ServiceHealthQueryDescription::new(service_name)
+                        .events_filter(HealthStateFilter::ALL);
+                    let health = self.get_service_health(&description, timeout_ms).await?;
+                    (health.aggregated_health_state, health.health_events)
+                }
+                HealthEntity::Partition(partition_id) => {
+                    let description = PartitionHealthQueryDescription::new(*partition_id)
+                        .events_filter(HealthStateFilter::ALL);
+                    let health = self.get_partition_health(&description, timeout_ms).await?;
+                    (health.aggregated_health_state, health.health_events)
+                }
+                HealthEntity::Replica {
+                    partition_id,
+                    replica_or_instance_id,
+                } => {
+                    let description =
+                        ReplicaHealthQueryDescription::new(*partition_id, *replica_or_instance_id)
+                            .events_filter(HealthStateFilter::ALL);
+                    let health = self.get_replica_health(&description, timeout_ms).await?;
+                    (health.aggregated_health_state, health.health_events)
+                }
+            };
+
+            Ok(HealthSnapshot {
+                aggregated_health_state,
+                health_events,
+            })
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HealthTransitionKind {
+    /// The entity's aggregated health state changed to Warning or Error.
+    StateChanged,
+    /// An event that wasn't there, or had expired, is now reported.
+    EventAdded(HealthEvent),
+    /// A previously live event expired. Events removed on expiry are
+    /// reported with their last seen contents.
+    EventExpired(HealthEvent),
+}
+
+/// A change between two polls. `previous` and `current` are the entity's
+/// aggregated health states at those polls.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthTransition {
+    pub entity: HealthEntity,
+    pub previous: HealthState,
+    pub current: HealthState,
+    pub kind: HealthTransitionKind,
+}
+
+/// Polls an entity's health and streams what changed.
+#[derive(Debug, Clone)]
+pub struct HealthWatcher<S = HealthClient> {
+    source: Arc<S>,
+    timeout_ms: u32,
+}
+
+impl<S: HealthSource> HealthWatcher<S> {
+    /// `timeout_ms` applies to each individual health query.
+    pub fn new(source: S, timeout_ms: u32) -> Self {
+        Self {
+            source: Arc::new(source),
+            timeout_ms,
+        }
+    }
+
+    /// Polls `entity` every `interval` and streams its transitions. The first
+    /// poll is the baseline and produces nothing; the state the entity is
+    /// already in is not reported. Recoveries to Ok aren't reported either,
+    /// though the next transition's `previous` reflects them. The stream ends
+    /// after the first error, or stops polling once it's dropped. Called
+    /// outside a tokio runtime, the stream yields `Error::NoTokioRuntime`.
+    pub fn watch(
+        &self,
+        entity: HealthEntity,
+        interval: Duration,
+    ) -> ReceiverStream<Result<HealthTransition, Error>> {
+        let source = self.source.clone();
+        let timeout_ms = self.timeout_ms;
+        let (tx, rx) = mpsc::channel(16);
+        if !in_tokio_runtime() {
+            let _ = tx.try_send(Err(Error::NoTokioRuntime("HealthWatcher::watch")));
+            return ReceiverStream::new(rx);
+        }
+
+        tokio::spawn(async move {
+            let mut previous: Option<HealthSnapshot> = None;
+            loop {
+                let current = match source.get_health(&entity, timeout_ms).await {
+                    Ok(snapshot) => snapshot,
+                    Err(e) => {
+                        let _ = tx.send(Err(e)).await;
+                        return;
+                    }
+                };
+
+                if let Some(previous) = &previous {
+                    for transition in health_transitions(&entity, previous, &current) {
+                        if tx.send(Ok(transition)).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+
+                previous = Some(current);
+                tokio::select! {
+                    _ = tokio::time::sleep(interval) => {}
+                    _ = tx.closed() => return,
+                }
+            }
+        });
+
+        ReceiverStream::new(rx)
+    }
+}
+
+fn same_event(a: &HealthEvent, b: &HealthEvent) -> bool {
+    a.health_information.source_id == b.health_information.source_id
+        && a.health_information.property == b.health_information.property
+}
+
+fn health_transitions(
+    entity: &HealthEntity,
+    previous: &HealthSnapshot,
+    current: &HealthSnapshot,
+) -> Vec<HealthTransition> {
+    let transition = |kind| HealthTransition {
+        entity: entity.clone(),
+        previous: previous.aggregated_health_state,
+        current: current.aggregated_health_state,
+        kind,
+    };
+    let mut transitions = vec![];
+
+    if current.aggregated_health_state != previous.aggregated_health_state
+        && matches!(
+            current.aggregated_health_state,
+            HealthState::Warning | HealthState::Error
+        )
+    {
+        transitions.push(transition(HealthTransitionKind::StateChanged));
+    }
+
+    for event in &current.health_events {
+        let before = previous
+            .health_events
+            .iter()
+            .find(|before| same_event(before, event));
+        let was_live = before.is_some_and(|before| !before.is_expired);
+        if !event.is_expired && !was_live {
+            transitions.push(transition(HealthTransitionKind::EventAdded(event.clone())));
+        } else if event.is_expired && was_live {
+            transitions.push(transition(HealthTransitionKind::EventExpired(
+                event.clone(),
+            )));
+        }
+    }
+
+    for before in &previous.health_events {
+        let removed = !current
+            .health_events
+            .iter()
+            .any(|event| same_event(before, event));
+        if removed && !before.is_expired && before.health_information.remove_when_expired {
+            transitions.push(transition(HealthTransitionKind::EventExpired(
+                before.clone(),
+            )));
+        }
+    }
+
+    transitions
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::VecDeque, sync::Mutex};
+
+    use tokio_stream::StreamExt;
+
+    use super::*;
+    use crate::HealthInformation;
+
+    struct FakeHealthSource {
+        snapshots: Mutex<VecDeque<HealthSnapshot>>,
+    }
+
+    impl HealthSource for FakeHealthSource {
+        fn get_health<'a>(
+            &'a self,
+            _entity: &'a HealthEntity,
+            _timeout_ms: u32,
+        ) -> Pin<Box<dyn Future<Output = Result<HealthSnapshot, Error>> + Send + 'a>> {
+            let snapshot = self.snapshots.lock().unwrap().pop_front();
+            Box::pin(async move { snapshot.ok_or(Error::Abandoned("GetHealth")) })
+        }
+    }
+
+    fn event(property: &str, health_state: HealthState, is_expired: bool) -> HealthEvent {
+        HealthEvent {
+            health_information: HealthInformation::new("watchdog", property, health_state),
+            source_utc_timestamp: None,
+            last_modified_utc_timestamp: None,
+            is_expired,
+            last_ok_transition_at: None,
+            last_warning_transition_at: None,
+            last_error_transition_at: None,
+        }
+    }
+
+    fn snapshot(state: HealthState, health_events: Vec<HealthEvent>) -> HealthSnapshot {
+        HealthSnapshot {
+            aggregated_health_state: state,
+            health_events,
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn watch_emits_only_transitions() {
+        let disk = event("Disk", HealthState::Warning, false);
+        let source = FakeHealthSource {
+            snapshots: Mutex::new(VecDeque::from([
+                snapshot(HealthState::Ok, vec![]),
+                snapshot(HealthState::Ok, vec![]),
+                snapshot(HealthState::Warning, vec![disk.clone()]),
+                snapshot(HealthState::Warning, vec![disk.clone()]),
+                snapshot(
+                    HealthState::Ok,
+                    vec![event("Disk", HealthState::Warning, true)],
+                ),
+            ])),
+        };
+        let watcher = HealthWatcher::new(source, 1000);
+        let entity = HealthEntity::Node("_Node_0".to_string());
+        let mut stream = watcher.watch(entity.clone(), Duration::from_secs(30));
+
+        let started = tokio::time::Instant::now();
+        let transition = stream.next().await.unwrap().unwrap();
+        assert_eq!(started.elapsed(), Duration::from_secs(60));
+        assert_eq!(
+            transition,
+            HealthTransition {
+                entity: entity.clone(),
+                previous: HealthState::Ok,
+                current: HealthState::Warning,
+                kind: HealthTransitionKind::StateChanged,
+            }
+        );
+        assert_eq!(
+            stream.next().await.unwrap().unwrap().kind,
+            HealthTransitionKind::EventAdded(disk)
+        );
+
+        let transition = stream.next().await.unwrap().unwrap();
+        assert_eq!(started.elapsed(), Duration::from_secs(120));
+        assert_eq!(
+            (transition.previous, transition.current),
+            (HealthState::Warning, HealthState::Ok)
+        );
+        assert!(matches!(
+            transition.kind,
+            HealthTransitionKind::EventExpired(_)
+        ));
+
+        // The fake runs dry on the next poll, which ends the stream.
+        assert!(stream.next().await.unwrap().is_err());
+        assert!(stream.next().await.is_none());
+    }
+
+    #[test]
+    fn watch_outside_a_runtime_reports_an_error() {
+        let source = FakeHealthSource {
+            snapshots: Mutex::new(VecDeque::new()),
+        };
+        let watcher = HealthWatcher::new(source, 1000);
+        let mut stream = watcher
+            .watch(HealthEntity::Cluster, Duration::from_secs(30))
+            .into_inner();
+
+        assert!(matches!(
+            stream.try_recv(),
+            Ok(Err(Error::NoTokioRuntime(_)))
+        ));
+        assert!(stream.try_recv().is_err());
+    }
+
+    #[test]
+    fn removed_events_count_as_expired_only_when_removed_on_expiry() {
+        let entity = HealthEntity::Cluster;
+        let mut kept = event("Kept", HealthState::Error, false);
+        kept.health_information.remove_when_expired = false;
+        let mut removed = event("Removed", HealthState::Error, false);
+        removed.health_information.remove_when_expired = true;
+
+        let transitions = health_transitions(
+            &entity,
+            &snapshot(HealthState::Error, vec![kept, removed.clone()]),
+            &snapshot(HealthState::Error, vec![]),
+        );
+
+        assert_eq!(
+            transitions,
+            vec![HealthTransition {
+                entity,
+                previous: HealthState::Error,
+                current: HealthState::Error,
+                kind: HealthTransitionKind::EventExpired(removed),
+            }]
+        );
+    }
+}
@@ -1,17 +1,47 @@
 extern crate windows;
 
 mod agile;
+mod arena;
+mod callback;
+mod xml;
+
+pub mod application;
+pub use application::*;
 
 pub mod bindings;
-use std::future::Future;
+use std::{ffi::OsString, future::Future, os::windows::ffi::OsStrExt, ptr};
 
 pub use bindings::*;
 
 pub mod client;
 pub use client::*;
 
+pub mod cluster;
+pub use cluster::*;
+
+pub mod cluster_manifest;
+pub use cluster_manifest::*;
+
 pub mod error;
 
+pub mod fault;
+pub use fault::*;
+
+pub mod health;
+pub use health::*;
+
+pub mod health_chunk;
+pub use health_chunk::*;
+
+pub mod health_watcher;
+pub use health_watcher::*;
+
+pub mod health_policy;
+pub use health_policy::*;
+
+pub mod property;
+pub use property::*;
+
 pub mod query;
 use error::{Error, FabricErrorCode};
 pub use query::*;
@@ -19,12 +49,20 @@ pub use query::*;
 pub mod service;
 pub use service::*;
 
+pub mod service_description;
+pub use service_description::*;
+
+pub mod service_group;
+pub use service_group::*;
+
 pub mod types;
 use tokio::sync::mpsc;
 pub use types::*;
 
+pub mod upgrade;
+pub use upgrade::*;
+
 use lazy_static::lazy_static;
-use num_traits::FromPrimitive;
 use tokio_retry::{strategy::FixedInterval, RetryIf};
 
 lazy_static! {
@@ -34,13 +72,7 @@ lazy_static! {
         FabricErrorCode::InvalidReplicaStateForReplicaOperation,
         FabricErrorCode::ObjectClosed,
         FabricErrorCode::ServiceNotFound,
-        FabricErrorCode::AlreadyAuxiliaryReplica,
-        FabricErrorCode::AlreadyInstance,
-        FabricErrorCode::AlreadySecondaryReplica,
-        FabricErrorCode::AlreadyPrimaryReplica,
         FabricErrorCode::FabricVersionAlreadyExists,
-        FabricErrorCode::FabricUpgradeInProgress,
-        FabricErrorCode::FabricAlreadyInTargetVersion,
         FabricErrorCode::StopInProgress
     ];
 }
@@ -56,20 +88,16 @@ where
 }
 
 fn is_retryable_error(op_name: &str, err: &Error) -> bool {
-    if let Error::Windows(e) = err {
-        let err = FabricErrorCode::from_u32(e.code().0 as u32);
-        let retryable = err
-            .as_ref()
-            .map(|code| RETRYABLE_ERRORS.contains(code))
-            .unwrap_or(false);
-        if retryable {
-            log::warn!("Retrying {} due to error: {:?}", op_name, err);
-        }
-
-        retryable
-    } else {
-        false
+    let code = err.fabric_error_code();
+    let retryable = code
+        .as_ref()
+        .map(|code| RETRYABLE_ERRORS.contains(code))
+        .unwrap_or(false);
+    if retryable {
+        log::warn!("Retrying {} due to error: {:?}", op_name, code);
     }
+
+    retryable
 }
 
 pub(crate) fn in_tokio_runtime() -> bool {
@@ -93,3 +121,16 @@ where
 
     Ok(())
 }
+
+/// Encodes `s` as a null terminated UTF-16 string suitable for passing to SF.
+pub(crate) fn to_wide(s: &str) -> Vec<u16> {
+    OsString::from(s)
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect()
+}
+
+/// Returns a pointer to an optional wide string or null if it isn't present.
+pub(crate) fn optional_wide(s: &Option<Vec<u16>>) -> *const u16 {
+    s.as_ref().map_or(ptr::null(), |s| s.as_ptr())
+}
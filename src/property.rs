@@ -0,0 +1,1167 @@
+use std::{ffi::c_void, ptr, time::SystemTime};
+
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use windows::{
+    core::{ComInterface, Result as WindowsResult, GUID},
+    Win32::Foundation::BOOLEAN,
+};
+
+use crate::{
+    agile::AgileRef,
+    arena::Arena,
+    callback::begin_async,
+    error::{Error, FabricErrorCode},
+    filetime_to_system_time, in_tokio_runtime, list_items, optional_string, run_with_retry,
+    to_wide, wide_to_string, IFabricAsyncOperationContext, IFabricNameEnumerationResult,
+    IFabricPropertyManagementClient2, IFabricPropertyValueResult, MakeClient,
+    FABRIC_CHECK_EXISTS_PROPERTY_OPERATION, FABRIC_CHECK_SEQUENCE_PROPERTY_OPERATION,
+    FABRIC_CHECK_VALUE_PROPERTY_OPERATION, FABRIC_DELETE_PROPERTY_OPERATION,
+    FABRIC_ENUMERATION_FINISHED_MASK, FABRIC_GET_PROPERTY_OPERATION,
+    FABRIC_NAMED_PROPERTY_METADATA, FABRIC_NAMED_PROPERTY_METADATA_EX1,
+    FABRIC_OPERATION_DATA_BUFFER, FABRIC_PROPERTY_BATCH_OPERATION,
+    FABRIC_PROPERTY_BATCH_OPERATION_KIND_CHECK_EXISTS,
+    FABRIC_PROPERTY_BATCH_OPERATION_KIND_CHECK_SEQUENCE,
+    FABRIC_PROPERTY_BATCH_OPERATION_KIND_CHECK_VALUE, FABRIC_PROPERTY_BATCH_OPERATION_KIND_DELETE,
+    FABRIC_PROPERTY_BATCH_OPERATION_KIND_GET, FABRIC_PROPERTY_BATCH_OPERATION_KIND_PUT,
+    FABRIC_PROPERTY_BATCH_OPERATION_KIND_PUT_CUSTOM, FABRIC_PROPERTY_TYPE_BINARY,
+    FABRIC_PROPERTY_TYPE_DOUBLE, FABRIC_PROPERTY_TYPE_GUID, FABRIC_PROPERTY_TYPE_ID,
+    FABRIC_PROPERTY_TYPE_INT64, FABRIC_PROPERTY_TYPE_INVALID, FABRIC_PROPERTY_TYPE_WSTRING,
+    FABRIC_PUT_CUSTOM_PROPERTY_OPERATION, FABRIC_PUT_PROPERTY_OPERATION,
+};
+
+/// A client for the naming service: SF names and the properties stored
+/// under them.
+#[derive(Debug, Clone)]
+pub struct PropertyManagementClient {
+    client: AgileRef<IFabricPropertyManagementClient2>,
+}
+
+impl MakeClient for PropertyManagementClient {
+    type Interface = IFabricPropertyManagementClient2;
+
+    fn make(client: Self::Interface) -> Result<Self, Error> {
+        Ok(Self {
+            client: AgileRef::new(client.cast()?)?,
+        })
+    }
+}
+
+/// One page of a name enumeration. `result` is handed back to SF to fetch the
+/// next page.
+struct SubnamePage {
+    names: Vec<String>,
+    result: AgileRef<IFabricNameEnumerationResult>,
+    finished: bool,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[repr(i32)]
+pub enum PropertyTypeId {
+    Invalid = FABRIC_PROPERTY_TYPE_INVALID.0,
+    Binary = FABRIC_PROPERTY_TYPE_BINARY.0,
+    Int64 = FABRIC_PROPERTY_TYPE_INT64.0,
+    Double = FABRIC_PROPERTY_TYPE_DOUBLE.0,
+    WString = FABRIC_PROPERTY_TYPE_WSTRING.0,
+    Guid = FABRIC_PROPERTY_TYPE_GUID.0,
+}
+
+impl From<FABRIC_PROPERTY_TYPE_ID> for PropertyTypeId {
+    fn from(value: FABRIC_PROPERTY_TYPE_ID) -> Self {
+        match value {
+            FABRIC_PROPERTY_TYPE_BINARY => Self::Binary,
+            FABRIC_PROPERTY_TYPE_INT64 => Self::Int64,
+            FABRIC_PROPERTY_TYPE_DOUBLE => Self::Double,
+            FABRIC_PROPERTY_TYPE_WSTRING => Self::WString,
+            FABRIC_PROPERTY_TYPE_GUID => Self::Guid,
+            _ => Self::Invalid,
+        }
+    }
+}
+
+impl From<PropertyTypeId> for FABRIC_PROPERTY_TYPE_ID {
+    fn from(value: PropertyTypeId) -> Self {
+        FABRIC_PROPERTY_TYPE_ID(value as i32)
+    }
+}
+
+/// The value of a naming service property.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyValue {
+    Binary(Vec<u8>),
+    Int64(i64),
+    Double(f64),
+    String(String),
+    Guid(GUID),
+}
+
+impl PropertyValue {
+    pub fn type_id(&self) -> PropertyTypeId {
+        match self {
+            PropertyValue::Binary(_) => PropertyTypeId::Binary,
+            PropertyValue::Int64(_) => PropertyTypeId::Int64,
+            PropertyValue::Double(_) => PropertyTypeId::Double,
+            PropertyValue::String(_) => PropertyTypeId::WString,
+            PropertyValue::Guid(_) => PropertyTypeId::Guid,
+        }
+    }
+
+    /// Returns a pointer to the value as the operation structs expect it;
+    /// binary values are wrapped in a `FABRIC_OPERATION_DATA_BUFFER`.
+    pub(crate) fn to_raw(&self, arena: &mut Arena) -> *mut c_void {
+        match self {
+            PropertyValue::Binary(data) => {
+                let buffer = FABRIC_OPERATION_DATA_BUFFER {
+                    BufferSize: data.len() as u32,
+                    Buffer: arena.slice(data.clone()),
+                };
+                arena.alloc(buffer) as *mut c_void
+            }
+            PropertyValue::Int64(data) => arena.alloc(*data) as *mut c_void,
+            PropertyValue::Double(data) => arena.alloc(*data) as *mut c_void,
+            PropertyValue::String(data) => arena.wide(data).0 as *mut c_void,
+            PropertyValue::Guid(data) => arena.alloc(*data) as *mut c_void,
+        }
+    }
+}
+
+impl TryFrom<&IFabricPropertyValueResult> for PropertyValue {
+    type Error = Error;
+
+    fn try_from(value: &IFabricPropertyValueResult) -> Result<Self, Self::Error> {
+        let property = unsafe { &*value.get_Property() };
+        let metadata = unsafe { &*property.Metadata };
+
+        Ok(match PropertyTypeId::from(metadata.TypeId) {
+            PropertyTypeId::Binary => {
+                let mut count = 0;
+                let data = unsafe { value.GetValueAsBinary(&mut count) }?;
+                PropertyValue::Binary(unsafe { list_items(data, count) }.to_vec())
+            }
+            PropertyTypeId::Int64 => PropertyValue::Int64(unsafe { value.GetValueAsInt64() }?),
+            PropertyTypeId::Double => PropertyValue::Double(unsafe { value.GetValueAsDouble() }?),
+            PropertyTypeId::WString => {
+                let data = unsafe { value.GetValueAsWString() }?;
+                PropertyValue::String(unsafe { wide_to_string(data.0) }?)
+            }
+            PropertyTypeId::Guid => PropertyValue::Guid(unsafe { value.GetValueAsGuid() }?),
+            PropertyTypeId::Invalid => return Err(Error::InvalidPropertyType),
+        })
+    }
+}
+
+/// What the naming service knows about a property besides its value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropertyMetadata {
+    /// The SF name the property is stored under.
+    pub name: String,
+    pub property_name: String,
+    pub type_id: PropertyTypeId,
+    /// The size of the value in bytes.
+    pub value_size: i32,
+    /// Bumped by SF on every write to the property.
+    pub sequence_number: i64,
+    pub last_modified_utc: Option<SystemTime>,
+    /// Set by `put_custom_property`.
+    pub custom_type_id: Option<String>,
+}
+
+impl TryFrom<&FABRIC_NAMED_PROPERTY_METADATA> for PropertyMetadata {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_NAMED_PROPERTY_METADATA) -> Result<Self, Self::Error> {
+        let ex1 = unsafe { (value.Reserved as *const FABRIC_NAMED_PROPERTY_METADATA_EX1).as_ref() };
+        let custom_type_id = match ex1 {
+            Some(ex1) => unsafe { optional_string(ex1.CustomTypeId) }?,
+            None => None,
+        };
+
+        Ok(Self {
+            name: unsafe { wide_to_string(value.Name) }?,
+            property_name: unsafe { wide_to_string(value.PropertyName.0) }?,
+            type_id: value.TypeId.into(),
+            value_size: value.ValueSize,
+            sequence_number: value.SequenceNumber,
+            last_modified_utc: filetime_to_system_time(&value.LastModifiedUtc),
+            custom_type_id,
+        })
+    }
+}
+
+/// A property together with its metadata.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamedProperty {
+    pub metadata: PropertyMetadata,
+    pub value: PropertyValue,
+}
+
+impl TryFrom<&IFabricPropertyValueResult> for NamedProperty {
+    type Error = Error;
+
+    fn try_from(value: &IFabricPropertyValueResult) -> Result<Self, Self::Error> {
+        let property = unsafe { &*value.get_Property() };
+
+        Ok(Self {
+            metadata: PropertyMetadata::try_from(unsafe { &*property.Metadata })?,
+            value: PropertyValue::try_from(value)?,
+        })
+    }
+}
+
+/// One operation of a `PropertyBatch`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyBatchOperation {
+    /// Fails the batch unless the property's existence matches `exists`.
+    CheckExists {
+        property_name: String,
+        exists: bool,
+    },
+    /// Fails the batch unless the property's sequence number is
+    /// `sequence_number`.
+    CheckSequence {
+        property_name: String,
+        sequence_number: i64,
+    },
+    /// Fails the batch unless the property currently holds `value`.
+    CheckValue {
+        property_name: String,
+        value: PropertyValue,
+    },
+    Put {
+        property_name: String,
+        value: PropertyValue,
+    },
+    PutCustom {
+        property_name: String,
+        value: PropertyValue,
+        custom_type_id: String,
+    },
+    Get {
+        property_name: String,
+    },
+    Delete {
+        property_name: String,
+    },
+}
+
+impl PropertyBatchOperation {
+    fn to_raw(&self, arena: &mut Arena) -> FABRIC_PROPERTY_BATCH_OPERATION {
+        let (kind, value) = match self {
+            PropertyBatchOperation::CheckExists {
+                property_name,
+                exists,
+            } => {
+                let operation = FABRIC_CHECK_EXISTS_PROPERTY_OPERATION {
+                    PropertyName: arena.wide(property_name),
+                    ExistenceCheck: (*exists).into(),
+                    Reserved: ptr::null_mut(),
+                };
+                (
+                    FABRIC_PROPERTY_BATCH_OPERATION_KIND_CHECK_EXISTS,
+                    arena.alloc(operation) as *mut c_void,
+                )
+            }
+            PropertyBatchOperation::CheckSequence {
+                property_name,
+                sequence_number,
+            } => {
+                let operation = FABRIC_CHECK_SEQUENCE_PROPERTY_OPERATION {
+                    PropertyName: arena.wide(property_name),
+                    SequenceNumber: *sequence_number,
+                    Reserved: ptr::null_mut(),
+                };
+                (
+                    FABRIC_PROPERTY_BATCH_OPERATION_KIND_CHECK_SEQUENCE,
+                    arena.alloc(operation) as *mut c_void,
+                )
+            }
+            PropertyBatchOperation::CheckValue {
+                property_name,
+                value,
+            } => {
+                let operation = FABRIC_CHECK_VALUE_PROPERTY_OPERATION {
+                    PropertyName: arena.wide(property_name),
+                    PropertyTypeId: value.type_id().into(),
+                    PropertyValue: value.to_raw(arena),
+                    Reserved: ptr::null_mut(),
+                };
+                (
+                    FABRIC_PROPERTY_BATCH_OPERATION_KIND_CHECK_VALUE,
+                    arena.alloc(operation) as *mut c_void,
+                )
+            }
+            PropertyBatchOperation::Put {
+                property_name,
+                value,
+            } => {
+                let operation = FABRIC_PUT_PROPERTY_OPERATION {
+                    PropertyName: arena.wide(property_name),
+                    PropertyTypeId: value.type_id().into(),
+                    PropertyValue: value.to_raw(arena),
+                    Reserved: ptr::null_mut(),
+                };
+                (
+                    FABRIC_PROPERTY_BATCH_OPERATION_KIND_PUT,
+                    arena.alloc(operation) as *mut c_void,
+                )
+            }
+            PropertyBatchOperation::PutCustom {
+                property_name,
+                value,
+                custom_type_id,
+            } => {
+                let operation = FABRIC_PUT_CUSTOM_PROPERTY_OPERATION {
+                    PropertyName: arena.wide(property_name),
+                    PropertyTypeId: value.type_id().into(),
+                    PropertyValue: value.to_raw(arena),
+                    PropertyCustomTypeId: arena.wide(custom_type_id),
+                    Reserved: ptr::null_mut(),
+                };
+                (
+                    FABRIC_PROPERTY_BATCH_OPERATION_KIND_PUT_CUSTOM,
+                    arena.alloc(operation) as *mut c_void,
+                )
+            }
+            PropertyBatchOperation::Get { property_name } => {
+                let operation = FABRIC_GET_PROPERTY_OPERATION {
+                    PropertyName: arena.wide(property_name),
+                    IncludeValue: true.into(),
+                    Reserved: ptr::null_mut(),
+                };
+                (
+                    FABRIC_PROPERTY_BATCH_OPERATION_KIND_GET,
+                    arena.alloc(operation) as *mut c_void,
+                )
+            }
+            PropertyBatchOperation::Delete { property_name } => {
+                let operation = FABRIC_DELETE_PROPERTY_OPERATION {
+                    PropertyName: arena.wide(property_name),
+                    Reserved: ptr::null_mut(),
+                };
+                (
+                    FABRIC_PROPERTY_BATCH_OPERATION_KIND_DELETE,
+                    arena.alloc(operation) as *mut c_void,
+                )
+            }
+        };
+
+        FABRIC_PROPERTY_BATCH_OPERATION {
+            Kind: kind,
+            Value: value,
+        }
+    }
+}
+
+/// Operations on the properties of one name that SF applies atomically:
+/// either every operation succeeds or none of the writes happen. Checks
+/// make it a compare-and-swap, e.g.
+/// `PropertyBatch::new().check_sequence("leader", seq).put("leader", me)`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PropertyBatch {
+    pub operations: Vec<PropertyBatchOperation>,
+}
+
+impl PropertyBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn check_exists(mut self, property_name: &str, exists: bool) -> Self {
+        self.operations.push(PropertyBatchOperation::CheckExists {
+            property_name: property_name.to_string(),
+            exists,
+        });
+        self
+    }
+
+    pub fn check_sequence(mut self, property_name: &str, sequence_number: i64) -> Self {
+        self.operations.push(PropertyBatchOperation::CheckSequence {
+            property_name: property_name.to_string(),
+            sequence_number,
+        });
+        self
+    }
+
+    pub fn check_value(mut self, property_name: &str, value: PropertyValue) -> Self {
+        self.operations.push(PropertyBatchOperation::CheckValue {
+            property_name: property_name.to_string(),
+            value,
+        });
+        self
+    }
+
+    pub fn put(mut self, property_name: &str, value: PropertyValue) -> Self {
+        self.operations.push(PropertyBatchOperation::Put {
+            property_name: property_name.to_string(),
+            value,
+        });
+        self
+    }
+
+    pub fn put_custom(
+        mut self,
+        property_name: &str,
+        value: PropertyValue,
+        custom_type_id: &str,
+    ) -> Self {
+        self.operations.push(PropertyBatchOperation::PutCustom {
+            property_name: property_name.to_string(),
+            value,
+            custom_type_id: custom_type_id.to_string(),
+        });
+        self
+    }
+
+    /// Fetches the property as of the batch; its value is returned in
+    /// `PropertyBatchOutcome::Committed`.
+    pub fn get(mut self, property_name: &str) -> Self {
+        self.operations.push(PropertyBatchOperation::Get {
+            property_name: property_name.to_string(),
+        });
+        self
+    }
+
+    pub fn delete(mut self, property_name: &str) -> Self {
+        self.operations.push(PropertyBatchOperation::Delete {
+            property_name: property_name.to_string(),
+        });
+        self
+    }
+
+    /// The indexes of the batch's `get` operations.
+    fn get_operation_indexes(&self) -> Vec<u32> {
+        self.operations
+            .iter()
+            .enumerate()
+            .filter(|(_, operation)| matches!(operation, PropertyBatchOperation::Get { .. }))
+            .map(|(index, _)| index as u32)
+            .collect()
+    }
+}
+
+/// The result of `submit_property_batch`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyBatchOutcome {
+    /// Every operation was applied. `properties` holds what the batch's `get`
+    /// operations fetched, in the order they were added.
+    Committed { properties: Vec<NamedProperty> },
+    /// The operation at `failed_operation_index` failed, usually a check, and
+    /// nothing was written.
+    Rejected { failed_operation_index: usize },
+}
+
+impl PropertyManagementClient {
+    pub fn new(client: IFabricPropertyManagementClient2) -> Result<Self, Error> {
+        Self::make(client)
+    }
+
+    /// Creates the name `name`, e.g. `fabric:/config/leader`. Fails with
+    /// `FabricErrorCode::NameAlreadyExists` if it's already there.
+    pub async fn create_name(&self, name: &str, timeout_ms: u32) -> Result<(), Error> {
+        run_with_retry("create_name", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx = try_create_name(client.resolve()?, name, timeout_ms)?;
+                rx.recv().await.ok_or(Error::Abandoned("CreateName"))?
+            }
+        })
+        .await
+    }
+
+    /// Deletes `name`. Fails with `FabricErrorCode::NameNotEmpty` while it
+    /// still has properties or services under it.
+    pub async fn delete_name(&self, name: &str, timeout_ms: u32) -> Result<(), Error> {
+        run_with_retry("delete_name", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx = try_delete_name(client.resolve()?, name, timeout_ms)?;
+                rx.recv().await.ok_or(Error::Abandoned("DeleteName"))?
+            }
+        })
+        .await
+    }
+
+    pub async fn name_exists(&self, name: &str, timeout_ms: u32) -> Result<bool, Error> {
+        run_with_retry("name_exists", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx = try_name_exists(client.resolve()?, name, timeout_ms)?;
+                rx.recv().await.ok_or(Error::Abandoned("NameExists"))?
+            }
+        })
+        .await
+    }
+
+    /// Streams the names under `name`, its direct children only unless
+    /// `recursive` is set. Pages are fetched as the stream is read until SF
+    /// reports the enumeration finished. The stream ends after the first
+    /// error. Called outside a tokio runtime, the stream yields
+    /// `Error::NoTokioRuntime`.
+    pub fn enumerate_subnames(
+        &self,
+        name: &str,
+        recursive: bool,
+        timeout_ms: u32,
+    ) -> ReceiverStream<Result<String, Error>> {
+        let client = self.client.clone();
+        let name = name.to_string();
+        let (tx, rx) = mpsc::channel(16);
+        if !in_tokio_runtime() {
+            let _ = tx.try_send(Err(Error::NoTokioRuntime("enumerate_subnames")));
+            return ReceiverStream::new(rx);
+        }
+
+        tokio::spawn(async move {
+            let mut previous: Option<AgileRef<IFabricNameEnumerationResult>> = None;
+            loop {
+                let page = run_with_retry("enumerate_subnames", || {
+                    let client = client.clone();
+                    let name = &name;
+                    let previous = &previous;
+                    async move {
+                        // COM pointers aren't Send, so don't hold one across the await.
+                        let mut rx = {
+                            let previous = previous.as_ref().map(AgileRef::resolve).transpose()?;
+                            try_enumerate_subnames(
+                                client.resolve()?,
+                                name,
+                                previous.as_ref(),
+                                recursive,
+                                timeout_ms,
+                            )?
+                        };
+                        rx.recv()
+                            .await
+                            .ok_or(Error::Abandoned("EnumerateSubNames"))?
+                    }
+                })
+                .await;
+
+                let page = match page {
+                    Ok(page) => page,
+                    Err(e) => {
+                        let _ = tx.send(Err(e)).await;
+                        return;
+                    }
+                };
+
+                for name in page.names {
+                    if tx.send(Ok(name)).await.is_err() {
+                        return;
+                    }
+                }
+
+                if page.finished {
+                    return;
+                }
+                previous = Some(page.result);
+            }
+        });
+
+        ReceiverStream::new(rx)
+    }
+
+    /// Writes `value` to the property `property_name` of `name`, creating the
+    /// property if needed. `name` must already exist.
+    pub async fn put_property(
+        &self,
+        name: &str,
+        property_name: &str,
+        value: &PropertyValue,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        run_with_retry("put_property", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx =
+                    try_put_property(client.resolve()?, name, property_name, value, timeout_ms)?;
+                rx.recv().await.ok_or(Error::Abandoned("PutProperty"))?
+            }
+        })
+        .await
+    }
+
+    /// Like `put_property`, but tags the value with an application defined
+    /// `custom_type_id` that's returned in its `PropertyMetadata`.
+    pub async fn put_custom_property(
+        &self,
+        name: &str,
+        property_name: &str,
+        value: &PropertyValue,
+        custom_type_id: &str,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        run_with_retry("put_custom_property", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx = try_put_custom_property(
+                    client.resolve()?,
+                    name,
+                    property_name,
+                    value,
+                    custom_type_id,
+                    timeout_ms,
+                )?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("PutCustomPropertyOperation"))?
+            }
+        })
+        .await
+    }
+
+    pub async fn get_property(
+        &self,
+        name: &str,
+        property_name: &str,
+        timeout_ms: u32,
+    ) -> Result<NamedProperty, Error> {
+        run_with_retry("get_property", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx = try_get_property(client.resolve()?, name, property_name, timeout_ms)?;
+                rx.recv().await.ok_or(Error::Abandoned("GetProperty"))?
+            }
+        })
+        .await
+    }
+
+    pub async fn delete_property(
+        &self,
+        name: &str,
+        property_name: &str,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        run_with_retry("delete_property", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx =
+                    try_delete_property(client.resolve()?, name, property_name, timeout_ms)?;
+                rx.recv().await.ok_or(Error::Abandoned("DeleteProperty"))?
+            }
+        })
+        .await
+    }
+
+    /// Fetches a property's metadata without transferring its value.
+    pub async fn get_property_metadata(
+        &self,
+        name: &str,
+        property_name: &str,
+        timeout_ms: u32,
+    ) -> Result<PropertyMetadata, Error> {
+        run_with_retry("get_property_metadata", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx =
+                    try_get_property_metadata(client.resolve()?, name, property_name, timeout_ms)?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("GetPropertyMetadata"))?
+            }
+        })
+        .await
+    }
+
+    /// Applies `batch` to the properties of `name` atomically. A failed check
+    /// is not an error; it's reported as `PropertyBatchOutcome::Rejected`.
+    pub async fn submit_property_batch(
+        &self,
+        name: &str,
+        batch: &PropertyBatch,
+        timeout_ms: u32,
+    ) -> Result<PropertyBatchOutcome, Error> {
+        run_with_retry("submit_property_batch", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx = try_submit_property_batch(client.resolve()?, name, batch, timeout_ms)?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("SubmitPropertyBatch"))?
+            }
+        })
+        .await
+    }
+}
+
+fn try_create_name(
+    client: IFabricPropertyManagementClient2,
+    name: &str,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<(), Error>>, Error> {
+    let name = to_wide(name);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe { client.BeginCreateName(name.as_ptr(), timeout_ms, Some(callback)) },
+        move |context| Ok(unsafe { end_client.EndCreateName(context) }?),
+    )
+}
+
+fn try_delete_name(
+    client: IFabricPropertyManagementClient2,
+    name: &str,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<(), Error>>, Error> {
+    let name = to_wide(name);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe { client.BeginDeleteName(name.as_ptr(), timeout_ms, Some(callback)) },
+        move |context| Ok(unsafe { end_client.EndDeleteName(context) }?),
+    )
+}
+
+fn try_name_exists(
+    client: IFabricPropertyManagementClient2,
+    name: &str,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<bool, Error>>, Error> {
+    let name = to_wide(name);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe { client.BeginNameExists(name.as_ptr(), timeout_ms, Some(callback)) },
+        move |context| {
+            let exists = unsafe { end_client.EndNameExists(context) }?;
+            Ok(exists != 0)
+        },
+    )
+}
+
+fn try_enumerate_subnames(
+    client: IFabricPropertyManagementClient2,
+    name: &str,
+    previous: Option<&IFabricNameEnumerationResult>,
+    recursive: bool,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<SubnamePage, Error>>, Error> {
+    let name = to_wide(name);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginEnumerateSubNames(
+                name.as_ptr(),
+                previous,
+                BOOLEAN::from(recursive),
+                timeout_ms,
+                Some(callback),
+            )
+        },
+        move |context| {
+            let result = unsafe { end_client.EndEnumerateSubNames(context) }?;
+            let status = unsafe { result.get_EnumerationStatus() };
+            let mut count = 0;
+            let names = unsafe { result.GetNames(&mut count) }?;
+            let names = unsafe { list_items(names, count) }
+                .iter()
+                .map(|name| unsafe { wide_to_string(*name) })
+                .collect::<Result<_, Error>>()?;
+
+            Ok(SubnamePage {
+                names,
+                result: AgileRef::new(result.cast()?)?,
+                finished: status.0 & FABRIC_ENUMERATION_FINISHED_MASK.0 != 0,
+            })
+        },
+    )
+}
+
+fn try_put_property(
+    client: IFabricPropertyManagementClient2,
+    name: &str,
+    property_name: &str,
+    value: &PropertyValue,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<(), Error>>, Error> {
+    let mut arena = Arena::new();
+    let name = arena.uri(name);
+    let property_name = arena.wide(property_name);
+    // Pick the End* call that matches the Begin* call made below.
+    let end: fn(
+        &IFabricPropertyManagementClient2,
+        Option<&IFabricAsyncOperationContext>,
+    ) -> WindowsResult<()> = match value {
+        PropertyValue::Binary(_) => {
+            |client, context| unsafe { client.EndPutPropertyBinary(context) }
+        }
+        PropertyValue::Int64(_) => |client, context| unsafe { client.EndPutPropertyInt64(context) },
+        PropertyValue::Double(_) => {
+            |client, context| unsafe { client.EndPutPropertyDouble(context) }
+        }
+        PropertyValue::String(_) => {
+            |client, context| unsafe { client.EndPutPropertyWString(context) }
+        }
+        PropertyValue::Guid(_) => |client, context| unsafe { client.EndPutPropertyGuid(context) },
+    };
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            match value {
+                PropertyValue::Binary(data) => client.BeginPutPropertyBinary(
+                    name,
+                    property_name,
+                    data.len() as u32,
+                    data.as_ptr(),
+                    timeout_ms,
+                    Some(callback),
+                ),
+                PropertyValue::Int64(data) => client.BeginPutPropertyInt64(
+                    name,
+                    property_name,
+                    *data,
+                    timeout_ms,
+                    Some(callback),
+                ),
+                PropertyValue::Double(data) => client.BeginPutPropertyDouble(
+                    name,
+                    property_name,
+                    *data,
+                    timeout_ms,
+                    Some(callback),
+                ),
+                PropertyValue::String(data) => client.BeginPutPropertyWString(
+                    name,
+                    property_name,
+                    arena.wide(data),
+                    timeout_ms,
+                    Some(callback),
+                ),
+                PropertyValue::Guid(data) => client.BeginPutPropertyGuid(
+                    name,
+                    property_name,
+                    data,
+                    timeout_ms,
+                    Some(callback),
+                ),
+            }
+        },
+        move |context| {
+            end(&end_client, context)?;
+            Ok(())
+        },
+    )
+}
+
+fn try_put_custom_property(
+    client: IFabricPropertyManagementClient2,
+    name: &str,
+    property_name: &str,
+    value: &PropertyValue,
+    custom_type_id: &str,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<(), Error>>, Error> {
+    let mut arena = Arena::new();
+    let name = arena.uri(name);
+    let operation = FABRIC_PUT_CUSTOM_PROPERTY_OPERATION {
+        PropertyName: arena.wide(property_name),
+        PropertyTypeId: value.type_id().into(),
+        PropertyValue: value.to_raw(&mut arena),
+        PropertyCustomTypeId: arena.wide(custom_type_id),
+        Reserved: ptr::null_mut(),
+    };
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginPutCustomPropertyOperation(name, &operation, timeout_ms, Some(callback))
+        },
+        move |context| Ok(unsafe { end_client.EndPutCustomPropertyOperation(context) }?),
+    )
+}
+
+fn try_get_property(
+    client: IFabricPropertyManagementClient2,
+    name: &str,
+    property_name: &str,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<NamedProperty, Error>>, Error> {
+    let mut arena = Arena::new();
+    let name = arena.uri(name);
+    let property_name = arena.wide(property_name);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginGetProperty(name, property_name, timeout_ms, Some(callback))
+        },
+        move |context| {
+            let res = unsafe { end_client.EndGetProperty(context) }?;
+            NamedProperty::try_from(&res)
+        },
+    )
+}
+
+fn try_delete_property(
+    client: IFabricPropertyManagementClient2,
+    name: &str,
+    property_name: &str,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<(), Error>>, Error> {
+    let mut arena = Arena::new();
+    let name = arena.uri(name);
+    let property_name = arena.wide(property_name);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginDeleteProperty(name, property_name, timeout_ms, Some(callback))
+        },
+        move |context| Ok(unsafe { end_client.EndDeleteProperty(context) }?),
+    )
+}
+
+fn try_get_property_metadata(
+    client: IFabricPropertyManagementClient2,
+    name: &str,
+    property_name: &str,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<PropertyMetadata, Error>>, Error> {
+    let mut arena = Arena::new();
+    let name = arena.uri(name);
+    let property_name = arena.wide(property_name);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginGetPropertyMetadata(name, property_name, timeout_ms, Some(callback))
+        },
+        move |context| {
+            let res = unsafe { end_client.EndGetPropertyMetadata(context) }?;
+            PropertyMetadata::try_from(unsafe { &*res.get_Metadata() })
+        },
+    )
+}
+
+fn try_submit_property_batch(
+    client: IFabricPropertyManagementClient2,
+    name: &str,
+    batch: &PropertyBatch,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<PropertyBatchOutcome, Error>>, Error> {
+    let mut arena = Arena::new();
+    let name = arena.uri(name);
+    let operations = batch
+        .operations
+        .iter()
+        .map(|operation| operation.to_raw(&mut arena))
+        .collect::<Vec<_>>();
+    let operation_count = operations.len() as u32;
+    let get_operation_indexes = batch.get_operation_indexes();
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginSubmitPropertyBatch(
+                name,
+                operation_count,
+                operations.as_ptr(),
+                timeout_ms,
+                Some(callback),
+            )
+        },
+        move |context| {
+            let mut failed_operation_index = u32::MAX;
+            let res =
+                unsafe { end_client.EndSubmitPropertyBatch(context, &mut failed_operation_index) };
+            batch_outcome(
+                res.map_err(Error::from),
+                failed_operation_index,
+                operation_count,
+                |res| {
+                    get_operation_indexes
+                        .iter()
+                        .map(|index| {
+                            let property = unsafe { res.GetProperty(*index) }?;
+                            NamedProperty::try_from(&property)
+                        })
+                        .collect()
+                },
+            )
+        },
+    )
+}
+
+/// Maps the end of a batch submission to its outcome. SF reports the failed
+/// operation through `failed_operation_index` whether it fails the whole call
+/// with `FabricErrorCode::PropertyCheckFailed` or not, so the index is checked
+/// before the error is.
+fn batch_outcome<T>(
+    res: Result<T, Error>,
+    failed_operation_index: u32,
+    operation_count: u32,
+    properties: impl FnOnce(T) -> Result<Vec<NamedProperty>, Error>,
+) -> Result<PropertyBatchOutcome, Error> {
+    let rejected =
+        (failed_operation_index < operation_count).then(|| PropertyBatchOutcome::Rejected {
+            failed_operation_index: failed_operation_index as usize,
+        });
+
+    match (res, rejected) {
+        (Ok(_), Some(rejected)) => Ok(rejected),
+        (Err(e), Some(rejected))
+            if e.fabric_error_code() == Some(FabricErrorCode::PropertyCheckFailed) =>
+        {
+            Ok(rejected)
+        }
+        (Ok(res), None) => Ok(PropertyBatchOutcome::Committed {
+            properties: properties(res)?,
+        }),
+        (Err(e), _) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use windows::core::HRESULT;
+
+    use super::*;
+
+    #[test]
+    fn batch_get_operations_are_indexed_in_request_order() {
+        let batch = PropertyBatch::new()
+            .get("term")
+            .check_sequence("leader", 7)
+            .put("leader", PropertyValue::String("node-1".to_string()))
+            .get("leader");
+
+        assert_eq!(batch.operations.len(), 4);
+        assert_eq!(batch.get_operation_indexes(), vec![0, 3]);
+    }
+
+    #[test]
+    fn property_values_to_raw() {
+        let mut arena = Arena::new();
+
+        let raw = PropertyValue::Binary(vec![1, 2, 3]).to_raw(&mut arena);
+        let buffer = unsafe { &*(raw as *const FABRIC_OPERATION_DATA_BUFFER) };
+        assert_eq!(buffer.BufferSize, 3);
+        assert_eq!(
+            unsafe { list_items(buffer.Buffer, buffer.BufferSize) },
+            &[1, 2, 3]
+        );
+
+        let raw = PropertyValue::Binary(vec![]).to_raw(&mut arena);
+        let buffer = unsafe { &*(raw as *const FABRIC_OPERATION_DATA_BUFFER) };
+        assert_eq!(buffer.BufferSize, 0);
+        assert!(buffer.Buffer.is_null());
+
+        let raw = PropertyValue::String("node-1".to_string()).to_raw(&mut arena);
+        assert_eq!(
+            unsafe { wide_to_string(raw as *const u16) }.unwrap(),
+            "node-1"
+        );
+
+        let raw = PropertyValue::Int64(-7).to_raw(&mut arena);
+        assert_eq!(unsafe { *(raw as *const i64) }, -7);
+    }
+
+    #[test]
+    fn batch_operations_to_raw() {
+        let batch = PropertyBatch::new()
+            .check_exists("leader", true)
+            .check_sequence("leader", 7)
+            .check_value("term", PropertyValue::Int64(3))
+            .put("leader", PropertyValue::String("node-1".to_string()))
+            .put_custom("config", PropertyValue::Binary(vec![1]), "json")
+            .get("term")
+            .delete("candidate");
+        let mut arena = Arena::new();
+        let raw = batch
+            .operations
+            .iter()
+            .map(|operation| operation.to_raw(&mut arena))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            raw.iter()
+                .map(|operation| operation.Kind)
+                .collect::<Vec<_>>(),
+            vec![
+                FABRIC_PROPERTY_BATCH_OPERATION_KIND_CHECK_EXISTS,
+                FABRIC_PROPERTY_BATCH_OPERATION_KIND_CHECK_SEQUENCE,
+                FABRIC_PROPERTY_BATCH_OPERATION_KIND_CHECK_VALUE,
+                FABRIC_PROPERTY_BATCH_OPERATION_KIND_PUT,
+                FABRIC_PROPERTY_BATCH_OPERATION_KIND_PUT_CUSTOM,
+                FABRIC_PROPERTY_BATCH_OPERATION_KIND_GET,
+                FABRIC_PROPERTY_BATCH_OPERATION_KIND_DELETE,
+            ]
+        );
+
+        let check = unsafe { &*(raw[0].Value as *const FABRIC_CHECK_EXISTS_PROPERTY_OPERATION) };
+        assert_eq!(
+            unsafe { wide_to_string(check.PropertyName.0) }.unwrap(),
+            "leader"
+        );
+        assert!(check.ExistenceCheck.as_bool());
+
+        let check = unsafe { &*(raw[1].Value as *const FABRIC_CHECK_SEQUENCE_PROPERTY_OPERATION) };
+        assert_eq!(check.SequenceNumber, 7);
+
+        let check = unsafe { &*(raw[2].Value as *const FABRIC_CHECK_VALUE_PROPERTY_OPERATION) };
+        assert_eq!(check.PropertyTypeId, FABRIC_PROPERTY_TYPE_INT64);
+        assert_eq!(unsafe { *(check.PropertyValue as *const i64) }, 3);
+
+        let put = unsafe { &*(raw[3].Value as *const FABRIC_PUT_PROPERTY_OPERATION) };
+        assert_eq!(put.PropertyTypeId, FABRIC_PROPERTY_TYPE_WSTRING);
+        assert_eq!(
+            unsafe { wide_to_string(put.PropertyValue as *const u16) }.unwrap(),
+            "node-1"
+        );
+
+        let put = unsafe { &*(raw[4].Value as *const FABRIC_PUT_CUSTOM_PROPERTY_OPERATION) };
+        assert_eq!(put.PropertyTypeId, FABRIC_PROPERTY_TYPE_BINARY);
+        assert_eq!(
+            unsafe { wide_to_string(put.PropertyCustomTypeId.0) }.unwrap(),
+            "json"
+        );
+
+        let get = unsafe { &*(raw[5].Value as *const FABRIC_GET_PROPERTY_OPERATION) };
+        assert!(get.IncludeValue.as_bool());
+
+        let delete = unsafe { &*(raw[6].Value as *const FABRIC_DELETE_PROPERTY_OPERATION) };
+        assert_eq!(
+            unsafe { wide_to_string(delete.PropertyName.0) }.unwrap(),
+            "candidate"
+        );
+    }
+
+    #[test]
+    fn batch_outcome_prefers_the_failed_operation_index() {
+        let check_failed = || {
+            Error::from(windows::core::Error::from(HRESULT(
+                FabricErrorCode::PropertyCheckFailed as i32,
+            )))
+        };
+        let no_properties = |_| Ok(vec![]);
+
+        assert_eq!(
+            batch_outcome(Ok(()), u32::MAX, 3, no_properties).unwrap(),
+            PropertyBatchOutcome::Committed { properties: vec![] }
+        );
+        assert_eq!(
+            batch_outcome(Ok(()), 1, 3, no_properties).unwrap(),
+            PropertyBatchOutcome::Rejected {
+                failed_operation_index: 1
+            }
+        );
+        assert_eq!(
+            batch_outcome(Err::<(), _>(check_failed()), 2, 3, no_properties).unwrap(),
+            PropertyBatchOutcome::Rejected {
+                failed_operation_index: 2
+            }
+        );
+
+        // Without a valid index there's nothing to report as rejected.
+        assert!(batch_outcome(Err::<(), _>(check_failed()), u32::MAX, 3, no_properties).is_err());
+        assert!(batch_outcome(
+            Err::<(), _>(Error::Abandoned("SubmitPropertyBatch")),
+            0,
+            3,
+            no_properties
+        )
+        .is_err());
+    }
+}
@@ -1,12 +1,25 @@
 use std::{ffi::OsString, os::windows::ffi::OsStrExt, ptr, slice};
 
 use tokio::sync::mpsc;
-use windows::core::{implement, ComInterface, GUID};
+use windows::core::{implement, ComInterface, GUID, PCWSTR};
 
 use crate::{
-    agile::AgileRef, channel_send, error::Error, run_with_retry, IFabricAsyncOperationCallback,
-    IFabricAsyncOperationCallback_Impl, IFabricAsyncOperationContext, IFabricQueryClient12,
-    MakeClient, PartitionQueryResultItem, FABRIC_SERVICE_PARTITION_QUERY_DESCRIPTION,
+    agile::AgileRef, callback::begin_async, channel_send, error::Error, list_items, optional_wide,
+    run_with_retry, to_wide, ApplicationLoadInformation, ClusterLoadInformation,
+    DeployedApplication, DeployedCodePackage, DeployedReplica, DeployedServicePackage,
+    DeployedServiceType, IFabricAsyncOperationCallback, IFabricAsyncOperationCallback_Impl,
+    IFabricAsyncOperationContext, IFabricQueryClient12, MakeClient, Node, NodeLoadInformation,
+    PartitionLoadInformation, PartitionQueryResultItem, QueryServicePartitionStatus,
+    ReplicaLoadInformation, ServicePartitionInformation, ServiceType, StatefulService,
+    StatelessService, UnplacedReplicaInformation,
+    FABRIC_APPLICATION_LOAD_INFORMATION_QUERY_DESCRIPTION,
+    FABRIC_DEPLOYED_APPLICATION_QUERY_DESCRIPTION, FABRIC_DEPLOYED_CODE_PACKAGE_QUERY_DESCRIPTION,
+    FABRIC_DEPLOYED_SERVICE_PACKAGE_QUERY_DESCRIPTION,
+    FABRIC_DEPLOYED_SERVICE_REPLICA_QUERY_DESCRIPTION,
+    FABRIC_DEPLOYED_SERVICE_TYPE_QUERY_DESCRIPTION, FABRIC_NODE_LOAD_INFORMATION_QUERY_DESCRIPTION,
+    FABRIC_NODE_QUERY_DESCRIPTION, FABRIC_PARTITION_LOAD_INFORMATION_QUERY_DESCRIPTION,
+    FABRIC_REPLICA_LOAD_INFORMATION_QUERY_DESCRIPTION, FABRIC_SERVICE_PARTITION_QUERY_DESCRIPTION,
+    FABRIC_SERVICE_TYPE_QUERY_DESCRIPTION, FABRIC_UNPLACED_REPLICA_INFORMATION_QUERY_DESCRIPTION,
 };
 
 #[derive(Debug, Clone)]
@@ -34,13 +47,19 @@ impl QueryClient {
     pub async fn get_partition_list(
         &self,
         service_name: &str,
+        partition_id_filter: Option<GUID>,
         timeout_ms: u32,
     ) -> Result<Vec<PartitionQueryResultItem>, Error> {
         let client = self.client.clone();
         run_with_retry("get_partition_list", move || {
             let client = client.clone();
             async move {
-                let mut rx = try_get_partition_list(client.resolve()?, service_name, timeout_ms)?;
+                let mut rx = try_get_partition_list(
+                    client.resolve()?,
+                    service_name,
+                    partition_id_filter,
+                    timeout_ms,
+                )?;
                 rx.recv()
                     .await
                     .ok_or(Error::Abandoned("GetPartitionList"))?
@@ -48,11 +67,383 @@ impl QueryClient {
         })
         .await
     }
+
+    pub async fn get_node_list(
+        &self,
+        node_name_filter: Option<&str>,
+        timeout_ms: u32,
+    ) -> Result<Vec<Node>, Error> {
+        let client = self.client.clone();
+        run_with_retry("get_node_list", move || {
+            let client = client.clone();
+            async move {
+                let mut rx = try_get_node_list(client.resolve()?, node_name_filter, timeout_ms)?;
+                rx.recv().await.ok_or(Error::Abandoned("GetNodeList"))?
+            }
+        })
+        .await
+    }
+
+    pub async fn get_deployed_application_list(
+        &self,
+        node_name: &str,
+        application_name_filter: Option<&str>,
+        timeout_ms: u32,
+    ) -> Result<Vec<DeployedApplication>, Error> {
+        let client = self.client.clone();
+        run_with_retry("get_deployed_application_list", move || {
+            let client = client.clone();
+            async move {
+                let mut rx = try_get_deployed_application_list(
+                    client.resolve()?,
+                    node_name,
+                    application_name_filter,
+                    timeout_ms,
+                )?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("GetDeployedApplicationList"))?
+            }
+        })
+        .await
+    }
+
+    pub async fn get_deployed_service_package_list(
+        &self,
+        node_name: &str,
+        application_name: &str,
+        service_manifest_name_filter: Option<&str>,
+        timeout_ms: u32,
+    ) -> Result<Vec<DeployedServicePackage>, Error> {
+        let client = self.client.clone();
+        run_with_retry("get_deployed_service_package_list", move || {
+            let client = client.clone();
+            async move {
+                let mut rx = try_get_deployed_service_package_list(
+                    client.resolve()?,
+                    node_name,
+                    application_name,
+                    service_manifest_name_filter,
+                    timeout_ms,
+                )?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("GetDeployedServicePackageList"))?
+            }
+        })
+        .await
+    }
+
+    pub async fn get_deployed_code_package_list(
+        &self,
+        node_name: &str,
+        application_name: &str,
+        service_manifest_name_filter: Option<&str>,
+        code_package_name_filter: Option<&str>,
+        timeout_ms: u32,
+    ) -> Result<Vec<DeployedCodePackage>, Error> {
+        let client = self.client.clone();
+        run_with_retry("get_deployed_code_package_list", move || {
+            let client = client.clone();
+            async move {
+                let mut rx = try_get_deployed_code_package_list(
+                    client.resolve()?,
+                    node_name,
+                    application_name,
+                    service_manifest_name_filter,
+                    code_package_name_filter,
+                    timeout_ms,
+                )?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("GetDeployedCodePackageList"))?
+            }
+        })
+        .await
+    }
+
+    pub async fn get_deployed_replica_list(
+        &self,
+        node_name: &str,
+        application_name: &str,
+        service_manifest_name_filter: Option<&str>,
+        partition_id_filter: Option<GUID>,
+        timeout_ms: u32,
+    ) -> Result<Vec<DeployedReplica>, Error> {
+        let client = self.client.clone();
+        run_with_retry("get_deployed_replica_list", move || {
+            let client = client.clone();
+            async move {
+                let mut rx = try_get_deployed_replica_list(
+                    client.resolve()?,
+                    node_name,
+                    application_name,
+                    service_manifest_name_filter,
+                    partition_id_filter,
+                    timeout_ms,
+                )?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("GetDeployedReplicaList"))?
+            }
+        })
+        .await
+    }
+
+    pub async fn get_cluster_load_information(
+        &self,
+        timeout_ms: u32,
+    ) -> Result<ClusterLoadInformation, Error> {
+        let client = self.client.clone();
+        run_with_retry("get_cluster_load_information", move || {
+            let client = client.clone();
+            async move {
+                let mut rx = try_get_cluster_load_information(client.resolve()?, timeout_ms)?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("GetClusterLoadInformation"))?
+            }
+        })
+        .await
+    }
+
+    pub async fn get_node_load_information(
+        &self,
+        node_name: &str,
+        timeout_ms: u32,
+    ) -> Result<NodeLoadInformation, Error> {
+        let client = self.client.clone();
+        run_with_retry("get_node_load_information", move || {
+            let client = client.clone();
+            async move {
+                let mut rx =
+                    try_get_node_load_information(client.resolve()?, node_name, timeout_ms)?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("GetNodeLoadInformation"))?
+            }
+        })
+        .await
+    }
+
+    pub async fn get_partition_load_information(
+        &self,
+        partition_id: GUID,
+        timeout_ms: u32,
+    ) -> Result<PartitionLoadInformation, Error> {
+        let client = self.client.clone();
+        run_with_retry("get_partition_load_information", move || {
+            let client = client.clone();
+            async move {
+                let mut rx = try_get_partition_load_information(
+                    client.resolve()?,
+                    partition_id,
+                    timeout_ms,
+                )?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("GetPartitionLoadInformation"))?
+            }
+        })
+        .await
+    }
+
+    pub async fn get_replica_load_information(
+        &self,
+        partition_id: GUID,
+        replica_or_instance_id: i64,
+        timeout_ms: u32,
+    ) -> Result<ReplicaLoadInformation, Error> {
+        let client = self.client.clone();
+        run_with_retry("get_replica_load_information", move || {
+            let client = client.clone();
+            async move {
+                let mut rx = try_get_replica_load_information(
+                    client.resolve()?,
+                    partition_id,
+                    replica_or_instance_id,
+                    timeout_ms,
+                )?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("GetReplicaLoadInformation"))?
+            }
+        })
+        .await
+    }
+
+    pub async fn get_application_load_information(
+        &self,
+        application_name: &str,
+        timeout_ms: u32,
+    ) -> Result<ApplicationLoadInformation, Error> {
+        let client = self.client.clone();
+        run_with_retry("get_application_load_information", move || {
+            let client = client.clone();
+            async move {
+                let mut rx = try_get_application_load_information(
+                    client.resolve()?,
+                    application_name,
+                    timeout_ms,
+                )?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("GetApplicationLoadInformation"))?
+            }
+        })
+        .await
+    }
+
+    /// Returns the reasons the placement and load balancer gave for not being
+    /// able to place replicas of the given partition. Passing `None` for the
+    /// partition queries all partitions of the service.
+    pub async fn get_unplaced_replica_information(
+        &self,
+        service_name: &str,
+        partition_id: Option<GUID>,
+        only_query_primaries: bool,
+        timeout_ms: u32,
+    ) -> Result<UnplacedReplicaInformation, Error> {
+        let client = self.client.clone();
+        run_with_retry("get_unplaced_replica_information", move || {
+            let client = client.clone();
+            async move {
+                let mut rx = try_get_unplaced_replica_information(
+                    client.resolve()?,
+                    service_name,
+                    partition_id,
+                    only_query_primaries,
+                    timeout_ms,
+                )?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("GetUnplacedReplicaInformation"))?
+            }
+        })
+        .await
+    }
+
+    pub async fn get_service_type_list(
+        &self,
+        application_type_name: &str,
+        application_type_version: &str,
+        service_type_name_filter: Option<&str>,
+        timeout_ms: u32,
+    ) -> Result<Vec<ServiceType>, Error> {
+        let client = self.client.clone();
+        run_with_retry("get_service_type_list", move || {
+            let client = client.clone();
+            async move {
+                let mut rx = try_get_service_type_list(
+                    client.resolve()?,
+                    application_type_name,
+                    application_type_version,
+                    service_type_name_filter,
+                    timeout_ms,
+                )?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("GetServiceTypeList"))?
+            }
+        })
+        .await
+    }
+
+    pub async fn get_deployed_service_type_list(
+        &self,
+        node_name: &str,
+        application_name: &str,
+        service_manifest_name_filter: Option<&str>,
+        service_type_name_filter: Option<&str>,
+        timeout_ms: u32,
+    ) -> Result<Vec<DeployedServiceType>, Error> {
+        let client = self.client.clone();
+        run_with_retry("get_deployed_service_type_list", move || {
+            let client = client.clone();
+            async move {
+                let mut rx = try_get_deployed_service_type_list(
+                    client.resolve()?,
+                    node_name,
+                    application_name,
+                    service_manifest_name_filter,
+                    service_type_name_filter,
+                    timeout_ms,
+                )?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("GetDeployedServiceTypeList"))?
+            }
+        })
+        .await
+    }
+}
+
+impl StatefulService {
+    /// Fetches the unplaced replica diagnostics for this partition if it isn't
+    /// `Ready`. Returns `None` for partitions that are.
+    pub async fn unplaced_replica_information(
+        &self,
+        query_client: &QueryClient,
+        service_name: &str,
+        timeout_ms: u32,
+    ) -> Result<Option<UnplacedReplicaInformation>, Error> {
+        unplaced_replica_information_if_not_ready(
+            query_client,
+            service_name,
+            &self.partition_information,
+            self.partition_status,
+            timeout_ms,
+        )
+        .await
+    }
+}
+
+impl StatelessService {
+    /// Fetches the unplaced replica diagnostics for this partition if it isn't
+    /// `Ready`. Returns `None` for partitions that are.
+    pub async fn unplaced_replica_information(
+        &self,
+        query_client: &QueryClient,
+        service_name: &str,
+        timeout_ms: u32,
+    ) -> Result<Option<UnplacedReplicaInformation>, Error> {
+        unplaced_replica_information_if_not_ready(
+            query_client,
+            service_name,
+            &self.partition_information,
+            self.partition_status,
+            timeout_ms,
+        )
+        .await
+    }
+}
+
+async fn unplaced_replica_information_if_not_ready(
+    query_client: &QueryClient,
+    service_name: &str,
+    partition_information: &ServicePartitionInformation,
+    partition_status: QueryServicePartitionStatus,
+    timeout_ms: u32,
+) -> Result<Option<UnplacedReplicaInformation>, Error> {
+    if partition_status == QueryServicePartitionStatus::Ready {
+        return Ok(None);
+    }
+
+    query_client
+        .get_unplaced_replica_information(
+            service_name,
+            Some(partition_information.partition_id()),
+            false,
+            timeout_ms,
+        )
+        .await
+        .map(Some)
 }
 
 fn try_get_partition_list(
     client: IFabricQueryClient12,
     service_name: &str,
+    partition_id_filter: Option<GUID>,
     timeout_ms: u32,
 ) -> Result<mpsc::Receiver<Result<Vec<PartitionQueryResultItem>, Error>>, Error> {
     let service_name = OsString::from(service_name);
@@ -60,7 +451,7 @@ fn try_get_partition_list(
 
     let query_desc = FABRIC_SERVICE_PARTITION_QUERY_DESCRIPTION {
         ServiceName: service_name.as_mut_ptr(),
-        PartitionIdFilter: GUID::zeroed(),
+        PartitionIdFilter: partition_id_filter.unwrap_or_else(GUID::zeroed),
         Reserved: ptr::null_mut(),
     };
     let (tx, rx) = mpsc::channel(1);
@@ -102,3 +493,365 @@ impl IFabricAsyncOperationCallback_Impl for GetPartitionListCallback {
         }
     }
 }
+
+fn try_get_node_list(
+    client: IFabricQueryClient12,
+    node_name_filter: Option<&str>,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<Vec<Node>, Error>>, Error> {
+    let node_name_filter = node_name_filter.map(to_wide);
+    let query_desc = FABRIC_NODE_QUERY_DESCRIPTION {
+        NodeNameFilter: PCWSTR(optional_wide(&node_name_filter)),
+        Reserved: ptr::null_mut(),
+    };
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe { client.BeginGetNodeList(&query_desc, timeout_ms, Some(callback)) },
+        move |context| {
+            let res = unsafe { end_client.EndGetNodeList(context) }?;
+            let list = unsafe { &*res.get_NodeList() };
+            unsafe { list_items(list.Items, list.Count) }
+                .iter()
+                .map(Node::try_from)
+                .collect()
+        },
+    )
+}
+
+fn try_get_deployed_application_list(
+    client: IFabricQueryClient12,
+    node_name: &str,
+    application_name_filter: Option<&str>,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<Vec<DeployedApplication>, Error>>, Error> {
+    let node_name = to_wide(node_name);
+    let application_name_filter = application_name_filter.map(to_wide);
+    let query_desc = FABRIC_DEPLOYED_APPLICATION_QUERY_DESCRIPTION {
+        NodeName: PCWSTR(node_name.as_ptr()),
+        ApplicationNameFilter: optional_wide(&application_name_filter) as *mut u16,
+        Reserved: ptr::null_mut(),
+    };
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginGetDeployedApplicationList(&query_desc, timeout_ms, Some(callback))
+        },
+        move |context| {
+            let res = unsafe { end_client.EndGetDeployedApplicationList(context) }?;
+            let list = unsafe { &*res.get_DeployedApplicationList() };
+            unsafe { list_items(list.Items, list.Count) }
+                .iter()
+                .map(DeployedApplication::try_from)
+                .collect()
+        },
+    )
+}
+
+fn try_get_deployed_service_package_list(
+    client: IFabricQueryClient12,
+    node_name: &str,
+    application_name: &str,
+    service_manifest_name_filter: Option<&str>,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<Vec<DeployedServicePackage>, Error>>, Error> {
+    let node_name = to_wide(node_name);
+    let mut application_name = to_wide(application_name);
+    let service_manifest_name_filter = service_manifest_name_filter.map(to_wide);
+    let query_desc = FABRIC_DEPLOYED_SERVICE_PACKAGE_QUERY_DESCRIPTION {
+        NodeName: PCWSTR(node_name.as_ptr()),
+        ApplicationName: application_name.as_mut_ptr(),
+        ServiceManifestNameFilter: PCWSTR(optional_wide(&service_manifest_name_filter)),
+        Reserved: ptr::null_mut(),
+    };
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginGetDeployedServicePackageList(&query_desc, timeout_ms, Some(callback))
+        },
+        move |context| {
+            let res = unsafe { end_client.EndGetDeployedServicePackageList(context) }?;
+            let list = unsafe { &*res.get_DeployedServicePackageList() };
+            unsafe { list_items(list.Items, list.Count) }
+                .iter()
+                .map(DeployedServicePackage::try_from)
+                .collect()
+        },
+    )
+}
+
+fn try_get_deployed_code_package_list(
+    client: IFabricQueryClient12,
+    node_name: &str,
+    application_name: &str,
+    service_manifest_name_filter: Option<&str>,
+    code_package_name_filter: Option<&str>,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<Vec<DeployedCodePackage>, Error>>, Error> {
+    let node_name = to_wide(node_name);
+    let mut application_name = to_wide(application_name);
+    let service_manifest_name_filter = service_manifest_name_filter.map(to_wide);
+    let code_package_name_filter = code_package_name_filter.map(to_wide);
+    let query_desc = FABRIC_DEPLOYED_CODE_PACKAGE_QUERY_DESCRIPTION {
+        NodeName: PCWSTR(node_name.as_ptr()),
+        ApplicationName: application_name.as_mut_ptr(),
+        ServiceManifestNameFilter: PCWSTR(optional_wide(&service_manifest_name_filter)),
+        CodePackageNameFilter: PCWSTR(optional_wide(&code_package_name_filter)),
+        Reserved: ptr::null_mut(),
+    };
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginGetDeployedCodePackageList(&query_desc, timeout_ms, Some(callback))
+        },
+        move |context| {
+            let res = unsafe { end_client.EndGetDeployedCodePackageList(context) }?;
+            let list = unsafe { &*res.get_DeployedCodePackageList() };
+            unsafe { list_items(list.Items, list.Count) }
+                .iter()
+                .map(DeployedCodePackage::try_from)
+                .collect()
+        },
+    )
+}
+
+fn try_get_deployed_replica_list(
+    client: IFabricQueryClient12,
+    node_name: &str,
+    application_name: &str,
+    service_manifest_name_filter: Option<&str>,
+    partition_id_filter: Option<GUID>,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<Vec<DeployedReplica>, Error>>, Error> {
+    let node_name = to_wide(node_name);
+    let mut application_name = to_wide(application_name);
+    let service_manifest_name_filter = service_manifest_name_filter.map(to_wide);
+    let query_desc = FABRIC_DEPLOYED_SERVICE_REPLICA_QUERY_DESCRIPTION {
+        NodeName: PCWSTR(node_name.as_ptr()),
+        ApplicationName: application_name.as_mut_ptr(),
+        ServiceManifestNameFilter: PCWSTR(optional_wide(&service_manifest_name_filter)),
+        PartitionIdFilter: partition_id_filter.unwrap_or_else(GUID::zeroed),
+        Reserved: ptr::null_mut(),
+    };
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginGetDeployedReplicaList(&query_desc, timeout_ms, Some(callback))
+        },
+        move |context| {
+            let res = unsafe { end_client.EndGetDeployedReplicaList(context) }?;
+            let list = unsafe { &*res.get_DeployedReplicaList() };
+            unsafe { list_items(list.Items, list.Count) }
+                .iter()
+                .map(DeployedReplica::try_from)
+                .collect()
+        },
+    )
+}
+
+fn try_get_cluster_load_information(
+    client: IFabricQueryClient12,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<ClusterLoadInformation, Error>>, Error> {
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe { client.BeginGetClusterLoadInformation(timeout_ms, Some(callback)) },
+        move |context| {
+            let res = unsafe { end_client.EndGetClusterLoadInformation(context) }?;
+            ClusterLoadInformation::try_from(unsafe { &*res.get_ClusterLoadInformation() })
+        },
+    )
+}
+
+fn try_get_node_load_information(
+    client: IFabricQueryClient12,
+    node_name: &str,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<NodeLoadInformation, Error>>, Error> {
+    let node_name = to_wide(node_name);
+    let query_desc = FABRIC_NODE_LOAD_INFORMATION_QUERY_DESCRIPTION {
+        NodeName: PCWSTR(node_name.as_ptr()),
+        Reserved: ptr::null_mut(),
+    };
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginGetNodeLoadInformation(&query_desc, timeout_ms, Some(callback))
+        },
+        move |context| {
+            let res = unsafe { end_client.EndGetNodeLoadInformation(context) }?;
+            NodeLoadInformation::try_from(unsafe { &*res.get_NodeLoadInformation() })
+        },
+    )
+}
+
+fn try_get_partition_load_information(
+    client: IFabricQueryClient12,
+    partition_id: GUID,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<PartitionLoadInformation, Error>>, Error> {
+    let query_desc = FABRIC_PARTITION_LOAD_INFORMATION_QUERY_DESCRIPTION {
+        PartitionId: partition_id,
+        Reserved: ptr::null_mut(),
+    };
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginGetPartitionLoadInformation(&query_desc, timeout_ms, Some(callback))
+        },
+        move |context| {
+            let res = unsafe { end_client.EndGetPartitionLoadInformation(context) }?;
+            PartitionLoadInformation::try_from(unsafe { &*res.get_PartitionLoadInformation() })
+        },
+    )
+}
+
+fn try_get_replica_load_information(
+    client: IFabricQueryClient12,
+    partition_id: GUID,
+    replica_or_instance_id: i64,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<ReplicaLoadInformation, Error>>, Error> {
+    let query_desc = FABRIC_REPLICA_LOAD_INFORMATION_QUERY_DESCRIPTION {
+        PartitionId: partition_id,
+        ReplicaOrInstanceId: replica_or_instance_id,
+        Reserved: ptr::null_mut(),
+    };
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginGetReplicaLoadInformation(&query_desc, timeout_ms, Some(callback))
+        },
+        move |context| {
+            let res = unsafe { end_client.EndGetReplicaLoadInformation(context) }?;
+            ReplicaLoadInformation::try_from(unsafe { &*res.get_ReplicaLoadInformation() })
+        },
+    )
+}
+
+fn try_get_application_load_information(
+    client: IFabricQueryClient12,
+    application_name: &str,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<ApplicationLoadInformation, Error>>, Error> {
+    let application_name = to_wide(application_name);
+    let query_desc = FABRIC_APPLICATION_LOAD_INFORMATION_QUERY_DESCRIPTION {
+        ApplicationName: PCWSTR(application_name.as_ptr()),
+        Reserved: ptr::null_mut(),
+    };
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginGetApplicationLoadInformation(&query_desc, timeout_ms, Some(callback))
+        },
+        move |context| {
+            let res = unsafe { end_client.EndGetApplicationLoadInformation(context) }?;
+            ApplicationLoadInformation::try_from(unsafe { &*res.get_ApplicationLoadInformation() })
+        },
+    )
+}
+
+fn try_get_unplaced_replica_information(
+    client: IFabricQueryClient12,
+    service_name: &str,
+    partition_id: Option<GUID>,
+    only_query_primaries: bool,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<UnplacedReplicaInformation, Error>>, Error> {
+    let mut service_name = to_wide(service_name);
+    let query_desc = FABRIC_UNPLACED_REPLICA_INFORMATION_QUERY_DESCRIPTION {
+        ServiceName: service_name.as_mut_ptr(),
+        PartitionId: partition_id.unwrap_or_else(GUID::zeroed),
+        OnlyQueryPrimaries: only_query_primaries.into(),
+        Reserved: ptr::null_mut(),
+    };
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginGetUnplacedReplicaInformation(&query_desc, timeout_ms, Some(callback))
+        },
+        move |context| {
+            let res = unsafe { end_client.EndGetUnplacedReplicaInformation(context) }?;
+            UnplacedReplicaInformation::try_from(unsafe { &*res.get_UnplacedReplicaInformation() })
+        },
+    )
+}
+
+fn try_get_service_type_list(
+    client: IFabricQueryClient12,
+    application_type_name: &str,
+    application_type_version: &str,
+    service_type_name_filter: Option<&str>,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<Vec<ServiceType>, Error>>, Error> {
+    let application_type_name = to_wide(application_type_name);
+    let application_type_version = to_wide(application_type_version);
+    let service_type_name_filter = service_type_name_filter.map(to_wide);
+    let query_desc = FABRIC_SERVICE_TYPE_QUERY_DESCRIPTION {
+        ApplicationTypeName: PCWSTR(application_type_name.as_ptr()),
+        ApplicationTypeVersion: PCWSTR(application_type_version.as_ptr()),
+        ServiceTypeNameFilter: PCWSTR(optional_wide(&service_type_name_filter)),
+        Reserved: ptr::null_mut(),
+    };
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginGetServiceTypeList(&query_desc, timeout_ms, Some(callback))
+        },
+        move |context| {
+            let res = unsafe { end_client.EndGetServiceTypeList(context) }?;
+            let list = unsafe { &*res.get_ServiceTypeList() };
+            unsafe { list_items(list.Items, list.Count) }
+                .iter()
+                .map(ServiceType::try_from)
+                .collect()
+        },
+    )
+}
+
+fn try_get_deployed_service_type_list(
+    client: IFabricQueryClient12,
+    node_name: &str,
+    application_name: &str,
+    service_manifest_name_filter: Option<&str>,
+    service_type_name_filter: Option<&str>,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<Vec<DeployedServiceType>, Error>>, Error> {
+    let node_name = to_wide(node_name);
+    let mut application_name = to_wide(application_name);
+    let service_manifest_name_filter = service_manifest_name_filter.map(to_wide);
+    let service_type_name_filter = service_type_name_filter.map(to_wide);
+    let query_desc = FABRIC_DEPLOYED_SERVICE_TYPE_QUERY_DESCRIPTION {
+        NodeName: PCWSTR(node_name.as_ptr()),
+        ApplicationName: application_name.as_mut_ptr(),
+        ServiceManifestNameFilter: PCWSTR(optional_wide(&service_manifest_name_filter)),
+        ServiceTypeNameFilter: PCWSTR(optional_wide(&service_type_name_filter)),
+        Reserved: ptr::null_mut(),
+    };
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginGetDeployedServiceTypeList(&query_desc, timeout_ms, Some(callback))
+        },
+        move |context| {
+            let res = unsafe { end_client.EndGetDeployedServiceTypeList(context) }?;
+            let list = unsafe { &*res.get_DeployedServiceTypeList() };
+            unsafe { list_items(list.Items, list.Count) }
+                .iter()
+                .map(DeployedServiceType::try_from)
+                .collect()
+        },
+    )
+}
@@ -1,16 +1,21 @@
 use std::{
     ffi::{c_void, OsString},
     os::windows::ffi::OsStrExt,
+    ptr,
 };
 
 use tokio::sync::mpsc;
-use windows::core::{implement, ComInterface};
+use windows::core::{implement, ComInterface, GUID, PCWSTR};
 
 use crate::{
-    agile::AgileRef, channel_send, error::Error, run_with_retry, types::ServicePartition,
+    agile::AgileRef, arena::Arena, callback::begin_async, channel_send, error::Error,
+    run_with_retry, to_wide, types::ServicePartition, wide_to_string,
     IFabricAsyncOperationCallback, IFabricAsyncOperationCallback_Impl,
     IFabricAsyncOperationContext, IFabricResolvedServicePartitionResult,
-    IFabricServiceManagementClient7, MakeClient, PartitionKeyType,
+    IFabricServiceManagementClient7, MakeClient, PartitionKeyType, ServiceDescription,
+    ServiceFromTemplateDescription, ServiceUpdateDescription, FABRIC_DELETE_SERVICE_DESCRIPTION,
+    FABRIC_REMOVE_REPLICA_DESCRIPTION, FABRIC_REMOVE_REPLICA_DESCRIPTION_EX1,
+    FABRIC_RESTART_REPLICA_DESCRIPTION,
 };
 
 #[derive(Debug, Clone)]
@@ -59,6 +64,175 @@ impl ServiceManagementClient {
         })
         .await
     }
+
+    /// Creates the service described by `description`.
+    pub async fn create_service(
+        &self,
+        description: &ServiceDescription,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        description.validate()?;
+        run_with_retry("create_service", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx = try_create_service(client.resolve()?, description, timeout_ms)?;
+                rx.recv().await.ok_or(Error::Abandoned("CreateService"))?
+            }
+        })
+        .await
+    }
+
+    /// Creates a service from a template in the application manifest.
+    pub async fn create_service_from_template(
+        &self,
+        description: &ServiceFromTemplateDescription,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        run_with_retry("create_service_from_template", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx =
+                    try_create_service_from_template(client.resolve()?, description, timeout_ms)?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("CreateServiceFromTemplate"))?
+            }
+        })
+        .await
+    }
+
+    /// Deletes a service. `force` skips the graceful close of its replicas.
+    pub async fn delete_service(
+        &self,
+        service_name: &str,
+        force: bool,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        run_with_retry("delete_service", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx =
+                    try_delete_service(client.resolve()?, service_name, force, timeout_ms)?;
+                rx.recv().await.ok_or(Error::Abandoned("DeleteService"))?
+            }
+        })
+        .await
+    }
+
+    /// Applies the fields set in `description` to a running service.
+    pub async fn update_service(
+        &self,
+        service_name: &str,
+        description: &ServiceUpdateDescription,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        description.validate()?;
+        run_with_retry("update_service", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx =
+                    try_update_service(client.resolve()?, service_name, description, timeout_ms)?;
+                rx.recv().await.ok_or(Error::Abandoned("UpdateService"))?
+            }
+        })
+        .await
+    }
+
+    /// Returns the description the service is currently running with.
+    pub async fn get_service_description(
+        &self,
+        service_name: &str,
+        timeout_ms: u32,
+    ) -> Result<ServiceDescription, Error> {
+        run_with_retry("get_service_description", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx =
+                    try_get_service_description(client.resolve()?, service_name, timeout_ms)?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("GetServiceDescription"))?
+            }
+        })
+        .await
+    }
+
+    /// Removes a replica or instance from `node_name`. `force` skips the
+    /// graceful close of the replica.
+    pub async fn remove_replica(
+        &self,
+        node_name: &str,
+        partition_id: GUID,
+        replica_or_instance_id: i64,
+        force: bool,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        run_with_retry("remove_replica", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx = try_remove_replica(
+                    client.resolve()?,
+                    node_name,
+                    partition_id,
+                    replica_or_instance_id,
+                    force,
+                    timeout_ms,
+                )?;
+                rx.recv().await.ok_or(Error::Abandoned("RemoveReplica"))?
+            }
+        })
+        .await
+    }
+
+    /// Restarts a persisted stateful replica on `node_name`.
+    pub async fn restart_replica(
+        &self,
+        node_name: &str,
+        partition_id: GUID,
+        replica_or_instance_id: i64,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        run_with_retry("restart_replica", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx = try_restart_replica(
+                    client.resolve()?,
+                    node_name,
+                    partition_id,
+                    replica_or_instance_id,
+                    timeout_ms,
+                )?;
+                rx.recv().await.ok_or(Error::Abandoned("RestartReplica"))?
+            }
+        })
+        .await
+    }
+
+    /// Returns the XML of a service manifest in a provisioned application type.
+    pub async fn get_service_manifest(
+        &self,
+        application_type_name: &str,
+        application_type_version: &str,
+        service_manifest_name: &str,
+        timeout_ms: u32,
+    ) -> Result<String, Error> {
+        run_with_retry("get_service_manifest", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx = try_get_service_manifest(
+                    client.resolve()?,
+                    application_type_name,
+                    application_type_version,
+                    service_manifest_name,
+                    timeout_ms,
+                )?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("GetServiceManifest"))?
+            }
+        })
+        .await
+    }
 }
 
 fn try_resolve_service_partition(
@@ -118,3 +292,176 @@ impl IFabricAsyncOperationCallback_Impl for ResolveCallback {
         }
     }
 }
+
+fn try_get_service_manifest(
+    client: IFabricServiceManagementClient7,
+    application_type_name: &str,
+    application_type_version: &str,
+    service_manifest_name: &str,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<String, Error>>, Error> {
+    let application_type_name = to_wide(application_type_name);
+    let application_type_version = to_wide(application_type_version);
+    let service_manifest_name = to_wide(service_manifest_name);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginGetServiceManifest(
+                PCWSTR(application_type_name.as_ptr()),
+                PCWSTR(application_type_version.as_ptr()),
+                PCWSTR(service_manifest_name.as_ptr()),
+                timeout_ms,
+                Some(callback),
+            )
+        },
+        move |context| {
+            let res = unsafe { end_client.EndGetServiceManifest(context) }?;
+            unsafe { wide_to_string(res.get_String().0) }
+        },
+    )
+}
+
+fn try_create_service(
+    client: IFabricServiceManagementClient7,
+    description: &ServiceDescription,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<(), Error>>, Error> {
+    let mut arena = Arena::new();
+    let description = description.to_raw(&mut arena);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe { client.BeginCreateService(&description, timeout_ms, Some(callback)) },
+        move |context| Ok(unsafe { end_client.EndCreateService(context) }?),
+    )
+}
+
+fn try_delete_service(
+    client: IFabricServiceManagementClient7,
+    service_name: &str,
+    force: bool,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<(), Error>>, Error> {
+    let mut service_name = to_wide(service_name);
+    let description = FABRIC_DELETE_SERVICE_DESCRIPTION {
+        ServiceName: service_name.as_mut_ptr(),
+        ForceDelete: force.into(),
+        Reserved: ptr::null_mut(),
+    };
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe { client.BeginDeleteService2(&description, timeout_ms, Some(callback)) },
+        move |context| Ok(unsafe { end_client.EndDeleteService2(context) }?),
+    )
+}
+
+fn try_update_service(
+    client: IFabricServiceManagementClient7,
+    service_name: &str,
+    description: &ServiceUpdateDescription,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<(), Error>>, Error> {
+    let service_name = to_wide(service_name);
+    let mut arena = Arena::new();
+    let description = description.to_raw(&mut arena);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginUpdateService(
+                service_name.as_ptr(),
+                &description,
+                timeout_ms,
+                Some(callback),
+            )
+        },
+        move |context| Ok(unsafe { end_client.EndUpdateService(context) }?),
+    )
+}
+
+fn try_get_service_description(
+    client: IFabricServiceManagementClient7,
+    service_name: &str,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<ServiceDescription, Error>>, Error> {
+    let service_name = to_wide(service_name);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginGetServiceDescription(service_name.as_ptr(), timeout_ms, Some(callback))
+        },
+        move |context| {
+            let res = unsafe { end_client.EndGetServiceDescription(context) }?;
+            ServiceDescription::try_from(unsafe { &*res.get_Description() })
+        },
+    )
+}
+
+fn try_create_service_from_template(
+    client: IFabricServiceManagementClient7,
+    description: &ServiceFromTemplateDescription,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<(), Error>>, Error> {
+    let mut arena = Arena::new();
+    let description = description.to_raw(&mut arena);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginCreateServiceFromTemplate2(&description, timeout_ms, Some(callback))
+        },
+        move |context| Ok(unsafe { end_client.EndCreateServiceFromTemplate2(context) }?),
+    )
+}
+
+fn try_remove_replica(
+    client: IFabricServiceManagementClient7,
+    node_name: &str,
+    partition_id: GUID,
+    replica_or_instance_id: i64,
+    force: bool,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<(), Error>>, Error> {
+    let node_name = to_wide(node_name);
+    let mut ex1 = FABRIC_REMOVE_REPLICA_DESCRIPTION_EX1 {
+        ForceRemove: force.into(),
+        Reserved: ptr::null_mut(),
+    };
+    let description = FABRIC_REMOVE_REPLICA_DESCRIPTION {
+        NodeName: PCWSTR(node_name.as_ptr()),
+        PartitionId: partition_id,
+        ReplicaOrInstanceId: replica_or_instance_id,
+        Reserved: &mut ex1 as *mut _ as *mut c_void,
+    };
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe { client.BeginRemoveReplica(&description, timeout_ms, Some(callback)) },
+        move |context| Ok(unsafe { end_client.EndRemoveReplica(context) }?),
+    )
+}
+
+fn try_restart_replica(
+    client: IFabricServiceManagementClient7,
+    node_name: &str,
+    partition_id: GUID,
+    replica_or_instance_id: i64,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<(), Error>>, Error> {
+    let node_name = to_wide(node_name);
+    let description = FABRIC_RESTART_REPLICA_DESCRIPTION {
+        NodeName: PCWSTR(node_name.as_ptr()),
+        PartitionId: partition_id,
+        ReplicaOrInstanceId: replica_or_instance_id,
+        Reserved: ptr::null_mut(),
+    };
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe { client.BeginRestartReplica(&description, timeout_ms, Some(callback)) },
+        move |context| Ok(unsafe { end_client.EndRestartReplica(context) }?),
+    )
+}
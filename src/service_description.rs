@@ -0,0 +1,1760 @@
+use std::{ffi::c_void, ptr, time::Duration};
+
+use windows::core::{PCWSTR, PWSTR};
+
+use crate::{
+    arena::Arena, error::Error, list_items, seconds, wide_to_string, ServiceLoadMetricDescription,
+    FABRIC_MOVE_COST, FABRIC_MOVE_COST_HIGH, FABRIC_MOVE_COST_LOW, FABRIC_MOVE_COST_MEDIUM,
+    FABRIC_MOVE_COST_VERYHIGH, FABRIC_MOVE_COST_ZERO, FABRIC_NAMED_PARTITION_SCHEME_DESCRIPTION,
+    FABRIC_PARTITION_SCHEME, FABRIC_PARTITION_SCHEME_NAMED, FABRIC_PARTITION_SCHEME_SINGLETON,
+    FABRIC_PARTITION_SCHEME_UNIFORM_INT64_RANGE, FABRIC_SCALING_MECHANISM,
+    FABRIC_SCALING_MECHANISM_ADD_REMOVE_INCREMENTAL_NAMED_PARTITION,
+    FABRIC_SCALING_MECHANISM_KIND_ADD_REMOVE_INCREMENTAL_NAMED_PARTITION,
+    FABRIC_SCALING_MECHANISM_KIND_SCALE_PARTITION_INSTANCE_COUNT,
+    FABRIC_SCALING_MECHANISM_PARTITION_INSTANCE_COUNT, FABRIC_SCALING_TRIGGER,
+    FABRIC_SCALING_TRIGGER_AVERAGE_PARTITION_LOAD, FABRIC_SCALING_TRIGGER_AVERAGE_SERVICE_LOAD,
+    FABRIC_SCALING_TRIGGER_AVERAGE_SERVICE_LOAD_EX1,
+    FABRIC_SCALING_TRIGGER_KIND_AVERAGE_PARTITION_LOAD,
+    FABRIC_SCALING_TRIGGER_KIND_AVERAGE_SERVICE_LOAD, FABRIC_SERVICE_CORRELATION_DESCRIPTION,
+    FABRIC_SERVICE_CORRELATION_SCHEME, FABRIC_SERVICE_CORRELATION_SCHEME_AFFINITY,
+    FABRIC_SERVICE_CORRELATION_SCHEME_ALIGNED_AFFINITY,
+    FABRIC_SERVICE_CORRELATION_SCHEME_NONALIGNED_AFFINITY, FABRIC_SERVICE_DESCRIPTION,
+    FABRIC_SERVICE_DESCRIPTION_KIND_STATEFUL, FABRIC_SERVICE_DESCRIPTION_KIND_STATELESS,
+    FABRIC_SERVICE_FROM_TEMPLATE_DESCRIPTION, FABRIC_SERVICE_LOAD_METRIC_DESCRIPTION,
+    FABRIC_SERVICE_PACKAGE_ACTIVATION_MODE,
+    FABRIC_SERVICE_PACKAGE_ACTIVATION_MODE_EXCLUSIVE_PROCESS,
+    FABRIC_SERVICE_PACKAGE_ACTIVATION_MODE_SHARED_PROCESS, FABRIC_SERVICE_PARTITION_KIND_INVALID,
+    FABRIC_SERVICE_SCALING_POLICY, FABRIC_SERVICE_UPDATE_DESCRIPTION,
+    FABRIC_STATEFUL_SERVICE_DESCRIPTION, FABRIC_STATEFUL_SERVICE_DESCRIPTION_EX1,
+    FABRIC_STATEFUL_SERVICE_DESCRIPTION_EX2, FABRIC_STATEFUL_SERVICE_DESCRIPTION_EX3,
+    FABRIC_STATEFUL_SERVICE_DESCRIPTION_EX4, FABRIC_STATEFUL_SERVICE_FAILOVER_SETTINGS,
+    FABRIC_STATEFUL_SERVICE_METRICS, FABRIC_STATEFUL_SERVICE_MIN_REPLICA_SET_SIZE,
+    FABRIC_STATEFUL_SERVICE_MOVE_COST, FABRIC_STATEFUL_SERVICE_PLACEMENT_CONSTRAINTS,
+    FABRIC_STATEFUL_SERVICE_REPLICA_RESTART_WAIT_DURATION, FABRIC_STATEFUL_SERVICE_SCALING_POLICY,
+    FABRIC_STATEFUL_SERVICE_SETTINGS_REPLICA_RESTART_WAIT_DURATION,
+    FABRIC_STATEFUL_SERVICE_TARGET_REPLICA_SET_SIZE, FABRIC_STATEFUL_SERVICE_UPDATE_DESCRIPTION,
+    FABRIC_STATEFUL_SERVICE_UPDATE_DESCRIPTION_EX1, FABRIC_STATEFUL_SERVICE_UPDATE_DESCRIPTION_EX2,
+    FABRIC_STATEFUL_SERVICE_UPDATE_DESCRIPTION_EX3, FABRIC_STATEFUL_SERVICE_UPDATE_DESCRIPTION_EX4,
+    FABRIC_STATEFUL_SERVICE_UPDATE_DESCRIPTION_EX5, FABRIC_STATELESS_SERVICE_DESCRIPTION,
+    FABRIC_STATELESS_SERVICE_DESCRIPTION_EX1, FABRIC_STATELESS_SERVICE_DESCRIPTION_EX2,
+    FABRIC_STATELESS_SERVICE_DESCRIPTION_EX3, FABRIC_STATELESS_SERVICE_DESCRIPTION_EX4,
+    FABRIC_STATELESS_SERVICE_INSTANCE_COUNT, FABRIC_STATELESS_SERVICE_METRICS,
+    FABRIC_STATELESS_SERVICE_MOVE_COST, FABRIC_STATELESS_SERVICE_PLACEMENT_CONSTRAINTS,
+    FABRIC_STATELESS_SERVICE_SCALING_POLICY, FABRIC_STATELESS_SERVICE_UPDATE_DESCRIPTION,
+    FABRIC_STATELESS_SERVICE_UPDATE_DESCRIPTION_EX1,
+    FABRIC_STATELESS_SERVICE_UPDATE_DESCRIPTION_EX2,
+    FABRIC_STATELESS_SERVICE_UPDATE_DESCRIPTION_EX3,
+    FABRIC_UNIFORM_INT64_RANGE_PARTITION_SCHEME_DESCRIPTION,
+};
+
+/// Describes a service to create. Start from [`ServiceDescription::stateless`]
+/// or [`ServiceDescription::stateful`] and chain the setters for anything
+/// beyond the defaults.
+///
+/// This is also what `ServiceManagementClient::get_service_description`
+/// returns, so a live service can be compared against or recreated from its
+/// definition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceDescription {
+    pub application_name: String,
+    pub service_name: String,
+    pub service_type_name: String,
+    pub initialization_data: Vec<u8>,
+    pub kind: ServiceDescriptionKind,
+    pub partition_scheme: PartitionScheme,
+    pub placement_constraints: Option<String>,
+    pub correlations: Vec<ServiceCorrelation>,
+    pub load_metrics: Vec<ServiceLoadMetricDescription>,
+    pub default_move_cost: Option<MoveCost>,
+    pub service_package_activation_mode: ServicePackageActivationMode,
+    pub scaling_policies: Vec<ScalingPolicy>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServiceDescriptionKind {
+    Stateless(StatelessServiceDescription),
+    Stateful(StatefulServiceDescription),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatelessServiceDescription {
+    /// Use -1 to place an instance on every node.
+    pub instance_count: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatefulServiceDescription {
+    pub target_replica_set_size: i32,
+    pub min_replica_set_size: i32,
+    pub has_persisted_state: bool,
+    /// How long SF waits for a down replica to come back before building a
+    /// new one. The cluster default is used when this isn't set.
+    pub replica_restart_wait: Option<Duration>,
+}
+
+impl StatefulServiceDescription {
+    pub fn new(target_replica_set_size: i32, min_replica_set_size: i32) -> Self {
+        Self {
+            target_replica_set_size,
+            min_replica_set_size,
+            has_persisted_state: false,
+            replica_restart_wait: None,
+        }
+    }
+
+    pub fn has_persisted_state(mut self, has_persisted_state: bool) -> Self {
+        self.has_persisted_state = has_persisted_state;
+        self
+    }
+
+    pub fn replica_restart_wait(mut self, replica_restart_wait: Duration) -> Self {
+        self.replica_restart_wait = Some(replica_restart_wait);
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PartitionScheme {
+    Singleton,
+    UniformInt64Range {
+        partition_count: i32,
+        low_key: i64,
+        high_key: i64,
+    },
+    Named(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceCorrelation {
+    pub service_name: String,
+    pub scheme: ServiceCorrelationScheme,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(i32)]
+pub enum ServiceCorrelationScheme {
+    Affinity = FABRIC_SERVICE_CORRELATION_SCHEME_AFFINITY.0,
+    AlignedAffinity = FABRIC_SERVICE_CORRELATION_SCHEME_ALIGNED_AFFINITY.0,
+    NonAlignedAffinity = FABRIC_SERVICE_CORRELATION_SCHEME_NONALIGNED_AFFINITY.0,
+}
+
+impl TryFrom<FABRIC_SERVICE_CORRELATION_SCHEME> for ServiceCorrelationScheme {
+    type Error = Error;
+
+    fn try_from(value: FABRIC_SERVICE_CORRELATION_SCHEME) -> Result<Self, Self::Error> {
+        match value {
+            FABRIC_SERVICE_CORRELATION_SCHEME_AFFINITY => Ok(Self::Affinity),
+            FABRIC_SERVICE_CORRELATION_SCHEME_ALIGNED_AFFINITY => Ok(Self::AlignedAffinity),
+            FABRIC_SERVICE_CORRELATION_SCHEME_NONALIGNED_AFFINITY => Ok(Self::NonAlignedAffinity),
+            _ => Err(Error::InvalidServiceCorrelationScheme),
+        }
+    }
+}
+
+impl From<ServiceCorrelationScheme> for FABRIC_SERVICE_CORRELATION_SCHEME {
+    fn from(value: ServiceCorrelationScheme) -> Self {
+        FABRIC_SERVICE_CORRELATION_SCHEME(value as i32)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(i32)]
+pub enum MoveCost {
+    Zero = FABRIC_MOVE_COST_ZERO.0,
+    Low = FABRIC_MOVE_COST_LOW.0,
+    Medium = FABRIC_MOVE_COST_MEDIUM.0,
+    High = FABRIC_MOVE_COST_HIGH.0,
+    VeryHigh = FABRIC_MOVE_COST_VERYHIGH.0,
+}
+
+impl From<FABRIC_MOVE_COST> for MoveCost {
+    fn from(value: FABRIC_MOVE_COST) -> Self {
+        match value {
+            FABRIC_MOVE_COST_ZERO => Self::Zero,
+            FABRIC_MOVE_COST_MEDIUM => Self::Medium,
+            FABRIC_MOVE_COST_HIGH => Self::High,
+            FABRIC_MOVE_COST_VERYHIGH => Self::VeryHigh,
+            _ => Self::Low,
+        }
+    }
+}
+
+impl From<MoveCost> for FABRIC_MOVE_COST {
+    fn from(value: MoveCost) -> Self {
+        FABRIC_MOVE_COST(value as i32)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[repr(i32)]
+pub enum ServicePackageActivationMode {
+    #[default]
+    SharedProcess = FABRIC_SERVICE_PACKAGE_ACTIVATION_MODE_SHARED_PROCESS.0,
+    ExclusiveProcess = FABRIC_SERVICE_PACKAGE_ACTIVATION_MODE_EXCLUSIVE_PROCESS.0,
+}
+
+impl From<FABRIC_SERVICE_PACKAGE_ACTIVATION_MODE> for ServicePackageActivationMode {
+    fn from(value: FABRIC_SERVICE_PACKAGE_ACTIVATION_MODE) -> Self {
+        match value {
+            FABRIC_SERVICE_PACKAGE_ACTIVATION_MODE_EXCLUSIVE_PROCESS => Self::ExclusiveProcess,
+            _ => Self::SharedProcess,
+        }
+    }
+}
+
+impl From<ServicePackageActivationMode> for FABRIC_SERVICE_PACKAGE_ACTIVATION_MODE {
+    fn from(value: ServicePackageActivationMode) -> Self {
+        FABRIC_SERVICE_PACKAGE_ACTIVATION_MODE(value as i32)
+    }
+}
+
+impl ServiceDescription {
+    pub fn stateless(
+        application_name: &str,
+        service_name: &str,
+        service_type_name: &str,
+        instance_count: i32,
+    ) -> Self {
+        Self::new(
+            application_name,
+            service_name,
+            service_type_name,
+            ServiceDescriptionKind::Stateless(StatelessServiceDescription { instance_count }),
+        )
+    }
+
+    pub fn stateful(
+        application_name: &str,
+        service_name: &str,
+        service_type_name: &str,
+        description: StatefulServiceDescription,
+    ) -> Self {
+        Self::new(
+            application_name,
+            service_name,
+            service_type_name,
+            ServiceDescriptionKind::Stateful(description),
+        )
+    }
+
+    fn new(
+        application_name: &str,
+        service_name: &str,
+        service_type_name: &str,
+        kind: ServiceDescriptionKind,
+    ) -> Self {
+        Self {
+            application_name: application_name.to_string(),
+            service_name: service_name.to_string(),
+            service_type_name: service_type_name.to_string(),
+            initialization_data: vec![],
+            kind,
+            partition_scheme: PartitionScheme::Singleton,
+            placement_constraints: None,
+            correlations: vec![],
+            load_metrics: vec![],
+            default_move_cost: None,
+            service_package_activation_mode: ServicePackageActivationMode::default(),
+            scaling_policies: vec![],
+        }
+    }
+
+    pub fn initialization_data(mut self, initialization_data: Vec<u8>) -> Self {
+        self.initialization_data = initialization_data;
+        self
+    }
+
+    pub fn partition_scheme(mut self, partition_scheme: PartitionScheme) -> Self {
+        self.partition_scheme = partition_scheme;
+        self
+    }
+
+    pub fn placement_constraints(mut self, placement_constraints: &str) -> Self {
+        self.placement_constraints = Some(placement_constraints.to_string());
+        self
+    }
+
+    pub fn correlation(mut self, service_name: &str, scheme: ServiceCorrelationScheme) -> Self {
+        self.correlations.push(ServiceCorrelation {
+            service_name: service_name.to_string(),
+            scheme,
+        });
+        self
+    }
+
+    pub fn load_metric(mut self, load_metric: ServiceLoadMetricDescription) -> Self {
+        self.load_metrics.push(load_metric);
+        self
+    }
+
+    pub fn default_move_cost(mut self, default_move_cost: MoveCost) -> Self {
+        self.default_move_cost = Some(default_move_cost);
+        self
+    }
+
+    pub fn service_package_activation_mode(mut self, mode: ServicePackageActivationMode) -> Self {
+        self.service_package_activation_mode = mode;
+        self
+    }
+
+    pub fn scaling_policy(mut self, scaling_policy: ScalingPolicy) -> Self {
+        self.scaling_policies.push(scaling_policy);
+        self
+    }
+
+    /// Checks the scaling policies against the rest of the description so
+    /// that mistakes are reported before anything is sent to the cluster.
+    pub fn validate(&self) -> Result<(), Error> {
+        for policy in &self.scaling_policies {
+            policy.validate()?;
+            match (&policy.mechanism, &self.kind, &self.partition_scheme) {
+                (
+                    ScalingMechanism::PartitionInstanceCount { .. },
+                    ServiceDescriptionKind::Stateful(_),
+                    _,
+                ) => {
+                    return Err(Error::InvalidScalingPolicy(
+                        "instance count scaling only applies to stateless services",
+                    ))
+                }
+                (ScalingMechanism::AddRemoveIncrementalNamedPartition { .. }, _, scheme)
+                    if !matches!(scheme, PartitionScheme::Named(_)) =>
+                {
+                    return Err(Error::InvalidScalingPolicy(
+                        "partition scaling requires the named partition scheme",
+                    ))
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Builds the FFI description. Every pointer in the result refers to
+    /// memory owned by `arena`.
+    pub(crate) fn to_raw(&self, arena: &mut Arena) -> FABRIC_SERVICE_DESCRIPTION {
+        let application_name = arena.uri(&self.application_name);
+        let service_name = arena.uri(&self.service_name);
+        let service_type_name = arena.wide(&self.service_type_name);
+        let initialization_data_size = self.initialization_data.len() as u32;
+        let initialization_data = arena.slice(self.initialization_data.clone());
+        let (partition_scheme, partition_scheme_description) = self.partition_scheme.to_raw(arena);
+        let placement_constraints = arena.optional_wide(self.placement_constraints.as_deref());
+        let correlations = self
+            .correlations
+            .iter()
+            .map(|correlation| FABRIC_SERVICE_CORRELATION_DESCRIPTION {
+                ServiceName: arena.uri(&correlation.service_name),
+                Scheme: correlation.scheme.into(),
+                Reserved: ptr::null_mut(),
+            })
+            .collect::<Vec<_>>();
+        let correlation_count = correlations.len() as u32;
+        let correlations = arena.slice(correlations);
+        let (metric_count, metrics) = load_metric_list(arena, &self.load_metrics);
+        let move_cost_specified = self.default_move_cost.is_some().into();
+        let move_cost = self.default_move_cost.unwrap_or(MoveCost::Zero).into();
+        let activation_mode = self.service_package_activation_mode.into();
+        let (scaling_policy_count, scaling_policies) =
+            scaling_policy_list(arena, &self.scaling_policies);
+
+        match &self.kind {
+            ServiceDescriptionKind::Stateless(stateless) => {
+                let ex4 = arena.alloc(FABRIC_STATELESS_SERVICE_DESCRIPTION_EX4 {
+                    ScalingPolicyCount: scaling_policy_count,
+                    ServiceScalingPolicies: scaling_policies,
+                    Reserved: ptr::null_mut(),
+                });
+                let ex3 = arena.alloc(FABRIC_STATELESS_SERVICE_DESCRIPTION_EX3 {
+                    ServicePackageActivationMode: activation_mode,
+                    ServiceDnsName: PCWSTR::null(),
+                    Reserved: ex4 as *mut c_void,
+                });
+                let ex2 = arena.alloc(FABRIC_STATELESS_SERVICE_DESCRIPTION_EX2 {
+                    IsDefaultMoveCostSpecified: move_cost_specified,
+                    DefaultMoveCost: move_cost,
+                    Reserved: ex3 as *mut c_void,
+                });
+                let ex1 = arena.alloc(FABRIC_STATELESS_SERVICE_DESCRIPTION_EX1 {
+                    PolicyList: ptr::null_mut(),
+                    Reserved: ex2 as *mut c_void,
+                });
+                let value = arena.alloc(FABRIC_STATELESS_SERVICE_DESCRIPTION {
+                    ApplicationName: application_name,
+                    ServiceName: service_name,
+                    ServiceTypeName: service_type_name,
+                    InitializationDataSize: initialization_data_size,
+                    InitializationData: initialization_data,
+                    PartitionScheme: partition_scheme,
+                    PartitionSchemeDescription: partition_scheme_description,
+                    InstanceCount: stateless.instance_count,
+                    PlacementConstraints: placement_constraints,
+                    CorrelationCount: correlation_count,
+                    Correlations: correlations,
+                    MetricCount: metric_count,
+                    Metrics: metrics,
+                    Reserved: ex1 as *mut c_void,
+                });
+
+                FABRIC_SERVICE_DESCRIPTION {
+                    Kind: FABRIC_SERVICE_DESCRIPTION_KIND_STATELESS,
+                    Value: value as *mut c_void,
+                }
+            }
+            ServiceDescriptionKind::Stateful(stateful) => {
+                let failover_settings = match stateful.replica_restart_wait {
+                    Some(wait) => arena.alloc(FABRIC_STATEFUL_SERVICE_FAILOVER_SETTINGS {
+                        Flags: FABRIC_STATEFUL_SERVICE_SETTINGS_REPLICA_RESTART_WAIT_DURATION.0
+                            as u32,
+                        ReplicaRestartWaitDurationSeconds: seconds(Some(wait)),
+                        QuorumLossWaitDurationSeconds: 0,
+                        Reserved: ptr::null_mut(),
+                    }),
+                    None => ptr::null_mut(),
+                };
+                let ex4 = arena.alloc(FABRIC_STATEFUL_SERVICE_DESCRIPTION_EX4 {
+                    ScalingPolicyCount: scaling_policy_count,
+                    ServiceScalingPolicies: scaling_policies,
+                    Reserved: ptr::null_mut(),
+                });
+                let ex3 = arena.alloc(FABRIC_STATEFUL_SERVICE_DESCRIPTION_EX3 {
+                    ServicePackageActivationMode: activation_mode,
+                    ServiceDnsName: PCWSTR::null(),
+                    Reserved: ex4 as *mut c_void,
+                });
+                let ex2 = arena.alloc(FABRIC_STATEFUL_SERVICE_DESCRIPTION_EX2 {
+                    IsDefaultMoveCostSpecified: move_cost_specified,
+                    DefaultMoveCost: move_cost,
+                    Reserved: ex3 as *mut c_void,
+                });
+                let ex1 = arena.alloc(FABRIC_STATEFUL_SERVICE_DESCRIPTION_EX1 {
+                    PolicyList: ptr::null_mut(),
+                    FailoverSettings: failover_settings,
+                    Reserved: ex2 as *mut c_void,
+                });
+                let value = arena.alloc(FABRIC_STATEFUL_SERVICE_DESCRIPTION {
+                    ApplicationName: application_name,
+                    ServiceName: service_name,
+                    ServiceTypeName: service_type_name,
+                    InitializationDataSize: initialization_data_size,
+                    InitializationData: initialization_data,
+                    PartitionScheme: partition_scheme,
+                    PartitionSchemeDescription: partition_scheme_description,
+                    TargetReplicaSetSize: stateful.target_replica_set_size,
+                    MinReplicaSetSize: stateful.min_replica_set_size,
+                    PlacementConstraints: placement_constraints,
+                    CorrelationCount: correlation_count,
+                    Correlations: correlations,
+                    MetricCount: metric_count,
+                    Metrics: metrics,
+                    HasPersistedState: stateful.has_persisted_state.into(),
+                    Reserved: ex1 as *mut c_void,
+                });
+
+                FABRIC_SERVICE_DESCRIPTION {
+                    Kind: FABRIC_SERVICE_DESCRIPTION_KIND_STATEFUL,
+                    Value: value as *mut c_void,
+                }
+            }
+        }
+    }
+}
+
+impl TryFrom<&FABRIC_SERVICE_DESCRIPTION> for ServiceDescription {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_SERVICE_DESCRIPTION) -> Result<Self, Self::Error> {
+        match value.Kind {
+            FABRIC_SERVICE_DESCRIPTION_KIND_STATELESS => {
+                let value =
+                    unsafe { &*(value.Value as *const FABRIC_STATELESS_SERVICE_DESCRIPTION) };
+                let ex1 = unsafe {
+                    (value.Reserved as *const FABRIC_STATELESS_SERVICE_DESCRIPTION_EX1).as_ref()
+                };
+                let ex2 = ex1.and_then(|ex1| unsafe {
+                    (ex1.Reserved as *const FABRIC_STATELESS_SERVICE_DESCRIPTION_EX2).as_ref()
+                });
+                let ex3 = ex2.and_then(|ex2| unsafe {
+                    (ex2.Reserved as *const FABRIC_STATELESS_SERVICE_DESCRIPTION_EX3).as_ref()
+                });
+                let ex4 = ex3.and_then(|ex3| unsafe {
+                    (ex3.Reserved as *const FABRIC_STATELESS_SERVICE_DESCRIPTION_EX4).as_ref()
+                });
+
+                Ok(Self {
+                    application_name: unsafe { wide_to_string(value.ApplicationName)? },
+                    service_name: unsafe { wide_to_string(value.ServiceName)? },
+                    service_type_name: unsafe { wide_to_string(value.ServiceTypeName.0)? },
+                    initialization_data: unsafe {
+                        list_items(value.InitializationData, value.InitializationDataSize)
+                    }
+                    .to_vec(),
+                    kind: ServiceDescriptionKind::Stateless(StatelessServiceDescription {
+                        instance_count: value.InstanceCount,
+                    }),
+                    partition_scheme: unsafe {
+                        PartitionScheme::from_raw(
+                            value.PartitionScheme,
+                            value.PartitionSchemeDescription,
+                        )?
+                    },
+                    placement_constraints: unsafe { optional_string(value.PlacementConstraints)? },
+                    correlations: unsafe {
+                        correlations(value.Correlations, value.CorrelationCount)?
+                    },
+                    load_metrics: unsafe { list_items(value.Metrics, value.MetricCount) }
+                        .iter()
+                        .map(ServiceLoadMetricDescription::try_from)
+                        .collect::<Result<_, _>>()?,
+                    default_move_cost: ex2
+                        .filter(|ex2| ex2.IsDefaultMoveCostSpecified.as_bool())
+                        .map(|ex2| ex2.DefaultMoveCost.into()),
+                    service_package_activation_mode: ex3
+                        .map(|ex3| ex3.ServicePackageActivationMode.into())
+                        .unwrap_or_default(),
+                    scaling_policies: match ex4 {
+                        Some(ex4) => unsafe {
+                            list_items(ex4.ServiceScalingPolicies, ex4.ScalingPolicyCount)
+                        }
+                        .iter()
+                        .map(ScalingPolicy::try_from)
+                        .collect::<Result<_, _>>()?,
+                        None => vec![],
+                    },
+                })
+            }
+            FABRIC_SERVICE_DESCRIPTION_KIND_STATEFUL => {
+                let value =
+                    unsafe { &*(value.Value as *const FABRIC_STATEFUL_SERVICE_DESCRIPTION) };
+                let ex1 = unsafe {
+                    (value.Reserved as *const FABRIC_STATEFUL_SERVICE_DESCRIPTION_EX1).as_ref()
+                };
+                let ex2 = ex1.and_then(|ex1| unsafe {
+                    (ex1.Reserved as *const FABRIC_STATEFUL_SERVICE_DESCRIPTION_EX2).as_ref()
+                });
+                let ex3 = ex2.and_then(|ex2| unsafe {
+                    (ex2.Reserved as *const FABRIC_STATEFUL_SERVICE_DESCRIPTION_EX3).as_ref()
+                });
+                let ex4 = ex3.and_then(|ex3| unsafe {
+                    (ex3.Reserved as *const FABRIC_STATEFUL_SERVICE_DESCRIPTION_EX4).as_ref()
+                });
+                let replica_restart_wait = ex1
+                    .and_then(|ex1| unsafe { ex1.FailoverSettings.as_ref() })
+                    .filter(|settings| {
+                        settings.Flags
+                            & FABRIC_STATEFUL_SERVICE_SETTINGS_REPLICA_RESTART_WAIT_DURATION.0
+                                as u32
+                            != 0
+                    })
+                    .map(|settings| {
+                        Duration::from_secs(settings.ReplicaRestartWaitDurationSeconds as u64)
+                    });
+
+                Ok(Self {
+                    application_name: unsafe { wide_to_string(value.ApplicationName)? },
+                    service_name: unsafe { wide_to_string(value.ServiceName)? },
+                    service_type_name: unsafe { wide_to_string(value.ServiceTypeName.0)? },
+                    initialization_data: unsafe {
+                        list_items(value.InitializationData, value.InitializationDataSize)
+                    }
+                    .to_vec(),
+                    kind: ServiceDescriptionKind::Stateful(StatefulServiceDescription {
+                        target_replica_set_size: value.TargetReplicaSetSize,
+                        min_replica_set_size: value.MinReplicaSetSize,
+                        has_persisted_state: value.HasPersistedState.as_bool(),
+                        replica_restart_wait,
+                    }),
+                    partition_scheme: unsafe {
+                        PartitionScheme::from_raw(
+                            value.PartitionScheme,
+                            value.PartitionSchemeDescription,
+                        )?
+                    },
+                    placement_constraints: unsafe { optional_string(value.PlacementConstraints)? },
+                    correlations: unsafe {
+                        correlations(value.Correlations, value.CorrelationCount)?
+                    },
+                    load_metrics: unsafe { list_items(value.Metrics, value.MetricCount) }
+                        .iter()
+                        .map(ServiceLoadMetricDescription::try_from)
+                        .collect::<Result<_, _>>()?,
+                    default_move_cost: ex2
+                        .filter(|ex2| ex2.IsDefaultMoveCostSpecified.as_bool())
+                        .map(|ex2| ex2.DefaultMoveCost.into()),
+                    service_package_activation_mode: ex3
+                        .map(|ex3| ex3.ServicePackageActivationMode.into())
+                        .unwrap_or_default(),
+                    scaling_policies: match ex4 {
+                        Some(ex4) => unsafe {
+                            list_items(ex4.ServiceScalingPolicies, ex4.ScalingPolicyCount)
+                        }
+                        .iter()
+                        .map(ScalingPolicy::try_from)
+                        .collect::<Result<_, _>>()?,
+                        None => vec![],
+                    },
+                })
+            }
+            _ => Err(Error::InvalidServiceKind),
+        }
+    }
+}
+
+impl PartitionScheme {
+    unsafe fn from_raw(
+        scheme: FABRIC_PARTITION_SCHEME,
+        description: *const c_void,
+    ) -> Result<Self, Error> {
+        match scheme {
+            FABRIC_PARTITION_SCHEME_SINGLETON => Ok(PartitionScheme::Singleton),
+            FABRIC_PARTITION_SCHEME_UNIFORM_INT64_RANGE => {
+                let description = &*(description
+                    as *const FABRIC_UNIFORM_INT64_RANGE_PARTITION_SCHEME_DESCRIPTION);
+                Ok(PartitionScheme::UniformInt64Range {
+                    partition_count: description.PartitionCount,
+                    low_key: description.LowKey,
+                    high_key: description.HighKey,
+                })
+            }
+            FABRIC_PARTITION_SCHEME_NAMED => {
+                let description =
+                    &*(description as *const FABRIC_NAMED_PARTITION_SCHEME_DESCRIPTION);
+                let names = list_items(description.Names, description.PartitionCount as u32)
+                    .iter()
+                    .map(|name| wide_to_string(name.0))
+                    .collect::<Result<_, _>>()?;
+                Ok(PartitionScheme::Named(names))
+            }
+            _ => Err(Error::InvalidServicePartitionKind),
+        }
+    }
+
+    fn to_raw(&self, arena: &mut Arena) -> (FABRIC_PARTITION_SCHEME, *mut c_void) {
+        match self {
+            PartitionScheme::Singleton => (FABRIC_PARTITION_SCHEME_SINGLETON, ptr::null_mut()),
+            PartitionScheme::UniformInt64Range {
+                partition_count,
+                low_key,
+                high_key,
+            } => {
+                let description =
+                    arena.alloc(FABRIC_UNIFORM_INT64_RANGE_PARTITION_SCHEME_DESCRIPTION {
+                        PartitionCount: *partition_count,
+                        LowKey: *low_key,
+                        HighKey: *high_key,
+                        Reserved: ptr::null_mut(),
+                    });
+                (
+                    FABRIC_PARTITION_SCHEME_UNIFORM_INT64_RANGE,
+                    description as *mut c_void,
+                )
+            }
+            PartitionScheme::Named(names) => {
+                let items = names
+                    .iter()
+                    .map(|name| PWSTR(arena.wide(name).0 as *mut u16))
+                    .collect::<Vec<_>>();
+                let items = arena.slice(items);
+                let description = arena.alloc(FABRIC_NAMED_PARTITION_SCHEME_DESCRIPTION {
+                    PartitionCount: names.len() as i32,
+                    Names: items,
+                    Reserved: ptr::null_mut(),
+                });
+                (FABRIC_PARTITION_SCHEME_NAMED, description as *mut c_void)
+            }
+        }
+    }
+}
+
+pub(crate) unsafe fn optional_string(value: PCWSTR) -> Result<Option<String>, Error> {
+    if value.is_null() {
+        Ok(None)
+    } else {
+        wide_to_string(value.0).map(Some)
+    }
+}
+
+unsafe fn correlations(
+    items: *const FABRIC_SERVICE_CORRELATION_DESCRIPTION,
+    count: u32,
+) -> Result<Vec<ServiceCorrelation>, Error> {
+    list_items(items, count)
+        .iter()
+        .map(|correlation| {
+            Ok(ServiceCorrelation {
+                service_name: wide_to_string(correlation.ServiceName)?,
+                scheme: correlation.Scheme.try_into()?,
+            })
+        })
+        .collect()
+}
+
+pub(crate) fn load_metric_list(
+    arena: &mut Arena,
+    load_metrics: &[ServiceLoadMetricDescription],
+) -> (u32, *mut FABRIC_SERVICE_LOAD_METRIC_DESCRIPTION) {
+    let items = load_metrics
+        .iter()
+        .map(|metric| FABRIC_SERVICE_LOAD_METRIC_DESCRIPTION {
+            Name: arena.wide(&metric.name),
+            Weight: metric.weight.into(),
+            PrimaryDefaultLoad: metric.primary_default_load,
+            SecondaryDefaultLoad: metric.secondary_default_load,
+            Reserved: ptr::null_mut(),
+        })
+        .collect::<Vec<_>>();
+
+    (items.len() as u32, arena.slice(items))
+}
+
+/// Creates a service from a service template declared in the application
+/// manifest. Placeholders in the template resolve against the parameters the
+/// application was created with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceFromTemplateDescription {
+    pub application_name: String,
+    pub service_name: String,
+    pub service_type_name: String,
+    pub initialization_data: Vec<u8>,
+    pub service_package_activation_mode: ServicePackageActivationMode,
+}
+
+impl ServiceFromTemplateDescription {
+    pub fn new(application_name: &str, service_name: &str, service_type_name: &str) -> Self {
+        Self {
+            application_name: application_name.to_string(),
+            service_name: service_name.to_string(),
+            service_type_name: service_type_name.to_string(),
+            initialization_data: vec![],
+            service_package_activation_mode: ServicePackageActivationMode::default(),
+        }
+    }
+
+    pub fn initialization_data(mut self, initialization_data: Vec<u8>) -> Self {
+        self.initialization_data = initialization_data;
+        self
+    }
+
+    pub fn service_package_activation_mode(mut self, mode: ServicePackageActivationMode) -> Self {
+        self.service_package_activation_mode = mode;
+        self
+    }
+
+    pub(crate) fn to_raw(&self, arena: &mut Arena) -> FABRIC_SERVICE_FROM_TEMPLATE_DESCRIPTION {
+        let application_name = arena.uri(&self.application_name);
+        let service_name = arena.uri(&self.service_name);
+        let service_type_name = arena.wide(&self.service_type_name);
+        let initialization_data_size = self.initialization_data.len() as u32;
+        let initialization_data = arena.slice(self.initialization_data.clone());
+
+        FABRIC_SERVICE_FROM_TEMPLATE_DESCRIPTION {
+            ApplicationName: application_name,
+            ServiceName: service_name,
+            ServiceDnsName: PCWSTR::null(),
+            ServiceTypeName: service_type_name,
+            ServicePackageActivationMode: self.service_package_activation_mode.into(),
+            InitializationDataSize: initialization_data_size,
+            InitializationData: initialization_data,
+            Reserved: ptr::null_mut(),
+        }
+    }
+}
+
+/// Describes changes to an existing service. Only the fields that are set
+/// are sent to the cluster; everything else keeps its current value.
+#[derive(Debug, Clone)]
+pub struct ServiceUpdateDescription {
+    pub kind: ServiceUpdateDescriptionKind,
+    pub placement_constraints: Option<String>,
+    pub load_metrics: Option<Vec<ServiceLoadMetricDescription>>,
+    pub default_move_cost: Option<MoveCost>,
+    pub scaling_policies: Option<Vec<ScalingPolicy>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum ServiceUpdateDescriptionKind {
+    Stateless(StatelessServiceUpdateDescription),
+    Stateful(StatefulServiceUpdateDescription),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct StatelessServiceUpdateDescription {
+    pub instance_count: Option<i32>,
+}
+
+impl StatelessServiceUpdateDescription {
+    pub fn instance_count(mut self, instance_count: i32) -> Self {
+        self.instance_count = Some(instance_count);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct StatefulServiceUpdateDescription {
+    pub target_replica_set_size: Option<i32>,
+    pub min_replica_set_size: Option<i32>,
+    pub replica_restart_wait: Option<Duration>,
+}
+
+impl StatefulServiceUpdateDescription {
+    pub fn target_replica_set_size(mut self, target_replica_set_size: i32) -> Self {
+        self.target_replica_set_size = Some(target_replica_set_size);
+        self
+    }
+
+    pub fn min_replica_set_size(mut self, min_replica_set_size: i32) -> Self {
+        self.min_replica_set_size = Some(min_replica_set_size);
+        self
+    }
+
+    pub fn replica_restart_wait(mut self, replica_restart_wait: Duration) -> Self {
+        self.replica_restart_wait = Some(replica_restart_wait);
+        self
+    }
+}
+
+impl ServiceUpdateDescription {
+    pub fn stateless(description: StatelessServiceUpdateDescription) -> Self {
+        Self::new(ServiceUpdateDescriptionKind::Stateless(description))
+    }
+
+    pub fn stateful(description: StatefulServiceUpdateDescription) -> Self {
+        Self::new(ServiceUpdateDescriptionKind::Stateful(description))
+    }
+
+    fn new(kind: ServiceUpdateDescriptionKind) -> Self {
+        Self {
+            kind,
+            placement_constraints: None,
+            load_metrics: None,
+            default_move_cost: None,
+            scaling_policies: None,
+        }
+    }
+
+    pub fn placement_constraints(mut self, placement_constraints: &str) -> Self {
+        self.placement_constraints = Some(placement_constraints.to_string());
+        self
+    }
+
+    /// Replaces the service's load metrics with `load_metrics`.
+    pub fn load_metrics(mut self, load_metrics: Vec<ServiceLoadMetricDescription>) -> Self {
+        self.load_metrics = Some(load_metrics);
+        self
+    }
+
+    pub fn default_move_cost(mut self, default_move_cost: MoveCost) -> Self {
+        self.default_move_cost = Some(default_move_cost);
+        self
+    }
+
+    /// Replaces the service's scaling policies. An empty list removes them.
+    pub fn scaling_policies(mut self, scaling_policies: Vec<ScalingPolicy>) -> Self {
+        self.scaling_policies = Some(scaling_policies);
+        self
+    }
+
+    /// Checks any scaling policies being set. Instance count scaling is
+    /// rejected for stateful services.
+    pub fn validate(&self) -> Result<(), Error> {
+        for policy in self.scaling_policies.iter().flatten() {
+            policy.validate()?;
+            if let (
+                ScalingMechanism::PartitionInstanceCount { .. },
+                ServiceUpdateDescriptionKind::Stateful(_),
+            ) = (&policy.mechanism, &self.kind)
+            {
+                return Err(Error::InvalidScalingPolicy(
+                    "instance count scaling only applies to stateless services",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Builds the FFI description with `Flags` set for every field that is
+    /// present. Every pointer in the result refers to memory owned by `arena`.
+    pub(crate) fn to_raw(&self, arena: &mut Arena) -> FABRIC_SERVICE_UPDATE_DESCRIPTION {
+        let placement_constraints = arena.optional_wide(self.placement_constraints.as_deref());
+        let (metric_count, metrics) =
+            load_metric_list(arena, self.load_metrics.as_deref().unwrap_or_default());
+        let default_move_cost = self.default_move_cost.unwrap_or(MoveCost::Zero).into();
+        let (scaling_policy_count, scaling_policies) =
+            scaling_policy_list(arena, self.scaling_policies.as_deref().unwrap_or_default());
+
+        match &self.kind {
+            ServiceUpdateDescriptionKind::Stateless(stateless) => {
+                let flags = [
+                    (
+                        stateless.instance_count.is_some(),
+                        FABRIC_STATELESS_SERVICE_INSTANCE_COUNT,
+                    ),
+                    (
+                        self.placement_constraints.is_some(),
+                        FABRIC_STATELESS_SERVICE_PLACEMENT_CONSTRAINTS,
+                    ),
+                    (
+                        self.load_metrics.is_some(),
+                        FABRIC_STATELESS_SERVICE_METRICS,
+                    ),
+                    (
+                        self.default_move_cost.is_some(),
+                        FABRIC_STATELESS_SERVICE_MOVE_COST,
+                    ),
+                    (
+                        self.scaling_policies.is_some(),
+                        FABRIC_STATELESS_SERVICE_SCALING_POLICY,
+                    ),
+                ]
+                .iter()
+                .filter(|(set, _)| *set)
+                .fold(0, |flags, (_, flag)| flags | flag.0 as u32);
+
+                let ex3 = arena.alloc(FABRIC_STATELESS_SERVICE_UPDATE_DESCRIPTION_EX3 {
+                    RepartitionKind: FABRIC_SERVICE_PARTITION_KIND_INVALID,
+                    RepartitionDescription: ptr::null_mut(),
+                    ScalingPolicyCount: scaling_policy_count,
+                    ServiceScalingPolicies: scaling_policies,
+                    Reserved: ptr::null_mut(),
+                });
+                let ex2 = arena.alloc(FABRIC_STATELESS_SERVICE_UPDATE_DESCRIPTION_EX2 {
+                    DefaultMoveCost: default_move_cost,
+                    Reserved: ex3 as *mut c_void,
+                });
+                let ex1 = arena.alloc(FABRIC_STATELESS_SERVICE_UPDATE_DESCRIPTION_EX1 {
+                    PlacementConstraints: placement_constraints,
+                    PolicyList: ptr::null_mut(),
+                    CorrelationCount: 0,
+                    Correlations: ptr::null_mut(),
+                    MetricCount: metric_count,
+                    Metrics: metrics,
+                    Reserved: ex2 as *mut c_void,
+                });
+                let value = arena.alloc(FABRIC_STATELESS_SERVICE_UPDATE_DESCRIPTION {
+                    Flags: flags,
+                    InstanceCount: stateless.instance_count.unwrap_or_default(),
+                    Reserved: ex1 as *mut c_void,
+                });
+
+                FABRIC_SERVICE_UPDATE_DESCRIPTION {
+                    Kind: FABRIC_SERVICE_DESCRIPTION_KIND_STATELESS,
+                    Value: value as *mut c_void,
+                }
+            }
+            ServiceUpdateDescriptionKind::Stateful(stateful) => {
+                let flags = [
+                    (
+                        stateful.target_replica_set_size.is_some(),
+                        FABRIC_STATEFUL_SERVICE_TARGET_REPLICA_SET_SIZE,
+                    ),
+                    (
+                        stateful.min_replica_set_size.is_some(),
+                        FABRIC_STATEFUL_SERVICE_MIN_REPLICA_SET_SIZE,
+                    ),
+                    (
+                        stateful.replica_restart_wait.is_some(),
+                        FABRIC_STATEFUL_SERVICE_REPLICA_RESTART_WAIT_DURATION,
+                    ),
+                    (
+                        self.placement_constraints.is_some(),
+                        FABRIC_STATEFUL_SERVICE_PLACEMENT_CONSTRAINTS,
+                    ),
+                    (self.load_metrics.is_some(), FABRIC_STATEFUL_SERVICE_METRICS),
+                    (
+                        self.default_move_cost.is_some(),
+                        FABRIC_STATEFUL_SERVICE_MOVE_COST,
+                    ),
+                    (
+                        self.scaling_policies.is_some(),
+                        FABRIC_STATEFUL_SERVICE_SCALING_POLICY,
+                    ),
+                ]
+                .iter()
+                .filter(|(set, _)| *set)
+                .fold(0, |flags, (_, flag)| flags | flag.0 as u32);
+
+                let ex5 = arena.alloc(FABRIC_STATEFUL_SERVICE_UPDATE_DESCRIPTION_EX5 {
+                    RepartitionKind: FABRIC_SERVICE_PARTITION_KIND_INVALID,
+                    RepartitionDescription: ptr::null_mut(),
+                    ScalingPolicyCount: scaling_policy_count,
+                    ServiceScalingPolicies: scaling_policies,
+                    Reserved: ptr::null_mut(),
+                });
+                let ex4 = arena.alloc(FABRIC_STATEFUL_SERVICE_UPDATE_DESCRIPTION_EX4 {
+                    DefaultMoveCost: default_move_cost,
+                    Reserved: ex5 as *mut c_void,
+                });
+                let ex3 = arena.alloc(FABRIC_STATEFUL_SERVICE_UPDATE_DESCRIPTION_EX3 {
+                    PlacementConstraints: placement_constraints,
+                    PolicyList: ptr::null_mut(),
+                    CorrelationCount: 0,
+                    Correlations: ptr::null_mut(),
+                    MetricCount: metric_count,
+                    Metrics: metrics,
+                    Reserved: ex4 as *mut c_void,
+                });
+                let ex2 = arena.alloc(FABRIC_STATEFUL_SERVICE_UPDATE_DESCRIPTION_EX2 {
+                    MinReplicaSetSize: stateful.min_replica_set_size.unwrap_or_default(),
+                    Reserved: ex3 as *mut c_void,
+                });
+                let ex1 = arena.alloc(FABRIC_STATEFUL_SERVICE_UPDATE_DESCRIPTION_EX1 {
+                    StandByReplicaKeepDurationSeconds: 0,
+                    Reserved: ex2 as *mut c_void,
+                });
+                let value = arena.alloc(FABRIC_STATEFUL_SERVICE_UPDATE_DESCRIPTION {
+                    Flags: flags,
+                    TargetReplicaSetSize: stateful.target_replica_set_size.unwrap_or_default(),
+                    ReplicaRestartWaitDurationSeconds: stateful
+                        .replica_restart_wait
+                        .map_or(0, |wait| seconds(Some(wait))),
+                    QuorumLossWaitDurationSeconds: 0,
+                    Reserved: ex1 as *mut c_void,
+                });
+
+                FABRIC_SERVICE_UPDATE_DESCRIPTION {
+                    Kind: FABRIC_SERVICE_DESCRIPTION_KIND_STATEFUL,
+                    Value: value as *mut c_void,
+                }
+            }
+        }
+    }
+}
+
+/// Lets SF scale a service in or out based on its reported load.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScalingPolicy {
+    pub trigger: ScalingTrigger,
+    pub mechanism: ScalingMechanism,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScalingTrigger {
+    /// Fires on the average load of a metric across the replicas of each
+    /// partition.
+    AveragePartitionLoad {
+        metric_name: String,
+        lower_load_threshold: f64,
+        upper_load_threshold: f64,
+        scale_interval: Duration,
+    },
+    /// Fires on the average load of a metric across all partitions of the
+    /// service.
+    AverageServiceLoad {
+        metric_name: String,
+        lower_load_threshold: f64,
+        upper_load_threshold: f64,
+        scale_interval: Duration,
+        use_only_primary_load: bool,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScalingMechanism {
+    /// Changes the instance count of each partition. Stateless services only.
+    PartitionInstanceCount {
+        min_instance_count: i32,
+        max_instance_count: i32,
+        scale_increment: i32,
+    },
+    /// Adds or removes named partitions.
+    AddRemoveIncrementalNamedPartition {
+        min_partition_count: i32,
+        max_partition_count: i32,
+        scale_increment: i32,
+    },
+}
+
+impl ScalingPolicy {
+    /// Checks that the thresholds, interval and counts make sense on their
+    /// own.
+    pub fn validate(&self) -> Result<(), Error> {
+        let (lower, upper, scale_interval) = match &self.trigger {
+            ScalingTrigger::AveragePartitionLoad {
+                lower_load_threshold,
+                upper_load_threshold,
+                scale_interval,
+                ..
+            }
+            | ScalingTrigger::AverageServiceLoad {
+                lower_load_threshold,
+                upper_load_threshold,
+                scale_interval,
+                ..
+            } => (
+                *lower_load_threshold,
+                *upper_load_threshold,
+                *scale_interval,
+            ),
+        };
+        if !(lower.is_finite() && upper.is_finite()) || lower < 0.0 {
+            return Err(Error::InvalidScalingPolicy(
+                "load thresholds must be finite and not negative",
+            ));
+        }
+        if lower > upper {
+            return Err(Error::InvalidScalingPolicy(
+                "lower load threshold is above the upper load threshold",
+            ));
+        }
+        if scale_interval.as_secs() == 0 {
+            return Err(Error::InvalidScalingPolicy(
+                "scale interval must be at least one second",
+            ));
+        }
+
+        match self.mechanism {
+            ScalingMechanism::PartitionInstanceCount {
+                min_instance_count,
+                max_instance_count,
+                scale_increment,
+            } => {
+                // A maximum of -1 means "as many nodes as there are".
+                if min_instance_count < 1
+                    || (max_instance_count != -1 && max_instance_count < min_instance_count)
+                {
+                    return Err(Error::InvalidScalingPolicy(
+                        "instance counts must be positive with max no lower than min",
+                    ));
+                }
+                if scale_increment < 1 {
+                    return Err(Error::InvalidScalingPolicy(
+                        "scale increment must be positive",
+                    ));
+                }
+            }
+            ScalingMechanism::AddRemoveIncrementalNamedPartition {
+                min_partition_count,
+                max_partition_count,
+                scale_increment,
+            } => {
+                if min_partition_count < 1 || max_partition_count < min_partition_count {
+                    return Err(Error::InvalidScalingPolicy(
+                        "partition counts must be positive with max no lower than min",
+                    ));
+                }
+                if scale_increment < 1 {
+                    return Err(Error::InvalidScalingPolicy(
+                        "scale increment must be positive",
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl TryFrom<&FABRIC_SERVICE_SCALING_POLICY> for ScalingPolicy {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_SERVICE_SCALING_POLICY) -> Result<Self, Self::Error> {
+        let trigger = &value.ServiceScalingPolicyTrigger;
+        let trigger = match trigger.ScalingTriggerKind {
+            FABRIC_SCALING_TRIGGER_KIND_AVERAGE_PARTITION_LOAD => {
+                let description = unsafe {
+                    &*(trigger.ScalingTriggerDescription
+                        as *const FABRIC_SCALING_TRIGGER_AVERAGE_PARTITION_LOAD)
+                };
+                ScalingTrigger::AveragePartitionLoad {
+                    metric_name: unsafe { wide_to_string(description.MetricName.0)? },
+                    lower_load_threshold: description.LowerLoadThreshold,
+                    upper_load_threshold: description.UpperLoadThreshold,
+                    scale_interval: Duration::from_secs(description.ScaleIntervalInSeconds as u64),
+                }
+            }
+            FABRIC_SCALING_TRIGGER_KIND_AVERAGE_SERVICE_LOAD => {
+                let description = unsafe {
+                    &*(trigger.ScalingTriggerDescription
+                        as *const FABRIC_SCALING_TRIGGER_AVERAGE_SERVICE_LOAD)
+                };
+                let ex1 = unsafe {
+                    (description.Reserved as *const FABRIC_SCALING_TRIGGER_AVERAGE_SERVICE_LOAD_EX1)
+                        .as_ref()
+                };
+                ScalingTrigger::AverageServiceLoad {
+                    metric_name: unsafe { wide_to_string(description.MetricName.0)? },
+                    lower_load_threshold: description.LowerLoadThreshold,
+                    upper_load_threshold: description.UpperLoadThreshold,
+                    scale_interval: Duration::from_secs(description.ScaleIntervalInSeconds as u64),
+                    use_only_primary_load: ex1
+                        .map_or(false, |ex1| ex1.UseOnlyPrimaryLoad.as_bool()),
+                }
+            }
+            _ => return Err(Error::InvalidScalingPolicy("unknown scaling trigger")),
+        };
+
+        let mechanism = &value.ServiceScalingPolicyMechanism;
+        let mechanism = match mechanism.ScalingMechanismKind {
+            FABRIC_SCALING_MECHANISM_KIND_SCALE_PARTITION_INSTANCE_COUNT => {
+                let description = unsafe {
+                    &*(mechanism.ScalingMechanismDescription
+                        as *const FABRIC_SCALING_MECHANISM_PARTITION_INSTANCE_COUNT)
+                };
+                ScalingMechanism::PartitionInstanceCount {
+                    min_instance_count: description.MinimumInstanceCount,
+                    max_instance_count: description.MaximumInstanceCount,
+                    scale_increment: description.ScaleIncrement,
+                }
+            }
+            FABRIC_SCALING_MECHANISM_KIND_ADD_REMOVE_INCREMENTAL_NAMED_PARTITION => {
+                let description = unsafe {
+                    &*(mechanism.ScalingMechanismDescription
+                        as *const FABRIC_SCALING_MECHANISM_ADD_REMOVE_INCREMENTAL_NAMED_PARTITION)
+                };
+                ScalingMechanism::AddRemoveIncrementalNamedPartition {
+                    min_partition_count: description.MinimumPartitionCount,
+                    max_partition_count: description.MaximumPartitionCount,
+                    scale_increment: description.ScaleIncrement,
+                }
+            }
+            _ => return Err(Error::InvalidScalingPolicy("unknown scaling mechanism")),
+        };
+
+        Ok(Self { trigger, mechanism })
+    }
+}
+
+impl ScalingTrigger {
+    fn to_raw(&self, arena: &mut Arena) -> FABRIC_SCALING_TRIGGER {
+        match self {
+            ScalingTrigger::AveragePartitionLoad {
+                metric_name,
+                lower_load_threshold,
+                upper_load_threshold,
+                scale_interval,
+            } => {
+                let metric_name = arena.wide(metric_name);
+                let description = arena.alloc(FABRIC_SCALING_TRIGGER_AVERAGE_PARTITION_LOAD {
+                    MetricName: metric_name,
+                    LowerLoadThreshold: *lower_load_threshold,
+                    UpperLoadThreshold: *upper_load_threshold,
+                    ScaleIntervalInSeconds: seconds(Some(*scale_interval)),
+                    Reserved: ptr::null_mut(),
+                });
+
+                FABRIC_SCALING_TRIGGER {
+                    ScalingTriggerKind: FABRIC_SCALING_TRIGGER_KIND_AVERAGE_PARTITION_LOAD,
+                    ScalingTriggerDescription: description as *mut c_void,
+                }
+            }
+            ScalingTrigger::AverageServiceLoad {
+                metric_name,
+                lower_load_threshold,
+                upper_load_threshold,
+                scale_interval,
+                use_only_primary_load,
+            } => {
+                let metric_name = arena.wide(metric_name);
+                let ex1 = arena.alloc(FABRIC_SCALING_TRIGGER_AVERAGE_SERVICE_LOAD_EX1 {
+                    UseOnlyPrimaryLoad: (*use_only_primary_load).into(),
+                    Reserved: ptr::null_mut(),
+                });
+                let description = arena.alloc(FABRIC_SCALING_TRIGGER_AVERAGE_SERVICE_LOAD {
+                    MetricName: metric_name,
+                    LowerLoadThreshold: *lower_load_threshold,
+                    UpperLoadThreshold: *upper_load_threshold,
+                    ScaleIntervalInSeconds: seconds(Some(*scale_interval)),
+                    Reserved: ex1 as *mut c_void,
+                });
+
+                FABRIC_SCALING_TRIGGER {
+                    ScalingTriggerKind: FABRIC_SCALING_TRIGGER_KIND_AVERAGE_SERVICE_LOAD,
+                    ScalingTriggerDescription: description as *mut c_void,
+                }
+            }
+        }
+    }
+}
+
+impl ScalingMechanism {
+    fn to_raw(&self, arena: &mut Arena) -> FABRIC_SCALING_MECHANISM {
+        match self {
+            ScalingMechanism::PartitionInstanceCount {
+                min_instance_count,
+                max_instance_count,
+                scale_increment,
+            } => {
+                let description = arena.alloc(FABRIC_SCALING_MECHANISM_PARTITION_INSTANCE_COUNT {
+                    MaximumInstanceCount: *max_instance_count,
+                    MinimumInstanceCount: *min_instance_count,
+                    ScaleIncrement: *scale_increment,
+                    Reserved: ptr::null_mut(),
+                });
+
+                FABRIC_SCALING_MECHANISM {
+                    ScalingMechanismKind:
+                        FABRIC_SCALING_MECHANISM_KIND_SCALE_PARTITION_INSTANCE_COUNT,
+                    ScalingMechanismDescription: description as *mut c_void,
+                }
+            }
+            ScalingMechanism::AddRemoveIncrementalNamedPartition {
+                min_partition_count,
+                max_partition_count,
+                scale_increment,
+            } => {
+                let description = arena.alloc(
+                    FABRIC_SCALING_MECHANISM_ADD_REMOVE_INCREMENTAL_NAMED_PARTITION {
+                        MaximumPartitionCount: *max_partition_count,
+                        MinimumPartitionCount: *min_partition_count,
+                        ScaleIncrement: *scale_increment,
+                        Reserved: ptr::null_mut(),
+                    },
+                );
+
+                FABRIC_SCALING_MECHANISM {
+                    ScalingMechanismKind:
+                        FABRIC_SCALING_MECHANISM_KIND_ADD_REMOVE_INCREMENTAL_NAMED_PARTITION,
+                    ScalingMechanismDescription: description as *mut c_void,
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn scaling_policy_list(
+    arena: &mut Arena,
+    scaling_policies: &[ScalingPolicy],
+) -> (u32, *mut FABRIC_SERVICE_SCALING_POLICY) {
+    let items = scaling_policies
+        .iter()
+        .map(|policy| FABRIC_SERVICE_SCALING_POLICY {
+            ServiceScalingPolicyTrigger: policy.trigger.to_raw(arena),
+            ServiceScalingPolicyMechanism: policy.mechanism.to_raw(arena),
+            Reserved: ptr::null_mut(),
+        })
+        .collect::<Vec<_>>();
+
+    (items.len() as u32, arena.slice(items))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ServiceLoadMetricWeight, FABRIC_SERVICE_CORRELATION_SCHEME_INVALID};
+
+    fn string(value: *const u16) -> String {
+        unsafe { wide_to_string(value) }.unwrap()
+    }
+
+    #[test]
+    fn stateless_singleton() {
+        let mut arena = Arena::new();
+        let description =
+            ServiceDescription::stateless("fabric:/app", "fabric:/app/web", "WebType", -1)
+                .placement_constraints("NodeType == Front")
+                .to_raw(&mut arena);
+
+        assert_eq!(description.Kind, FABRIC_SERVICE_DESCRIPTION_KIND_STATELESS);
+        let value = unsafe { &*(description.Value as *const FABRIC_STATELESS_SERVICE_DESCRIPTION) };
+        assert_eq!(string(value.ApplicationName), "fabric:/app");
+        assert_eq!(string(value.ServiceName), "fabric:/app/web");
+        assert_eq!(string(value.ServiceTypeName.0), "WebType");
+        assert_eq!(value.InstanceCount, -1);
+        assert_eq!(value.PartitionScheme, FABRIC_PARTITION_SCHEME_SINGLETON);
+        assert!(value.PartitionSchemeDescription.is_null());
+        assert_eq!(string(value.PlacementConstraints.0), "NodeType == Front");
+        assert_eq!(value.CorrelationCount, 0);
+        assert_eq!(value.MetricCount, 0);
+        assert_eq!(value.InitializationDataSize, 0);
+
+        let ex1 = unsafe { &*(value.Reserved as *const FABRIC_STATELESS_SERVICE_DESCRIPTION_EX1) };
+        let ex2 = unsafe { &*(ex1.Reserved as *const FABRIC_STATELESS_SERVICE_DESCRIPTION_EX2) };
+        assert!(!ex2.IsDefaultMoveCostSpecified.as_bool());
+        let ex3 = unsafe { &*(ex2.Reserved as *const FABRIC_STATELESS_SERVICE_DESCRIPTION_EX3) };
+        assert_eq!(
+            ex3.ServicePackageActivationMode,
+            FABRIC_SERVICE_PACKAGE_ACTIVATION_MODE_SHARED_PROCESS
+        );
+    }
+
+    #[test]
+    fn stateful_uniform_range() {
+        let mut arena = Arena::new();
+        let description = ServiceDescription::stateful(
+            "fabric:/app",
+            "fabric:/app/store",
+            "StoreType",
+            StatefulServiceDescription::new(3, 2)
+                .has_persisted_state(true)
+                .replica_restart_wait(Duration::from_secs(60)),
+        )
+        .partition_scheme(PartitionScheme::UniformInt64Range {
+            partition_count: 4,
+            low_key: 0,
+            high_key: 1023,
+        })
+        .correlation("fabric:/app/web", ServiceCorrelationScheme::Affinity)
+        .load_metric(ServiceLoadMetricDescription {
+            name: "MemoryInMb".to_string(),
+            weight: ServiceLoadMetricWeight::High,
+            primary_default_load: 100,
+            secondary_default_load: 50,
+        })
+        .default_move_cost(MoveCost::Medium)
+        .service_package_activation_mode(ServicePackageActivationMode::ExclusiveProcess)
+        .to_raw(&mut arena);
+
+        assert_eq!(description.Kind, FABRIC_SERVICE_DESCRIPTION_KIND_STATEFUL);
+        let value = unsafe { &*(description.Value as *const FABRIC_STATEFUL_SERVICE_DESCRIPTION) };
+        assert_eq!(value.TargetReplicaSetSize, 3);
+        assert_eq!(value.MinReplicaSetSize, 2);
+        assert!(value.HasPersistedState.as_bool());
+        assert!(value.PlacementConstraints.is_null());
+
+        assert_eq!(
+            value.PartitionScheme,
+            FABRIC_PARTITION_SCHEME_UNIFORM_INT64_RANGE
+        );
+        let range = unsafe {
+            &*(value.PartitionSchemeDescription
+                as *const FABRIC_UNIFORM_INT64_RANGE_PARTITION_SCHEME_DESCRIPTION)
+        };
+        assert_eq!(
+            (range.PartitionCount, range.LowKey, range.HighKey),
+            (4, 0, 1023)
+        );
+
+        let correlations = unsafe { list_items(value.Correlations, value.CorrelationCount) };
+        assert_eq!(correlations.len(), 1);
+        assert_eq!(string(correlations[0].ServiceName), "fabric:/app/web");
+        assert_eq!(
+            correlations[0].Scheme,
+            FABRIC_SERVICE_CORRELATION_SCHEME_AFFINITY
+        );
+
+        let metrics = unsafe { list_items(value.Metrics, value.MetricCount) };
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(string(metrics[0].Name.0), "MemoryInMb");
+        assert_eq!(
+            (
+                metrics[0].PrimaryDefaultLoad,
+                metrics[0].SecondaryDefaultLoad
+            ),
+            (100, 50)
+        );
+
+        let ex1 = unsafe { &*(value.Reserved as *const FABRIC_STATEFUL_SERVICE_DESCRIPTION_EX1) };
+        let failover = unsafe { &*ex1.FailoverSettings };
+        assert_eq!(
+            failover.Flags,
+            FABRIC_STATEFUL_SERVICE_SETTINGS_REPLICA_RESTART_WAIT_DURATION.0 as u32
+        );
+        assert_eq!(failover.ReplicaRestartWaitDurationSeconds, 60);
+        let ex2 = unsafe { &*(ex1.Reserved as *const FABRIC_STATEFUL_SERVICE_DESCRIPTION_EX2) };
+        assert!(ex2.IsDefaultMoveCostSpecified.as_bool());
+        assert_eq!(ex2.DefaultMoveCost, FABRIC_MOVE_COST_MEDIUM);
+        let ex3 = unsafe { &*(ex2.Reserved as *const FABRIC_STATEFUL_SERVICE_DESCRIPTION_EX3) };
+        assert_eq!(
+            ex3.ServicePackageActivationMode,
+            FABRIC_SERVICE_PACKAGE_ACTIVATION_MODE_EXCLUSIVE_PROCESS
+        );
+    }
+
+    #[test]
+    fn replica_restart_wait_saturates() {
+        let mut arena = Arena::new();
+        let description = ServiceDescription::stateful(
+            "fabric:/app",
+            "fabric:/app/store",
+            "StoreType",
+            StatefulServiceDescription::new(3, 2)
+                .replica_restart_wait(Duration::from_secs(u32::MAX as u64 + 1)),
+        )
+        .to_raw(&mut arena);
+
+        let value = unsafe { &*(description.Value as *const FABRIC_STATEFUL_SERVICE_DESCRIPTION) };
+        let ex1 = unsafe { &*(value.Reserved as *const FABRIC_STATEFUL_SERVICE_DESCRIPTION_EX1) };
+        let failover = unsafe { &*ex1.FailoverSettings };
+        assert_eq!(failover.ReplicaRestartWaitDurationSeconds, u32::MAX);
+    }
+
+    #[test]
+    fn named_partitions() {
+        let mut arena = Arena::new();
+        let description = ServiceDescription::stateful(
+            "fabric:/app",
+            "fabric:/app/store",
+            "StoreType",
+            StatefulServiceDescription::new(3, 2),
+        )
+        .partition_scheme(PartitionScheme::Named(vec![
+            "east".to_string(),
+            "west".to_string(),
+        ]))
+        .to_raw(&mut arena);
+
+        let value = unsafe { &*(description.Value as *const FABRIC_STATEFUL_SERVICE_DESCRIPTION) };
+        assert_eq!(value.PartitionScheme, FABRIC_PARTITION_SCHEME_NAMED);
+        let named = unsafe {
+            &*(value.PartitionSchemeDescription as *const FABRIC_NAMED_PARTITION_SCHEME_DESCRIPTION)
+        };
+        let names = unsafe { list_items(named.Names, named.PartitionCount as u32) };
+        let names = names.iter().map(|name| string(name.0)).collect::<Vec<_>>();
+        assert_eq!(names, vec!["east", "west"]);
+
+        let ex1 = unsafe { &*(value.Reserved as *const FABRIC_STATEFUL_SERVICE_DESCRIPTION_EX1) };
+        assert!(ex1.FailoverSettings.is_null());
+    }
+
+    #[test]
+    fn description_round_trips() {
+        let mut arena = Arena::new();
+        let description = ServiceDescription::stateful(
+            "fabric:/app",
+            "fabric:/app/store",
+            "StoreType",
+            StatefulServiceDescription::new(3, 2)
+                .has_persisted_state(true)
+                .replica_restart_wait(Duration::from_secs(30)),
+        )
+        .initialization_data(vec![1, 2, 3])
+        .partition_scheme(PartitionScheme::Named(vec!["a".to_string()]))
+        .placement_constraints("NodeType == Back")
+        .correlation("fabric:/app/web", ServiceCorrelationScheme::AlignedAffinity)
+        .default_move_cost(MoveCost::VeryHigh)
+        .service_package_activation_mode(ServicePackageActivationMode::ExclusiveProcess);
+
+        let raw = description.to_raw(&mut arena);
+        assert_eq!(ServiceDescription::try_from(&raw).unwrap(), description);
+
+        let description =
+            ServiceDescription::stateless("fabric:/app", "fabric:/app/web", "WebType", 5);
+        let raw = description.to_raw(&mut arena);
+        assert_eq!(ServiceDescription::try_from(&raw).unwrap(), description);
+    }
+
+    #[test]
+    fn update_sets_flags_for_present_fields() {
+        let mut arena = Arena::new();
+        let description = ServiceUpdateDescription::stateful(
+            StatefulServiceUpdateDescription::default().min_replica_set_size(3),
+        )
+        .default_move_cost(MoveCost::High)
+        .scaling_policies(vec![ScalingPolicy {
+            trigger: ScalingTrigger::AveragePartitionLoad {
+                metric_name: "Cpu".to_string(),
+                lower_load_threshold: 0.2,
+                upper_load_threshold: 0.8,
+                scale_interval: Duration::from_secs(600),
+            },
+            mechanism: ScalingMechanism::AddRemoveIncrementalNamedPartition {
+                min_partition_count: 1,
+                max_partition_count: 5,
+                scale_increment: 1,
+            },
+        }])
+        .to_raw(&mut arena);
+
+        assert_eq!(description.Kind, FABRIC_SERVICE_DESCRIPTION_KIND_STATEFUL);
+        let value =
+            unsafe { &*(description.Value as *const FABRIC_STATEFUL_SERVICE_UPDATE_DESCRIPTION) };
+        assert_eq!(
+            value.Flags,
+            (FABRIC_STATEFUL_SERVICE_MIN_REPLICA_SET_SIZE.0
+                | FABRIC_STATEFUL_SERVICE_MOVE_COST.0
+                | FABRIC_STATEFUL_SERVICE_SCALING_POLICY.0) as u32
+        );
+
+        let ex1 =
+            unsafe { &*(value.Reserved as *const FABRIC_STATEFUL_SERVICE_UPDATE_DESCRIPTION_EX1) };
+        let ex2 =
+            unsafe { &*(ex1.Reserved as *const FABRIC_STATEFUL_SERVICE_UPDATE_DESCRIPTION_EX2) };
+        assert_eq!(ex2.MinReplicaSetSize, 3);
+        let ex3 =
+            unsafe { &*(ex2.Reserved as *const FABRIC_STATEFUL_SERVICE_UPDATE_DESCRIPTION_EX3) };
+        assert!(ex3.PlacementConstraints.is_null());
+        let ex4 =
+            unsafe { &*(ex3.Reserved as *const FABRIC_STATEFUL_SERVICE_UPDATE_DESCRIPTION_EX4) };
+        assert_eq!(ex4.DefaultMoveCost, FABRIC_MOVE_COST_HIGH);
+        let ex5 =
+            unsafe { &*(ex4.Reserved as *const FABRIC_STATEFUL_SERVICE_UPDATE_DESCRIPTION_EX5) };
+        let policies = unsafe { list_items(ex5.ServiceScalingPolicies, ex5.ScalingPolicyCount) };
+        assert_eq!(policies.len(), 1);
+        let trigger = &policies[0].ServiceScalingPolicyTrigger;
+        assert_eq!(
+            trigger.ScalingTriggerKind,
+            FABRIC_SCALING_TRIGGER_KIND_AVERAGE_PARTITION_LOAD
+        );
+        let trigger = unsafe {
+            &*(trigger.ScalingTriggerDescription
+                as *const FABRIC_SCALING_TRIGGER_AVERAGE_PARTITION_LOAD)
+        };
+        assert_eq!(string(trigger.MetricName.0), "Cpu");
+        assert_eq!(trigger.ScaleIntervalInSeconds, 600);
+        let mechanism = unsafe {
+            &*(policies[0]
+                .ServiceScalingPolicyMechanism
+                .ScalingMechanismDescription
+                as *const FABRIC_SCALING_MECHANISM_ADD_REMOVE_INCREMENTAL_NAMED_PARTITION)
+        };
+        assert_eq!(mechanism.MaximumPartitionCount, 5);
+    }
+
+    #[test]
+    fn stateless_update_without_fields_sends_no_flags() {
+        let mut arena = Arena::new();
+        let description =
+            ServiceUpdateDescription::stateless(StatelessServiceUpdateDescription::default())
+                .to_raw(&mut arena);
+
+        let value =
+            unsafe { &*(description.Value as *const FABRIC_STATELESS_SERVICE_UPDATE_DESCRIPTION) };
+        assert_eq!(value.Flags, 0);
+    }
+
+    fn partition_load_policy(mechanism: ScalingMechanism) -> ScalingPolicy {
+        ScalingPolicy {
+            trigger: ScalingTrigger::AveragePartitionLoad {
+                metric_name: "Cpu".to_string(),
+                lower_load_threshold: 0.2,
+                upper_load_threshold: 0.8,
+                scale_interval: Duration::from_secs(600),
+            },
+            mechanism,
+        }
+    }
+
+    #[test]
+    fn scaling_policies_round_trip() {
+        let mut arena = Arena::new();
+        let description =
+            ServiceDescription::stateless("fabric:/app", "fabric:/app/web", "WebType", 2)
+                .scaling_policy(partition_load_policy(
+                    ScalingMechanism::PartitionInstanceCount {
+                        min_instance_count: 1,
+                        max_instance_count: -1,
+                        scale_increment: 1,
+                    },
+                ))
+                .scaling_policy(ScalingPolicy {
+                    trigger: ScalingTrigger::AverageServiceLoad {
+                        metric_name: "Memory".to_string(),
+                        lower_load_threshold: 10.0,
+                        upper_load_threshold: 100.0,
+                        scale_interval: Duration::from_secs(60),
+                        use_only_primary_load: true,
+                    },
+                    mechanism: ScalingMechanism::PartitionInstanceCount {
+                        min_instance_count: 2,
+                        max_instance_count: 10,
+                        scale_increment: 2,
+                    },
+                });
+        assert!(description.validate().is_ok());
+
+        let raw = description.to_raw(&mut arena);
+        assert_eq!(ServiceDescription::try_from(&raw).unwrap(), description);
+    }
+
+    #[test]
+    fn invalid_scaling_policies_are_rejected() {
+        let instances = ScalingMechanism::PartitionInstanceCount {
+            min_instance_count: 1,
+            max_instance_count: 5,
+            scale_increment: 1,
+        };
+        let mut policy = partition_load_policy(instances.clone());
+        if let ScalingTrigger::AveragePartitionLoad {
+            lower_load_threshold,
+            ..
+        } = &mut policy.trigger
+        {
+            *lower_load_threshold = 0.9;
+        }
+        assert!(policy.validate().is_err());
+
+        let policy = partition_load_policy(ScalingMechanism::PartitionInstanceCount {
+            min_instance_count: 3,
+            max_instance_count: 2,
+            scale_increment: 1,
+        });
+        assert!(policy.validate().is_err());
+
+        let policy = partition_load_policy(ScalingMechanism::AddRemoveIncrementalNamedPartition {
+            min_partition_count: 1,
+            max_partition_count: 4,
+            scale_increment: 0,
+        });
+        assert!(policy.validate().is_err());
+
+        let stateful = ServiceDescription::stateful(
+            "fabric:/app",
+            "fabric:/app/store",
+            "StoreType",
+            StatefulServiceDescription::new(3, 2),
+        )
+        .scaling_policy(partition_load_policy(instances));
+        assert!(stateful.validate().is_err());
+
+        let singleton =
+            ServiceDescription::stateless("fabric:/app", "fabric:/app/web", "WebType", 2)
+                .scaling_policy(partition_load_policy(
+                    ScalingMechanism::AddRemoveIncrementalNamedPartition {
+                        min_partition_count: 1,
+                        max_partition_count: 4,
+                        scale_increment: 1,
+                    },
+                ));
+        assert!(singleton.validate().is_err());
+    }
+
+    #[test]
+    fn unknown_correlation_scheme_is_rejected() {
+        for scheme in [
+            ServiceCorrelationScheme::Affinity,
+            ServiceCorrelationScheme::AlignedAffinity,
+            ServiceCorrelationScheme::NonAlignedAffinity,
+        ] {
+            let raw = FABRIC_SERVICE_CORRELATION_SCHEME::from(scheme);
+            assert_eq!(ServiceCorrelationScheme::try_from(raw).unwrap(), scheme);
+        }
+
+        assert!(matches!(
+            ServiceCorrelationScheme::try_from(FABRIC_SERVICE_CORRELATION_SCHEME_INVALID),
+            Err(Error::InvalidServiceCorrelationScheme)
+        ));
+        assert!(matches!(
+            ServiceCorrelationScheme::try_from(FABRIC_SERVICE_CORRELATION_SCHEME(42)),
+            Err(Error::InvalidServiceCorrelationScheme)
+        ));
+    }
+
+    #[test]
+    fn scale_interval_saturates_at_u32_max() {
+        let mut arena = Arena::new();
+        for (interval, expected) in [
+            (Duration::from_secs(600), 600),
+            (Duration::from_secs(u32::MAX as u64), u32::MAX),
+            (Duration::from_secs(u32::MAX as u64 + 1), u32::MAX),
+        ] {
+            let trigger = ScalingTrigger::AveragePartitionLoad {
+                metric_name: "Cpu".to_string(),
+                lower_load_threshold: 0.2,
+                upper_load_threshold: 0.8,
+                scale_interval: interval,
+            }
+            .to_raw(&mut arena);
+            let trigger = unsafe {
+                &*(trigger.ScalingTriggerDescription
+                    as *const FABRIC_SCALING_TRIGGER_AVERAGE_PARTITION_LOAD)
+            };
+            assert_eq!(trigger.ScaleIntervalInSeconds, expected);
+
+            let trigger = ScalingTrigger::AverageServiceLoad {
+                metric_name: "Cpu".to_string(),
+                lower_load_threshold: 0.2,
+                upper_load_threshold: 0.8,
+                scale_interval: interval,
+                use_only_primary_load: false,
+            }
+            .to_raw(&mut arena);
+            let trigger = unsafe {
+                &*(trigger.ScalingTriggerDescription
+                    as *const FABRIC_SCALING_TRIGGER_AVERAGE_SERVICE_LOAD)
+            };
+            assert_eq!(trigger.ScaleIntervalInSeconds, expected);
+        }
+    }
+}
@@ -0,0 +1,361 @@
+use std::ptr;
+
+use tokio::sync::mpsc;
+use windows::core::ComInterface;
+
+use crate::{
+    agile::AgileRef, arena::Arena, callback::begin_async, error::Error, list_items,
+    load_metric_list, run_with_retry, to_wide, wide_to_string,
+    IFabricServiceGroupManagementClient4, MakeClient, ServiceDescription,
+    ServiceLoadMetricDescription, ServiceUpdateDescription, FABRIC_SERVICE_GROUP_DESCRIPTION,
+    FABRIC_SERVICE_GROUP_MEMBER_DESCRIPTION, FABRIC_SERVICE_GROUP_UPDATE_DESCRIPTION,
+};
+
+/// Describes a service group: the service that hosts the group plus the
+/// member services that share its replicas.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceGroupDescription {
+    pub description: ServiceDescription,
+    pub members: Vec<ServiceGroupMemberDescription>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceGroupMemberDescription {
+    pub service_type_name: String,
+    pub service_name: String,
+    pub initialization_data: Vec<u8>,
+    pub load_metrics: Vec<ServiceLoadMetricDescription>,
+}
+
+impl ServiceGroupMemberDescription {
+    pub fn new(service_type_name: &str, service_name: &str) -> Self {
+        Self {
+            service_type_name: service_type_name.to_string(),
+            service_name: service_name.to_string(),
+            initialization_data: vec![],
+            load_metrics: vec![],
+        }
+    }
+
+    pub fn initialization_data(mut self, initialization_data: Vec<u8>) -> Self {
+        self.initialization_data = initialization_data;
+        self
+    }
+
+    pub fn load_metric(mut self, load_metric: ServiceLoadMetricDescription) -> Self {
+        self.load_metrics.push(load_metric);
+        self
+    }
+}
+
+impl ServiceGroupDescription {
+    pub fn new(description: ServiceDescription) -> Self {
+        Self {
+            description,
+            members: vec![],
+        }
+    }
+
+    pub fn member(mut self, member: ServiceGroupMemberDescription) -> Self {
+        self.members.push(member);
+        self
+    }
+
+    pub(crate) fn to_raw(&self, arena: &mut Arena) -> FABRIC_SERVICE_GROUP_DESCRIPTION {
+        let description = self.description.to_raw(arena);
+        let description = arena.alloc(description);
+        let members = self
+            .members
+            .iter()
+            .map(|member| {
+                let (metric_count, metrics) = load_metric_list(arena, &member.load_metrics);
+                FABRIC_SERVICE_GROUP_MEMBER_DESCRIPTION {
+                    ServiceType: arena.wide(&member.service_type_name),
+                    ServiceName: arena.uri(&member.service_name),
+                    InitializationDataSize: member.initialization_data.len() as u32,
+                    InitializationData: arena.slice(member.initialization_data.clone()),
+                    MetricCount: metric_count,
+                    Metrics: metrics,
+                    Reserved: ptr::null_mut(),
+                }
+            })
+            .collect::<Vec<_>>();
+        let member_count = members.len() as u32;
+        let members = arena.slice(members);
+
+        FABRIC_SERVICE_GROUP_DESCRIPTION {
+            Description: description,
+            MemberCount: member_count,
+            MemberDescriptions: members,
+            Reserved: ptr::null_mut(),
+        }
+    }
+}
+
+impl TryFrom<&FABRIC_SERVICE_GROUP_DESCRIPTION> for ServiceGroupDescription {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_SERVICE_GROUP_DESCRIPTION) -> Result<Self, Self::Error> {
+        let description = unsafe { value.Description.as_ref() }
+            .ok_or(Error::NullPointer("FABRIC_SERVICE_GROUP_DESCRIPTION"))?;
+        let description = ServiceDescription::try_from(description)?;
+        let members = unsafe { list_items(value.MemberDescriptions, value.MemberCount) }
+            .iter()
+            .map(|member| {
+                Ok(ServiceGroupMemberDescription {
+                    service_type_name: unsafe { wide_to_string(member.ServiceType.0)? },
+                    service_name: unsafe { wide_to_string(member.ServiceName)? },
+                    initialization_data: unsafe {
+                        list_items(member.InitializationData, member.InitializationDataSize)
+                    }
+                    .to_vec(),
+                    load_metrics: unsafe { list_items(member.Metrics, member.MetricCount) }
+                        .iter()
+                        .map(ServiceLoadMetricDescription::try_from)
+                        .collect::<Result<_, _>>()?,
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+
+        Ok(Self {
+            description,
+            members,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ServiceGroupManagementClient {
+    client: AgileRef<IFabricServiceGroupManagementClient4>,
+}
+
+impl MakeClient for ServiceGroupManagementClient {
+    type Interface = IFabricServiceGroupManagementClient4;
+
+    fn make(client: Self::Interface) -> Result<Self, Error> {
+        Ok(Self {
+            client: AgileRef::new(client.cast()?)?,
+        })
+    }
+}
+
+impl ServiceGroupManagementClient {
+    pub fn new(client: IFabricServiceGroupManagementClient4) -> Result<Self, Error> {
+        Ok(Self {
+            client: AgileRef::new(client.cast()?)?,
+        })
+    }
+
+    pub async fn create_service_group(
+        &self,
+        description: &ServiceGroupDescription,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        description.description.validate()?;
+        run_with_retry("create_service_group", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx = try_create_service_group(client.resolve()?, description, timeout_ms)?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("CreateServiceGroup"))?
+            }
+        })
+        .await
+    }
+
+    pub async fn delete_service_group(&self, name: &str, timeout_ms: u32) -> Result<(), Error> {
+        run_with_retry("delete_service_group", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx = try_delete_service_group(client.resolve()?, name, timeout_ms)?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("DeleteServiceGroup"))?
+            }
+        })
+        .await
+    }
+
+    /// Applies the fields set in `description` to the service hosting the
+    /// group.
+    pub async fn update_service_group(
+        &self,
+        name: &str,
+        description: &ServiceUpdateDescription,
+        timeout_ms: u32,
+    ) -> Result<(), Error> {
+        description.validate()?;
+        run_with_retry("update_service_group", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx =
+                    try_update_service_group(client.resolve()?, name, description, timeout_ms)?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("UpdateServiceGroup"))?
+            }
+        })
+        .await
+    }
+
+    pub async fn get_service_group_description(
+        &self,
+        name: &str,
+        timeout_ms: u32,
+    ) -> Result<ServiceGroupDescription, Error> {
+        run_with_retry("get_service_group_description", move || {
+            let client = self.client.clone();
+            async move {
+                let mut rx =
+                    try_get_service_group_description(client.resolve()?, name, timeout_ms)?;
+                rx.recv()
+                    .await
+                    .ok_or(Error::Abandoned("GetServiceGroupDescription"))?
+            }
+        })
+        .await
+    }
+}
+
+fn try_create_service_group(
+    client: IFabricServiceGroupManagementClient4,
+    description: &ServiceGroupDescription,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<(), Error>>, Error> {
+    let mut arena = Arena::new();
+    let description = description.to_raw(&mut arena);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginCreateServiceGroup(&description, timeout_ms, Some(callback))
+        },
+        move |context| Ok(unsafe { end_client.EndCreateServiceGroup(context) }?),
+    )
+}
+
+fn try_delete_service_group(
+    client: IFabricServiceGroupManagementClient4,
+    name: &str,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<(), Error>>, Error> {
+    let name = to_wide(name);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginDeleteServiceGroup(name.as_ptr(), timeout_ms, Some(callback))
+        },
+        move |context| Ok(unsafe { end_client.EndDeleteServiceGroup(context) }?),
+    )
+}
+
+fn try_update_service_group(
+    client: IFabricServiceGroupManagementClient4,
+    name: &str,
+    description: &ServiceUpdateDescription,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<(), Error>>, Error> {
+    let name = to_wide(name);
+    let mut arena = Arena::new();
+    let update = description.to_raw(&mut arena);
+    let description = FABRIC_SERVICE_GROUP_UPDATE_DESCRIPTION {
+        Description: arena.alloc(update),
+        Reserved: ptr::null_mut(),
+    };
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginUpdateServiceGroup(name.as_ptr(), &description, timeout_ms, Some(callback))
+        },
+        move |context| Ok(unsafe { end_client.EndUpdateServiceGroup(context) }?),
+    )
+}
+
+fn try_get_service_group_description(
+    client: IFabricServiceGroupManagementClient4,
+    name: &str,
+    timeout_ms: u32,
+) -> Result<mpsc::Receiver<Result<ServiceGroupDescription, Error>>, Error> {
+    let name = to_wide(name);
+    let end_client = client.clone();
+
+    begin_async(
+        |callback| unsafe {
+            client.BeginGetServiceGroupDescription(name.as_ptr(), timeout_ms, Some(callback))
+        },
+        move |context| {
+            let res = unsafe { end_client.EndGetServiceGroupDescription(context) }?;
+            let description = unsafe { res.get_Description().as_ref() }
+                .ok_or(Error::NullPointer("GetServiceGroupDescription"))?;
+            ServiceGroupDescription::try_from(description)
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ServiceLoadMetricWeight, StatefulServiceDescription};
+
+    #[test]
+    fn description_round_trips() {
+        let mut arena = Arena::new();
+        let description = ServiceGroupDescription::new(ServiceDescription::stateful(
+            "fabric:/app",
+            "fabric:/app/group",
+            "GroupType",
+            StatefulServiceDescription::new(3, 2).has_persisted_state(true),
+        ))
+        .member(
+            ServiceGroupMemberDescription::new("StoreType", "fabric:/app/group#store")
+                .initialization_data(vec![1, 2, 3, 4])
+                .load_metric(ServiceLoadMetricDescription {
+                    name: "Memory".to_string(),
+                    weight: ServiceLoadMetricWeight::High,
+                    primary_default_load: 20,
+                    secondary_default_load: 10,
+                })
+                .load_metric(ServiceLoadMetricDescription {
+                    name: "Cpu".to_string(),
+                    weight: ServiceLoadMetricWeight::Low,
+                    primary_default_load: 2,
+                    secondary_default_load: 1,
+                }),
+        )
+        .member(ServiceGroupMemberDescription::new(
+            "CacheType",
+            "fabric:/app/group#cache",
+        ));
+
+        let raw = description.to_raw(&mut arena);
+        assert_eq!(raw.MemberCount, 2);
+        let members = unsafe { list_items(raw.MemberDescriptions, raw.MemberCount) };
+        assert_eq!(members[0].InitializationDataSize, 4);
+        assert_eq!(members[0].MetricCount, 2);
+        assert_eq!(members[1].InitializationDataSize, 0);
+        assert!(members[1].InitializationData.is_null());
+        assert_eq!(members[1].MetricCount, 0);
+
+        assert_eq!(
+            ServiceGroupDescription::try_from(&raw).unwrap(),
+            description
+        );
+    }
+
+    #[test]
+    fn null_service_description() {
+        let raw = FABRIC_SERVICE_GROUP_DESCRIPTION {
+            Description: ptr::null_mut(),
+            MemberCount: 0,
+            MemberDescriptions: ptr::null_mut(),
+            Reserved: ptr::null_mut(),
+        };
+        assert!(matches!(
+            ServiceGroupDescription::try_from(&raw),
+            Err(Error::NullPointer(_))
+        ));
+    }
+}
@@ -1,30 +1,91 @@
-use std::slice;
+use std::{
+    slice,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
-use windows::core::{GUID, PWSTR};
+use windows::{
+    core::{GUID, HRESULT, PCWSTR, PWSTR},
+    Win32::Foundation::FILETIME,
+};
 
 use crate::{
-    error::Error, IFabricResolvedServicePartitionResult, FABRIC_HEALTH_STATE,
+    error::Error, IFabricResolvedServicePartitionResult, FABRIC_APPLICATION_LOAD_INFORMATION,
+    FABRIC_APPLICATION_LOAD_METRIC_INFORMATION, FABRIC_CLUSTER_LOAD_INFORMATION,
+    FABRIC_CODE_PACKAGE_ENTRY_POINT, FABRIC_CODE_PACKAGE_ENTRY_POINT_STATISTICS,
+    FABRIC_DEPLOYED_APPLICATION_QUERY_RESULT_ITEM, FABRIC_DEPLOYED_CODE_PACKAGE_QUERY_RESULT_ITEM,
+    FABRIC_DEPLOYED_SERVICE_PACKAGE_QUERY_RESULT_ITEM,
+    FABRIC_DEPLOYED_SERVICE_REPLICA_QUERY_RESULT_ITEM,
+    FABRIC_DEPLOYED_SERVICE_TYPE_QUERY_RESULT_ITEM,
+    FABRIC_DEPLOYED_SERVICE_TYPE_QUERY_RESULT_ITEM_EX1,
+    FABRIC_DEPLOYED_STATEFUL_SERVICE_REPLICA_QUERY_RESULT_ITEM,
+    FABRIC_DEPLOYED_STATELESS_SERVICE_INSTANCE_QUERY_RESULT_ITEM, FABRIC_DEPLOYMENT_STATUS,
+    FABRIC_DEPLOYMENT_STATUS_ACTIVATING, FABRIC_DEPLOYMENT_STATUS_ACTIVE,
+    FABRIC_DEPLOYMENT_STATUS_DEACTIVATING, FABRIC_DEPLOYMENT_STATUS_DOWNLOADING,
+    FABRIC_DEPLOYMENT_STATUS_FAILED, FABRIC_DEPLOYMENT_STATUS_INVALID,
+    FABRIC_DEPLOYMENT_STATUS_RAN_TO_COMPLETION, FABRIC_DEPLOYMENT_STATUS_UPGRADING,
+    FABRIC_ENTRY_POINT_STATUS, FABRIC_ENTRY_POINT_STATUS_INVALID,
+    FABRIC_ENTRY_POINT_STATUS_PENDING, FABRIC_ENTRY_POINT_STATUS_STARTED,
+    FABRIC_ENTRY_POINT_STATUS_STARTING, FABRIC_ENTRY_POINT_STATUS_STOPPED,
+    FABRIC_ENTRY_POINT_STATUS_STOPPING, FABRIC_EPOCH, FABRIC_HEALTH_STATE,
     FABRIC_HEALTH_STATE_ERROR, FABRIC_HEALTH_STATE_INVALID, FABRIC_HEALTH_STATE_OK,
     FABRIC_HEALTH_STATE_UNKNOWN, FABRIC_HEALTH_STATE_WARNING,
-    FABRIC_INT64_RANGE_PARTITION_INFORMATION, FABRIC_NAMED_PARTITION_INFORMATION,
+    FABRIC_INT64_RANGE_PARTITION_INFORMATION, FABRIC_LOAD_METRIC_INFORMATION,
+    FABRIC_LOAD_METRIC_INFORMATION_EX1, FABRIC_LOAD_METRIC_INFORMATION_EX2,
+    FABRIC_LOAD_METRIC_REPORT, FABRIC_LOAD_METRIC_REPORT_EX1, FABRIC_LOAD_METRIC_REPORT_LIST,
+    FABRIC_NAMED_PARTITION_INFORMATION, FABRIC_NODE_DEACTIVATION_INTENT,
+    FABRIC_NODE_DEACTIVATION_INTENT_PAUSE, FABRIC_NODE_DEACTIVATION_INTENT_REMOVE_DATA,
+    FABRIC_NODE_DEACTIVATION_INTENT_REMOVE_NODE, FABRIC_NODE_DEACTIVATION_INTENT_RESTART,
+    FABRIC_NODE_DEACTIVATION_QUERY_RESULT_ITEM, FABRIC_NODE_DEACTIVATION_STATUS,
+    FABRIC_NODE_DEACTIVATION_STATUS_COMPLETED, FABRIC_NODE_DEACTIVATION_STATUS_NONE,
+    FABRIC_NODE_DEACTIVATION_STATUS_SAFETY_CHECK_COMPLETE,
+    FABRIC_NODE_DEACTIVATION_STATUS_SAFETY_CHECK_IN_PROGRESS, FABRIC_NODE_LOAD_INFORMATION,
+    FABRIC_NODE_LOAD_METRIC_INFORMATION, FABRIC_NODE_LOAD_METRIC_INFORMATION_EX1,
+    FABRIC_NODE_QUERY_RESULT_ITEM, FABRIC_NODE_QUERY_RESULT_ITEM_EX1,
+    FABRIC_NODE_QUERY_RESULT_ITEM_EX2, FABRIC_NODE_QUERY_RESULT_ITEM_EX3,
     FABRIC_PARTITION_KEY_TYPE, FABRIC_PARTITION_KEY_TYPE_INT64, FABRIC_PARTITION_KEY_TYPE_INVALID,
     FABRIC_PARTITION_KEY_TYPE_NONE, FABRIC_PARTITION_KEY_TYPE_STRING,
-    FABRIC_QUERY_SERVICE_PARTITION_STATUS, FABRIC_QUERY_SERVICE_PARTITION_STATUS_DELETING,
-    FABRIC_QUERY_SERVICE_PARTITION_STATUS_INVALID,
+    FABRIC_PARTITION_LOAD_INFORMATION, FABRIC_PARTITION_LOAD_INFORMATION_EX1,
+    FABRIC_QUERY_NODE_STATUS, FABRIC_QUERY_NODE_STATUS_DISABLED,
+    FABRIC_QUERY_NODE_STATUS_DISABLING, FABRIC_QUERY_NODE_STATUS_DOWN,
+    FABRIC_QUERY_NODE_STATUS_ENABLING, FABRIC_QUERY_NODE_STATUS_INVALID,
+    FABRIC_QUERY_NODE_STATUS_REMOVED, FABRIC_QUERY_NODE_STATUS_UNKNOWN,
+    FABRIC_QUERY_NODE_STATUS_UP, FABRIC_QUERY_SERVICE_PARTITION_STATUS,
+    FABRIC_QUERY_SERVICE_PARTITION_STATUS_DELETING, FABRIC_QUERY_SERVICE_PARTITION_STATUS_INVALID,
     FABRIC_QUERY_SERVICE_PARTITION_STATUS_IN_QUORUM_LOSS,
     FABRIC_QUERY_SERVICE_PARTITION_STATUS_NOT_READY, FABRIC_QUERY_SERVICE_PARTITION_STATUS_READY,
-    FABRIC_QUERY_SERVICE_PARTITION_STATUS_RECONFIGURING, FABRIC_RESOLVED_SERVICE_ENDPOINT,
-    FABRIC_SERVICE_ENDPOINT_ROLE, FABRIC_SERVICE_KIND, FABRIC_SERVICE_KIND_INVALID,
-    FABRIC_SERVICE_KIND_STATEFUL, FABRIC_SERVICE_KIND_STATELESS,
-    FABRIC_SERVICE_PARTITION_INFORMATION, FABRIC_SERVICE_PARTITION_KIND,
-    FABRIC_SERVICE_PARTITION_KIND_INT64_RANGE, FABRIC_SERVICE_PARTITION_KIND_INVALID,
-    FABRIC_SERVICE_PARTITION_KIND_NAMED, FABRIC_SERVICE_PARTITION_KIND_SINGLETON,
-    FABRIC_SERVICE_PARTITION_QUERY_RESULT_ITEM, FABRIC_SERVICE_ROLE_INVALID,
-    FABRIC_SERVICE_ROLE_STATEFUL_AUXILIARY, FABRIC_SERVICE_ROLE_STATEFUL_PRIMARY,
-    FABRIC_SERVICE_ROLE_STATEFUL_PRIMARY_AUXILIARY, FABRIC_SERVICE_ROLE_STATEFUL_SECONDARY,
-    FABRIC_SERVICE_ROLE_STATELESS, FABRIC_SINGLETON_PARTITION_INFORMATION,
+    FABRIC_QUERY_SERVICE_PARTITION_STATUS_RECONFIGURING, FABRIC_QUERY_SERVICE_REPLICA_STATUS,
+    FABRIC_QUERY_SERVICE_REPLICA_STATUS_COMPLETED, FABRIC_QUERY_SERVICE_REPLICA_STATUS_DOWN,
+    FABRIC_QUERY_SERVICE_REPLICA_STATUS_DROPPED, FABRIC_QUERY_SERVICE_REPLICA_STATUS_INBUILD,
+    FABRIC_QUERY_SERVICE_REPLICA_STATUS_INVALID, FABRIC_QUERY_SERVICE_REPLICA_STATUS_READY,
+    FABRIC_QUERY_SERVICE_REPLICA_STATUS_STANDBY, FABRIC_REPLICA_LOAD_INFORMATION,
+    FABRIC_REPLICA_ROLE, FABRIC_REPLICA_ROLE_ACTIVE_AUXILIARY,
+    FABRIC_REPLICA_ROLE_ACTIVE_SECONDARY, FABRIC_REPLICA_ROLE_IDLE_AUXILIARY,
+    FABRIC_REPLICA_ROLE_IDLE_SECONDARY, FABRIC_REPLICA_ROLE_NONE, FABRIC_REPLICA_ROLE_PRIMARY,
+    FABRIC_REPLICA_ROLE_PRIMARY_AUXILIARY, FABRIC_REPLICA_ROLE_UNKNOWN,
+    FABRIC_RESOLVED_SERVICE_ENDPOINT, FABRIC_SERVICE_ENDPOINT_ROLE, FABRIC_SERVICE_KIND,
+    FABRIC_SERVICE_KIND_INVALID, FABRIC_SERVICE_KIND_STATEFUL, FABRIC_SERVICE_KIND_STATELESS,
+    FABRIC_SERVICE_LOAD_METRIC_DESCRIPTION, FABRIC_SERVICE_LOAD_METRIC_DESCRIPTION_LIST,
+    FABRIC_SERVICE_LOAD_METRIC_WEIGHT, FABRIC_SERVICE_LOAD_METRIC_WEIGHT_HIGH,
+    FABRIC_SERVICE_LOAD_METRIC_WEIGHT_LOW, FABRIC_SERVICE_LOAD_METRIC_WEIGHT_MEDIUM,
+    FABRIC_SERVICE_LOAD_METRIC_WEIGHT_ZERO, FABRIC_SERVICE_PARTITION_INFORMATION,
+    FABRIC_SERVICE_PARTITION_KIND, FABRIC_SERVICE_PARTITION_KIND_INT64_RANGE,
+    FABRIC_SERVICE_PARTITION_KIND_INVALID, FABRIC_SERVICE_PARTITION_KIND_NAMED,
+    FABRIC_SERVICE_PARTITION_KIND_SINGLETON, FABRIC_SERVICE_PARTITION_QUERY_RESULT_ITEM,
+    FABRIC_SERVICE_ROLE_INVALID, FABRIC_SERVICE_ROLE_STATEFUL_AUXILIARY,
+    FABRIC_SERVICE_ROLE_STATEFUL_PRIMARY, FABRIC_SERVICE_ROLE_STATEFUL_PRIMARY_AUXILIARY,
+    FABRIC_SERVICE_ROLE_STATEFUL_SECONDARY, FABRIC_SERVICE_ROLE_STATELESS,
+    FABRIC_SERVICE_TYPE_DESCRIPTION, FABRIC_SERVICE_TYPE_QUERY_RESULT_ITEM,
+    FABRIC_SERVICE_TYPE_QUERY_RESULT_ITEM_EX1, FABRIC_SERVICE_TYPE_QUERY_RESULT_ITEM_EX2,
+    FABRIC_SERVICE_TYPE_REGISTRATION_STATUS, FABRIC_SERVICE_TYPE_REGISTRATION_STATUS_DISABLED,
+    FABRIC_SERVICE_TYPE_REGISTRATION_STATUS_INVALID,
+    FABRIC_SERVICE_TYPE_REGISTRATION_STATUS_NOT_REGISTERED,
+    FABRIC_SERVICE_TYPE_REGISTRATION_STATUS_REGISTERED, FABRIC_SINGLETON_PARTITION_INFORMATION,
     FABRIC_STATEFUL_SERVICE_PARTITION_QUERY_RESULT_ITEM,
-    FABRIC_STATELESS_SERVICE_PARTITION_QUERY_RESULT_ITEM,
+    FABRIC_STATEFUL_SERVICE_PARTITION_QUERY_RESULT_ITEM_EX1,
+    FABRIC_STATEFUL_SERVICE_PARTITION_QUERY_RESULT_ITEM_EX2,
+    FABRIC_STATEFUL_SERVICE_TYPE_DESCRIPTION, FABRIC_STATELESS_SERVICE_PARTITION_QUERY_RESULT_ITEM,
+    FABRIC_STATELESS_SERVICE_TYPE_DESCRIPTION, FABRIC_STRING_LIST,
+    FABRIC_UNPLACED_REPLICA_INFORMATION, FABRIC_UPDATE_PARTITION_LOAD_QUERY_RESULT_ITEM,
 };
 
 #[derive(Debug, Clone)]
@@ -194,6 +255,15 @@ impl TryFrom<&FABRIC_SERVICE_PARTITION_QUERY_RESULT_ITEM> for PartitionQueryResu
     }
 }
 
+impl PartitionQueryResultItem {
+    pub fn partition_id(&self) -> GUID {
+        match self {
+            Self::Stateful(service) => service.partition_information.partition_id(),
+            Self::Stateless(service) => service.partition_information.partition_id(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct StatefulService {
     pub partition_information: ServicePartitionInformation,
@@ -202,6 +272,8 @@ pub struct StatefulService {
     pub health_state: HealthState,
     pub partition_status: QueryServicePartitionStatus,
     pub last_quorum_loss_duration_in_seconds: i64,
+    pub primary_epoch: Option<Epoch>,
+    pub auxiliary_replica_count: Option<u32>,
 }
 
 impl TryFrom<&FABRIC_STATEFUL_SERVICE_PARTITION_QUERY_RESULT_ITEM> for StatefulService {
@@ -218,6 +290,19 @@ impl TryFrom<&FABRIC_STATEFUL_SERVICE_PARTITION_QUERY_RESULT_ITEM> for StatefulS
         let partition_status = QueryServicePartitionStatus::from(value.PartitionStatus);
         let last_quorum_loss_duration_in_seconds = value.LastQuorumLossDurationInSeconds;
 
+        // The newer fields hang off the `Reserved` pointer chain and are only
+        // present when the cluster runtime is recent enough to populate them.
+        let ex1 = unsafe {
+            (value.Reserved as *const FABRIC_STATEFUL_SERVICE_PARTITION_QUERY_RESULT_ITEM_EX1)
+                .as_ref()
+        };
+        let primary_epoch = ex1.map(|ex1| Epoch::from(&ex1.PrimaryEpoch));
+        let ex2 = ex1.and_then(|ex1| unsafe {
+            (ex1.Reserved as *const FABRIC_STATEFUL_SERVICE_PARTITION_QUERY_RESULT_ITEM_EX2)
+                .as_ref()
+        });
+        let auxiliary_replica_count = ex2.map(|ex2| ex2.AuxiliaryReplicaCount);
+
         Ok(Self {
             partition_information,
             target_replica_size,
@@ -225,10 +310,27 @@ impl TryFrom<&FABRIC_STATEFUL_SERVICE_PARTITION_QUERY_RESULT_ITEM> for StatefulS
             health_state,
             partition_status,
             last_quorum_loss_duration_in_seconds,
+            primary_epoch,
+            auxiliary_replica_count,
         })
     }
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Epoch {
+    pub data_loss_number: i64,
+    pub configuration_number: i64,
+}
+
+impl From<&FABRIC_EPOCH> for Epoch {
+    fn from(value: &FABRIC_EPOCH) -> Self {
+        Self {
+            data_loss_number: value.DataLossNumber,
+            configuration_number: value.ConfigurationNumber,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct StatelessService {
     pub partition_information: ServicePartitionInformation,
@@ -281,6 +383,12 @@ impl From<FABRIC_HEALTH_STATE> for HealthState {
     }
 }
 
+impl From<HealthState> for FABRIC_HEALTH_STATE {
+    fn from(state: HealthState) -> Self {
+        FABRIC_HEALTH_STATE(state as i32)
+    }
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 #[repr(i32)]
 pub enum ServiceKind {
@@ -357,6 +465,16 @@ impl TryFrom<&FABRIC_SERVICE_PARTITION_INFORMATION> for ServicePartitionInformat
     }
 }
 
+impl ServicePartitionInformation {
+    pub fn partition_id(&self) -> GUID {
+        match self {
+            Self::Singleton(info) => info.id,
+            Self::Int64Range(info) => info.id,
+            Self::Named(info) => info.id,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SingletonPartitionInformation {
     pub id: GUID,
@@ -404,3 +522,1566 @@ impl TryFrom<&FABRIC_NAMED_PARTITION_INFORMATION> for NamedPartitionInformation
         Ok(Self { id, name })
     }
 }
+
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub name: String,
+    pub ip_address_or_fqdn: String,
+    pub node_type: String,
+    pub code_version: String,
+    pub config_version: String,
+    pub status: NodeStatus,
+    pub up_time_in_seconds: i64,
+    pub health_state: HealthState,
+    pub is_seed_node: bool,
+    pub upgrade_domain: String,
+    pub fault_domain: String,
+    /// `None` when the cluster runtime doesn't report deactivation state.
+    pub deactivation_info: Option<NodeDeactivationInfo>,
+}
+
+impl TryFrom<&FABRIC_NODE_QUERY_RESULT_ITEM> for Node {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_NODE_QUERY_RESULT_ITEM) -> Result<Self, Self::Error> {
+        let name = unsafe { wide_to_string(value.NodeName.0)? };
+        let ip_address_or_fqdn = unsafe { wide_to_string(value.IpAddressOrFQDN.0)? };
+        let node_type = unsafe { wide_to_string(value.NodeType.0)? };
+        let code_version = unsafe { wide_to_string(value.CodeVersion.0)? };
+        let config_version = unsafe { wide_to_string(value.ConfigVersion.0)? };
+        let status = NodeStatus::from(value.NodeStatus);
+        let up_time_in_seconds = value.NodeUpTimeInSeconds;
+        let health_state = HealthState::from(value.AggregatedHealthState);
+        let is_seed_node = value.IsSeedNode.as_bool();
+        let upgrade_domain = unsafe { wide_to_string(value.UpgradeDomain.0)? };
+        let fault_domain = unsafe { wide_to_string(value.FaultDomain)? };
+        let ex1 = unsafe { (value.Reserved as *const FABRIC_NODE_QUERY_RESULT_ITEM_EX1).as_ref() };
+        let ex2 = ex1.and_then(|ex1| unsafe {
+            (ex1.Reserved as *const FABRIC_NODE_QUERY_RESULT_ITEM_EX2).as_ref()
+        });
+        let ex3 = ex2.and_then(|ex2| unsafe {
+            (ex2.Reserved as *const FABRIC_NODE_QUERY_RESULT_ITEM_EX3).as_ref()
+        });
+        let deactivation_info = ex3
+            .and_then(|ex3| unsafe { ex3.NodeDeactivationInfo.as_ref() })
+            .map(NodeDeactivationInfo::from);
+
+        Ok(Self {
+            name,
+            ip_address_or_fqdn,
+            node_type,
+            code_version,
+            config_version,
+            status,
+            up_time_in_seconds,
+            health_state,
+            is_seed_node,
+            upgrade_domain,
+            fault_domain,
+            deactivation_info,
+        })
+    }
+}
+
+/// Why a node is being deactivated. Each intent includes the safety checks
+/// of the ones before it.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[repr(i32)]
+pub enum NodeDeactivationIntent {
+    /// Keep the node's replicas but stop placing new ones on it.
+    Pause = FABRIC_NODE_DEACTIVATION_INTENT_PAUSE.0,
+    /// The node will come back with its data, e.g. after an OS patch.
+    Restart = FABRIC_NODE_DEACTIVATION_INTENT_RESTART.0,
+    /// The node will come back without its data, e.g. after a reimage.
+    RemoveData = FABRIC_NODE_DEACTIVATION_INTENT_REMOVE_DATA.0,
+    /// The node is leaving the cluster for good.
+    RemoveNode = FABRIC_NODE_DEACTIVATION_INTENT_REMOVE_NODE.0,
+}
+
+impl NodeDeactivationIntent {
+    fn from_raw(intent: FABRIC_NODE_DEACTIVATION_INTENT) -> Option<Self> {
+        match intent {
+            FABRIC_NODE_DEACTIVATION_INTENT_PAUSE => Some(Self::Pause),
+            FABRIC_NODE_DEACTIVATION_INTENT_RESTART => Some(Self::Restart),
+            FABRIC_NODE_DEACTIVATION_INTENT_REMOVE_DATA => Some(Self::RemoveData),
+            FABRIC_NODE_DEACTIVATION_INTENT_REMOVE_NODE => Some(Self::RemoveNode),
+            _ => None,
+        }
+    }
+}
+
+impl From<NodeDeactivationIntent> for FABRIC_NODE_DEACTIVATION_INTENT {
+    fn from(intent: NodeDeactivationIntent) -> Self {
+        FABRIC_NODE_DEACTIVATION_INTENT(intent as i32)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[repr(i32)]
+pub enum NodeDeactivationStatus {
+    None = FABRIC_NODE_DEACTIVATION_STATUS_NONE.0,
+    SafetyCheckInProgress = FABRIC_NODE_DEACTIVATION_STATUS_SAFETY_CHECK_IN_PROGRESS.0,
+    SafetyCheckComplete = FABRIC_NODE_DEACTIVATION_STATUS_SAFETY_CHECK_COMPLETE.0,
+    Completed = FABRIC_NODE_DEACTIVATION_STATUS_COMPLETED.0,
+}
+
+impl From<FABRIC_NODE_DEACTIVATION_STATUS> for NodeDeactivationStatus {
+    fn from(status: FABRIC_NODE_DEACTIVATION_STATUS) -> Self {
+        match status {
+            FABRIC_NODE_DEACTIVATION_STATUS_SAFETY_CHECK_IN_PROGRESS => Self::SafetyCheckInProgress,
+            FABRIC_NODE_DEACTIVATION_STATUS_SAFETY_CHECK_COMPLETE => Self::SafetyCheckComplete,
+            FABRIC_NODE_DEACTIVATION_STATUS_COMPLETED => Self::Completed,
+            _ => Self::None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct NodeDeactivationInfo {
+    /// The strongest intent among the pending deactivation requests, or
+    /// `None` if the node isn't being deactivated.
+    pub effective_intent: Option<NodeDeactivationIntent>,
+    pub status: NodeDeactivationStatus,
+}
+
+impl From<&FABRIC_NODE_DEACTIVATION_QUERY_RESULT_ITEM> for NodeDeactivationInfo {
+    fn from(value: &FABRIC_NODE_DEACTIVATION_QUERY_RESULT_ITEM) -> Self {
+        Self {
+            effective_intent: NodeDeactivationIntent::from_raw(value.EffectiveIntent),
+            status: value.Status.into(),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[repr(i32)]
+pub enum NodeStatus {
+    Invalid = FABRIC_QUERY_NODE_STATUS_INVALID.0,
+    Up = FABRIC_QUERY_NODE_STATUS_UP.0,
+    Down = FABRIC_QUERY_NODE_STATUS_DOWN.0,
+    Enabling = FABRIC_QUERY_NODE_STATUS_ENABLING.0,
+    Disabling = FABRIC_QUERY_NODE_STATUS_DISABLING.0,
+    Disabled = FABRIC_QUERY_NODE_STATUS_DISABLED.0,
+    Unknown = FABRIC_QUERY_NODE_STATUS_UNKNOWN.0,
+    Removed = FABRIC_QUERY_NODE_STATUS_REMOVED.0,
+}
+
+impl From<FABRIC_QUERY_NODE_STATUS> for NodeStatus {
+    fn from(status: FABRIC_QUERY_NODE_STATUS) -> Self {
+        match status {
+            FABRIC_QUERY_NODE_STATUS_INVALID => Self::Invalid,
+            FABRIC_QUERY_NODE_STATUS_UP => Self::Up,
+            FABRIC_QUERY_NODE_STATUS_DOWN => Self::Down,
+            FABRIC_QUERY_NODE_STATUS_ENABLING => Self::Enabling,
+            FABRIC_QUERY_NODE_STATUS_DISABLING => Self::Disabling,
+            FABRIC_QUERY_NODE_STATUS_DISABLED => Self::Disabled,
+            FABRIC_QUERY_NODE_STATUS_UNKNOWN => Self::Unknown,
+            FABRIC_QUERY_NODE_STATUS_REMOVED => Self::Removed,
+            _ => Self::Invalid,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[repr(i32)]
+pub enum DeploymentStatus {
+    Invalid = FABRIC_DEPLOYMENT_STATUS_INVALID.0,
+    Downloading = FABRIC_DEPLOYMENT_STATUS_DOWNLOADING.0,
+    Activating = FABRIC_DEPLOYMENT_STATUS_ACTIVATING.0,
+    Active = FABRIC_DEPLOYMENT_STATUS_ACTIVE.0,
+    Upgrading = FABRIC_DEPLOYMENT_STATUS_UPGRADING.0,
+    Deactivating = FABRIC_DEPLOYMENT_STATUS_DEACTIVATING.0,
+    RanToCompletion = FABRIC_DEPLOYMENT_STATUS_RAN_TO_COMPLETION.0,
+    Failed = FABRIC_DEPLOYMENT_STATUS_FAILED.0,
+}
+
+impl From<FABRIC_DEPLOYMENT_STATUS> for DeploymentStatus {
+    fn from(status: FABRIC_DEPLOYMENT_STATUS) -> Self {
+        match status {
+            FABRIC_DEPLOYMENT_STATUS_INVALID => Self::Invalid,
+            FABRIC_DEPLOYMENT_STATUS_DOWNLOADING => Self::Downloading,
+            FABRIC_DEPLOYMENT_STATUS_ACTIVATING => Self::Activating,
+            FABRIC_DEPLOYMENT_STATUS_ACTIVE => Self::Active,
+            FABRIC_DEPLOYMENT_STATUS_UPGRADING => Self::Upgrading,
+            FABRIC_DEPLOYMENT_STATUS_DEACTIVATING => Self::Deactivating,
+            FABRIC_DEPLOYMENT_STATUS_RAN_TO_COMPLETION => Self::RanToCompletion,
+            FABRIC_DEPLOYMENT_STATUS_FAILED => Self::Failed,
+            _ => Self::Invalid,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DeployedApplication {
+    pub application_name: String,
+    pub application_type_name: String,
+    pub status: DeploymentStatus,
+}
+
+impl TryFrom<&FABRIC_DEPLOYED_APPLICATION_QUERY_RESULT_ITEM> for DeployedApplication {
+    type Error = Error;
+
+    fn try_from(
+        value: &FABRIC_DEPLOYED_APPLICATION_QUERY_RESULT_ITEM,
+    ) -> Result<Self, Self::Error> {
+        let application_name = unsafe { wide_to_string(value.ApplicationName)? };
+        let application_type_name = unsafe { wide_to_string(value.ApplicationTypeName.0)? };
+        let status = DeploymentStatus::from(value.DeployedApplicationStatus);
+
+        Ok(Self {
+            application_name,
+            application_type_name,
+            status,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DeployedServicePackage {
+    pub service_manifest_name: String,
+    pub service_manifest_version: String,
+    pub status: DeploymentStatus,
+}
+
+impl TryFrom<&FABRIC_DEPLOYED_SERVICE_PACKAGE_QUERY_RESULT_ITEM> for DeployedServicePackage {
+    type Error = Error;
+
+    fn try_from(
+        value: &FABRIC_DEPLOYED_SERVICE_PACKAGE_QUERY_RESULT_ITEM,
+    ) -> Result<Self, Self::Error> {
+        let service_manifest_name = unsafe { wide_to_string(value.ServiceManifestName.0)? };
+        let service_manifest_version = unsafe { wide_to_string(value.ServiceManifestVersion.0)? };
+        let status = DeploymentStatus::from(value.DeployedServicePackageStatus);
+
+        Ok(Self {
+            service_manifest_name,
+            service_manifest_version,
+            status,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DeployedCodePackage {
+    pub code_package_name: String,
+    pub code_package_version: String,
+    pub service_manifest_name: String,
+    pub run_frequency_interval: u32,
+    pub status: DeploymentStatus,
+    pub setup_entry_point: Option<CodePackageEntryPoint>,
+    pub entry_point: Option<CodePackageEntryPoint>,
+}
+
+impl TryFrom<&FABRIC_DEPLOYED_CODE_PACKAGE_QUERY_RESULT_ITEM> for DeployedCodePackage {
+    type Error = Error;
+
+    fn try_from(
+        value: &FABRIC_DEPLOYED_CODE_PACKAGE_QUERY_RESULT_ITEM,
+    ) -> Result<Self, Self::Error> {
+        let code_package_name = unsafe { wide_to_string(value.CodePackageName.0)? };
+        let code_package_version = unsafe { wide_to_string(value.CodePackageVersion.0)? };
+        let service_manifest_name = unsafe { wide_to_string(value.ServiceManifestName.0)? };
+        let run_frequency_interval = value.RunFrequencyInterval;
+        let status = DeploymentStatus::from(value.DeployedCodePackageStatus);
+        let setup_entry_point = unsafe { value.SetupEntryPoint.as_ref() }
+            .map(CodePackageEntryPoint::try_from)
+            .transpose()?;
+        let entry_point = unsafe { value.EntryPoint.as_ref() }
+            .map(CodePackageEntryPoint::try_from)
+            .transpose()?;
+
+        Ok(Self {
+            code_package_name,
+            code_package_version,
+            service_manifest_name,
+            run_frequency_interval,
+            status,
+            setup_entry_point,
+            entry_point,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CodePackageEntryPoint {
+    pub location: String,
+    pub process_id: i64,
+    pub run_as_user_name: String,
+    pub status: EntryPointStatus,
+    pub next_activation: Option<SystemTime>,
+    pub statistics: Option<CodePackageEntryPointStatistics>,
+}
+
+impl TryFrom<&FABRIC_CODE_PACKAGE_ENTRY_POINT> for CodePackageEntryPoint {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_CODE_PACKAGE_ENTRY_POINT) -> Result<Self, Self::Error> {
+        let location = unsafe { wide_to_string(value.EntryPointLocation.0)? };
+        let process_id = value.ProcessId;
+        let run_as_user_name = unsafe { wide_to_string(value.RunAsUserName.0)? };
+        let status = EntryPointStatus::from(value.EntryPointStatus);
+        let next_activation = filetime_to_system_time(&value.NextActivationUtc);
+        let statistics =
+            unsafe { value.Statistics.as_ref() }.map(CodePackageEntryPointStatistics::from);
+
+        Ok(Self {
+            location,
+            process_id,
+            run_as_user_name,
+            status,
+            next_activation,
+            statistics,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CodePackageEntryPointStatistics {
+    pub last_exit_code: u32,
+    pub last_activation: Option<SystemTime>,
+    pub last_exit: Option<SystemTime>,
+    pub last_successful_activation: Option<SystemTime>,
+    pub last_successful_exit: Option<SystemTime>,
+    pub activation_count: u32,
+    pub activation_failure_count: u32,
+    pub continuous_activation_failure_count: u32,
+    pub exit_count: u32,
+    pub exit_failure_count: u32,
+    pub continuous_exit_failure_count: u32,
+}
+
+impl From<&FABRIC_CODE_PACKAGE_ENTRY_POINT_STATISTICS> for CodePackageEntryPointStatistics {
+    fn from(value: &FABRIC_CODE_PACKAGE_ENTRY_POINT_STATISTICS) -> Self {
+        Self {
+            last_exit_code: value.LastExitCode,
+            last_activation: filetime_to_system_time(&value.LastActivationUtc),
+            last_exit: filetime_to_system_time(&value.LastExitUtc),
+            last_successful_activation: filetime_to_system_time(&value.LastSuccessfulActivationUtc),
+            last_successful_exit: filetime_to_system_time(&value.LastSuccessfulExitUtc),
+            activation_count: value.ActivationCount,
+            activation_failure_count: value.ActivationFailureCount,
+            continuous_activation_failure_count: value.ContinuousActivationFailureCount,
+            exit_count: value.ExitCount,
+            exit_failure_count: value.ExitFailureCount,
+            continuous_exit_failure_count: value.ContinuousExitFailureCount,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[repr(i32)]
+pub enum EntryPointStatus {
+    Invalid = FABRIC_ENTRY_POINT_STATUS_INVALID.0,
+    Pending = FABRIC_ENTRY_POINT_STATUS_PENDING.0,
+    Starting = FABRIC_ENTRY_POINT_STATUS_STARTING.0,
+    Started = FABRIC_ENTRY_POINT_STATUS_STARTED.0,
+    Stopping = FABRIC_ENTRY_POINT_STATUS_STOPPING.0,
+    Stopped = FABRIC_ENTRY_POINT_STATUS_STOPPED.0,
+}
+
+impl From<FABRIC_ENTRY_POINT_STATUS> for EntryPointStatus {
+    fn from(status: FABRIC_ENTRY_POINT_STATUS) -> Self {
+        match status {
+            FABRIC_ENTRY_POINT_STATUS_INVALID => Self::Invalid,
+            FABRIC_ENTRY_POINT_STATUS_PENDING => Self::Pending,
+            FABRIC_ENTRY_POINT_STATUS_STARTING => Self::Starting,
+            FABRIC_ENTRY_POINT_STATUS_STARTED => Self::Started,
+            FABRIC_ENTRY_POINT_STATUS_STOPPING => Self::Stopping,
+            FABRIC_ENTRY_POINT_STATUS_STOPPED => Self::Stopped,
+            _ => Self::Invalid,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum DeployedReplica {
+    Stateful(DeployedStatefulReplica),
+    Stateless(DeployedStatelessInstance),
+}
+
+impl DeployedReplica {
+    pub fn partition_id(&self) -> GUID {
+        match self {
+            Self::Stateful(replica) => replica.partition_id,
+            Self::Stateless(instance) => instance.partition_id,
+        }
+    }
+}
+
+impl TryFrom<&FABRIC_DEPLOYED_SERVICE_REPLICA_QUERY_RESULT_ITEM> for DeployedReplica {
+    type Error = Error;
+
+    fn try_from(
+        value: &FABRIC_DEPLOYED_SERVICE_REPLICA_QUERY_RESULT_ITEM,
+    ) -> Result<Self, Self::Error> {
+        match ServiceKind::from(value.Kind) {
+            ServiceKind::Stateful => {
+                let replica = DeployedStatefulReplica::try_from(unsafe {
+                    &*(value.Value
+                        as *const FABRIC_DEPLOYED_STATEFUL_SERVICE_REPLICA_QUERY_RESULT_ITEM)
+                })?;
+                Ok(Self::Stateful(replica))
+            }
+            ServiceKind::Stateless => {
+                let instance = DeployedStatelessInstance::try_from(unsafe {
+                    &*(value.Value
+                        as *const FABRIC_DEPLOYED_STATELESS_SERVICE_INSTANCE_QUERY_RESULT_ITEM)
+                })?;
+                Ok(Self::Stateless(instance))
+            }
+            _ => Err(Error::InvalidServiceKind),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DeployedStatefulReplica {
+    pub service_name: String,
+    pub service_type_name: String,
+    pub service_manifest_version: String,
+    pub code_package_name: String,
+    pub partition_id: GUID,
+    pub replica_id: i64,
+    pub role: ReplicaRole,
+    pub status: ReplicaStatus,
+    pub address: String,
+}
+
+impl TryFrom<&FABRIC_DEPLOYED_STATEFUL_SERVICE_REPLICA_QUERY_RESULT_ITEM>
+    for DeployedStatefulReplica
+{
+    type Error = Error;
+
+    fn try_from(
+        value: &FABRIC_DEPLOYED_STATEFUL_SERVICE_REPLICA_QUERY_RESULT_ITEM,
+    ) -> Result<Self, Self::Error> {
+        let service_name = unsafe { wide_to_string(value.ServiceName)? };
+        let service_type_name = unsafe { wide_to_string(value.ServiceTypeName.0)? };
+        let service_manifest_version = unsafe { wide_to_string(value.ServiceManifestVersion.0)? };
+        let code_package_name = unsafe { wide_to_string(value.CodePackageName.0)? };
+        let partition_id = value.PartitionId;
+        let replica_id = value.ReplicaId;
+        let role = ReplicaRole::from(value.ReplicaRole);
+        let status = ReplicaStatus::from(value.ReplicaStatus);
+        let address = unsafe { wide_to_string(value.Address.0)? };
+
+        Ok(Self {
+            service_name,
+            service_type_name,
+            service_manifest_version,
+            code_package_name,
+            partition_id,
+            replica_id,
+            role,
+            status,
+            address,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DeployedStatelessInstance {
+    pub service_name: String,
+    pub service_type_name: String,
+    pub service_manifest_version: String,
+    pub code_package_name: String,
+    pub partition_id: GUID,
+    pub instance_id: i64,
+    pub status: ReplicaStatus,
+    pub address: String,
+}
+
+impl TryFrom<&FABRIC_DEPLOYED_STATELESS_SERVICE_INSTANCE_QUERY_RESULT_ITEM>
+    for DeployedStatelessInstance
+{
+    type Error = Error;
+
+    fn try_from(
+        value: &FABRIC_DEPLOYED_STATELESS_SERVICE_INSTANCE_QUERY_RESULT_ITEM,
+    ) -> Result<Self, Self::Error> {
+        let service_name = unsafe { wide_to_string(value.ServiceName)? };
+        let service_type_name = unsafe { wide_to_string(value.ServiceTypeName.0)? };
+        let service_manifest_version = unsafe { wide_to_string(value.ServiceManifestVersion.0)? };
+        let code_package_name = unsafe { wide_to_string(value.CodePackageName.0)? };
+        let partition_id = value.PartitionId;
+        let instance_id = value.InstanceId;
+        let status = ReplicaStatus::from(value.ReplicaStatus);
+        let address = unsafe { wide_to_string(value.Address.0)? };
+
+        Ok(Self {
+            service_name,
+            service_type_name,
+            service_manifest_version,
+            code_package_name,
+            partition_id,
+            instance_id,
+            status,
+            address,
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[repr(i32)]
+pub enum ReplicaRole {
+    Unknown = FABRIC_REPLICA_ROLE_UNKNOWN.0,
+    None = FABRIC_REPLICA_ROLE_NONE.0,
+    Primary = FABRIC_REPLICA_ROLE_PRIMARY.0,
+    IdleSecondary = FABRIC_REPLICA_ROLE_IDLE_SECONDARY.0,
+    ActiveSecondary = FABRIC_REPLICA_ROLE_ACTIVE_SECONDARY.0,
+    IdleAuxiliary = FABRIC_REPLICA_ROLE_IDLE_AUXILIARY.0,
+    ActiveAuxiliary = FABRIC_REPLICA_ROLE_ACTIVE_AUXILIARY.0,
+    PrimaryAuxiliary = FABRIC_REPLICA_ROLE_PRIMARY_AUXILIARY.0,
+}
+
+impl From<FABRIC_REPLICA_ROLE> for ReplicaRole {
+    fn from(role: FABRIC_REPLICA_ROLE) -> Self {
+        match role {
+            FABRIC_REPLICA_ROLE_UNKNOWN => Self::Unknown,
+            FABRIC_REPLICA_ROLE_NONE => Self::None,
+            FABRIC_REPLICA_ROLE_PRIMARY => Self::Primary,
+            FABRIC_REPLICA_ROLE_IDLE_SECONDARY => Self::IdleSecondary,
+            FABRIC_REPLICA_ROLE_ACTIVE_SECONDARY => Self::ActiveSecondary,
+            FABRIC_REPLICA_ROLE_IDLE_AUXILIARY => Self::IdleAuxiliary,
+            FABRIC_REPLICA_ROLE_ACTIVE_AUXILIARY => Self::ActiveAuxiliary,
+            FABRIC_REPLICA_ROLE_PRIMARY_AUXILIARY => Self::PrimaryAuxiliary,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[repr(i32)]
+pub enum ReplicaStatus {
+    Invalid = FABRIC_QUERY_SERVICE_REPLICA_STATUS_INVALID.0,
+    InBuild = FABRIC_QUERY_SERVICE_REPLICA_STATUS_INBUILD.0,
+    Standby = FABRIC_QUERY_SERVICE_REPLICA_STATUS_STANDBY.0,
+    Ready = FABRIC_QUERY_SERVICE_REPLICA_STATUS_READY.0,
+    Down = FABRIC_QUERY_SERVICE_REPLICA_STATUS_DOWN.0,
+    Dropped = FABRIC_QUERY_SERVICE_REPLICA_STATUS_DROPPED.0,
+    Completed = FABRIC_QUERY_SERVICE_REPLICA_STATUS_COMPLETED.0,
+}
+
+impl From<FABRIC_QUERY_SERVICE_REPLICA_STATUS> for ReplicaStatus {
+    fn from(status: FABRIC_QUERY_SERVICE_REPLICA_STATUS) -> Self {
+        match status {
+            FABRIC_QUERY_SERVICE_REPLICA_STATUS_INVALID => Self::Invalid,
+            FABRIC_QUERY_SERVICE_REPLICA_STATUS_INBUILD => Self::InBuild,
+            FABRIC_QUERY_SERVICE_REPLICA_STATUS_STANDBY => Self::Standby,
+            FABRIC_QUERY_SERVICE_REPLICA_STATUS_READY => Self::Ready,
+            FABRIC_QUERY_SERVICE_REPLICA_STATUS_DOWN => Self::Down,
+            FABRIC_QUERY_SERVICE_REPLICA_STATUS_DROPPED => Self::Dropped,
+            FABRIC_QUERY_SERVICE_REPLICA_STATUS_COMPLETED => Self::Completed,
+            _ => Self::Invalid,
+        }
+    }
+}
+
+/// Converts a possibly null wide string returned by SF into an owned `String`.
+pub(crate) unsafe fn wide_to_string(value: *const u16) -> Result<String, Error> {
+    if value.is_null() {
+        Ok(String::new())
+    } else {
+        Ok(PCWSTR::from_raw(value).to_string()?)
+    }
+}
+
+/// Views a `Count`/`Items` pair from an SF result list as a slice.
+pub(crate) unsafe fn list_items<'a, T>(items: *const T, count: u32) -> &'a [T] {
+    if items.is_null() || count == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(items, count as usize)
+    }
+}
+
+/// Converts a `FILETIME` into a `SystemTime`, treating the zero value as unset.
+pub(crate) fn filetime_to_system_time(value: &FILETIME) -> Option<SystemTime> {
+    // Number of 100ns intervals between 1601-01-01 and 1970-01-01.
+    const UNIX_EPOCH_INTERVALS: u64 = 116_444_736_000_000_000;
+
+    let intervals = ((value.dwHighDateTime as u64) << 32) | value.dwLowDateTime as u64;
+    if intervals == 0 {
+        return None;
+    }
+
+    let since_unix_epoch = intervals.checked_sub(UNIX_EPOCH_INTERVALS)?;
+    Some(UNIX_EPOCH + Duration::from_nanos(since_unix_epoch.saturating_mul(100)))
+}
+
+/// Converts an optional duration to whole seconds, saturating at `u32::MAX`.
+/// `None` maps to `u32::MAX` too, which SF reads as "infinite" for timeouts.
+pub(crate) fn seconds(duration: Option<Duration>) -> u32 {
+    duration.map_or(u32::MAX, |duration| {
+        duration.as_secs().try_into().unwrap_or(u32::MAX)
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct ClusterLoadInformation {
+    pub last_balancing_start: Option<SystemTime>,
+    pub last_balancing_end: Option<SystemTime>,
+    pub metrics: Vec<LoadMetricInformation>,
+}
+
+impl TryFrom<&FABRIC_CLUSTER_LOAD_INFORMATION> for ClusterLoadInformation {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_CLUSTER_LOAD_INFORMATION) -> Result<Self, Self::Error> {
+        let last_balancing_start = filetime_to_system_time(&value.LastBalancingStartTimeUtc);
+        let last_balancing_end = filetime_to_system_time(&value.LastBalancingEndTimeUtc);
+        let metrics = match unsafe { value.LoadMetricInformation.as_ref() } {
+            Some(list) => unsafe { list_items(list.Items, list.Count) }
+                .iter()
+                .map(LoadMetricInformation::try_from)
+                .collect::<Result<Vec<_>, Error>>()?,
+            None => vec![],
+        };
+
+        Ok(Self {
+            last_balancing_start,
+            last_balancing_end,
+            metrics,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LoadMetricInformation {
+    pub name: String,
+    pub is_balanced_before: bool,
+    pub is_balanced_after: bool,
+    pub deviation_before: f64,
+    pub deviation_after: f64,
+    pub balancing_threshold: f64,
+    pub action: String,
+    pub activity_threshold: Option<u32>,
+    pub capacity: Option<i64>,
+    pub load: Option<i64>,
+    pub remaining_capacity: Option<i64>,
+    pub is_capacity_violation: Option<bool>,
+}
+
+impl TryFrom<&FABRIC_LOAD_METRIC_INFORMATION> for LoadMetricInformation {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_LOAD_METRIC_INFORMATION) -> Result<Self, Self::Error> {
+        let name = unsafe { wide_to_string(value.Name.0)? };
+        let action = unsafe { wide_to_string(value.Action.0)? };
+        let ex1 = unsafe { (value.Reserved as *const FABRIC_LOAD_METRIC_INFORMATION_EX1).as_ref() };
+        let ex2 = ex1.and_then(|ex1| unsafe {
+            (ex1.Reserved as *const FABRIC_LOAD_METRIC_INFORMATION_EX2).as_ref()
+        });
+
+        Ok(Self {
+            name,
+            is_balanced_before: value.IsBalancedBefore.as_bool(),
+            is_balanced_after: value.IsBalancedAfter.as_bool(),
+            deviation_before: value.DeviationBefore,
+            deviation_after: value.DeviationAfter,
+            balancing_threshold: value.BalancingThreshold,
+            action,
+            activity_threshold: ex1.map(|ex1| ex1.ActivityThreshold),
+            capacity: ex1.map(|ex1| ex1.ClusterCapacity),
+            load: ex1.map(|ex1| ex1.ClusterLoad),
+            remaining_capacity: ex2.map(|ex2| ex2.RemainingUnbufferedCapacity),
+            is_capacity_violation: ex2.map(|ex2| ex2.IsClusterCapacityViolation.as_bool()),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NodeLoadInformation {
+    pub node_name: String,
+    pub metrics: Vec<NodeLoadMetricInformation>,
+}
+
+impl TryFrom<&FABRIC_NODE_LOAD_INFORMATION> for NodeLoadInformation {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_NODE_LOAD_INFORMATION) -> Result<Self, Self::Error> {
+        let node_name = unsafe { wide_to_string(value.NodeName.0)? };
+        let metrics = match unsafe { value.NodeLoadMetricInformation.as_ref() } {
+            Some(list) => unsafe { list_items(list.Items, list.Count) }
+                .iter()
+                .map(NodeLoadMetricInformation::try_from)
+                .collect::<Result<Vec<_>, Error>>()?,
+            None => vec![],
+        };
+
+        Ok(Self { node_name, metrics })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NodeLoadMetricInformation {
+    pub name: String,
+    pub capacity: i64,
+    pub load: i64,
+    pub remaining_capacity: i64,
+    pub is_capacity_violation: bool,
+    pub buffered_capacity: Option<i64>,
+    pub remaining_buffered_capacity: Option<i64>,
+}
+
+impl TryFrom<&FABRIC_NODE_LOAD_METRIC_INFORMATION> for NodeLoadMetricInformation {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_NODE_LOAD_METRIC_INFORMATION) -> Result<Self, Self::Error> {
+        let name = unsafe { wide_to_string(value.Name.0)? };
+        let ex1 =
+            unsafe { (value.Reserved as *const FABRIC_NODE_LOAD_METRIC_INFORMATION_EX1).as_ref() };
+
+        Ok(Self {
+            name,
+            capacity: value.NodeCapacity,
+            load: value.NodeLoad,
+            remaining_capacity: value.NodeRemainingCapacity,
+            is_capacity_violation: value.IsCapacityViolation.as_bool(),
+            buffered_capacity: ex1.map(|ex1| ex1.NodeBufferedCapacity),
+            remaining_buffered_capacity: ex1.map(|ex1| ex1.NodeRemainingBufferedCapacity),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PartitionLoadInformation {
+    pub partition_id: GUID,
+    pub primary_load_metric_reports: Vec<LoadMetricReport>,
+    pub secondary_load_metric_reports: Vec<LoadMetricReport>,
+    pub auxiliary_load_metric_reports: Vec<LoadMetricReport>,
+}
+
+impl TryFrom<&FABRIC_PARTITION_LOAD_INFORMATION> for PartitionLoadInformation {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_PARTITION_LOAD_INFORMATION) -> Result<Self, Self::Error> {
+        let primary_load_metric_reports =
+            unsafe { load_metric_reports(value.PrimaryLoadMetricReports)? };
+        let secondary_load_metric_reports =
+            unsafe { load_metric_reports(value.SecondaryLoadMetricReports)? };
+        let auxiliary_load_metric_reports = match unsafe {
+            (value.Reserved as *const FABRIC_PARTITION_LOAD_INFORMATION_EX1).as_ref()
+        } {
+            Some(ex1) => unsafe { load_metric_reports(ex1.AuxiliaryLoadMetricReports)? },
+            None => vec![],
+        };
+
+        Ok(Self {
+            partition_id: value.PartitionId,
+            primary_load_metric_reports,
+            secondary_load_metric_reports,
+            auxiliary_load_metric_reports,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplicaLoadInformation {
+    pub partition_id: GUID,
+    pub replica_or_instance_id: i64,
+    pub load_metric_reports: Vec<LoadMetricReport>,
+}
+
+impl TryFrom<&FABRIC_REPLICA_LOAD_INFORMATION> for ReplicaLoadInformation {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_REPLICA_LOAD_INFORMATION) -> Result<Self, Self::Error> {
+        let load_metric_reports = unsafe { load_metric_reports(value.LoadMetricReports)? };
+
+        Ok(Self {
+            partition_id: value.PartitionId,
+            replica_or_instance_id: value.ReplicaOrInstanceId,
+            load_metric_reports,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LoadMetricReport {
+    pub name: String,
+    pub value: u32,
+    pub current_value: Option<f64>,
+    pub last_reported: Option<SystemTime>,
+}
+
+impl TryFrom<&FABRIC_LOAD_METRIC_REPORT> for LoadMetricReport {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_LOAD_METRIC_REPORT) -> Result<Self, Self::Error> {
+        let name = unsafe { wide_to_string(value.Name.0)? };
+        let ex1 = unsafe { (value.Reserved as *const FABRIC_LOAD_METRIC_REPORT_EX1).as_ref() };
+
+        Ok(Self {
+            name,
+            value: value.Value,
+            current_value: ex1.map(|ex1| ex1.CurrentValue),
+            last_reported: filetime_to_system_time(&value.LastReportedUtc),
+        })
+    }
+}
+
+unsafe fn load_metric_reports(
+    list: *const FABRIC_LOAD_METRIC_REPORT_LIST,
+) -> Result<Vec<LoadMetricReport>, Error> {
+    match list.as_ref() {
+        Some(list) => list_items(list.Items, list.Count)
+            .iter()
+            .map(LoadMetricReport::try_from)
+            .collect(),
+        None => Ok(vec![]),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ApplicationLoadInformation {
+    pub name: String,
+    pub minimum_nodes: u32,
+    pub maximum_nodes: u32,
+    pub node_count: u32,
+    pub metrics: Vec<ApplicationLoadMetricInformation>,
+}
+
+impl TryFrom<&FABRIC_APPLICATION_LOAD_INFORMATION> for ApplicationLoadInformation {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_APPLICATION_LOAD_INFORMATION) -> Result<Self, Self::Error> {
+        let name = unsafe { wide_to_string(value.Name.0)? };
+        let metrics = match unsafe { value.ApplicationLoadMetricInformation.as_ref() } {
+            Some(list) => unsafe { list_items(list.LoadMetrics, list.Count) }
+                .iter()
+                .map(ApplicationLoadMetricInformation::try_from)
+                .collect::<Result<Vec<_>, Error>>()?,
+            None => vec![],
+        };
+
+        Ok(Self {
+            name,
+            minimum_nodes: value.MinimumNodes,
+            maximum_nodes: value.MaximumNodes,
+            node_count: value.NodeCount,
+            metrics,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ApplicationLoadMetricInformation {
+    pub name: String,
+    pub reservation_capacity: i64,
+    pub application_capacity: i64,
+    pub application_load: i64,
+}
+
+impl TryFrom<&FABRIC_APPLICATION_LOAD_METRIC_INFORMATION> for ApplicationLoadMetricInformation {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_APPLICATION_LOAD_METRIC_INFORMATION) -> Result<Self, Self::Error> {
+        let name = unsafe { wide_to_string(value.Name.0)? };
+
+        Ok(Self {
+            name,
+            reservation_capacity: value.ReservationCapacity,
+            application_capacity: value.ApplicationCapacity,
+            application_load: value.ApplicationLoad,
+        })
+    }
+}
+
+/// New load values for the replicas of a partition, passed to
+/// `ClusterManagementClient::update_partition_load`.
+#[derive(Debug, Clone)]
+pub struct PartitionMetricLoad {
+    pub partition_id: GUID,
+    pub primary_replica_load: Vec<MetricLoad>,
+    pub secondary_replicas_or_instances_load: Vec<MetricLoad>,
+    pub secondary_replica_or_instance_load_per_node: Vec<ReplicaMetricLoad>,
+}
+
+/// Load for the secondary replica or instance placed on a specific node.
+#[derive(Debug, Clone)]
+pub struct ReplicaMetricLoad {
+    pub node_name: String,
+    pub load: Vec<MetricLoad>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MetricLoad {
+    pub metric_name: String,
+    pub current_load: Option<u32>,
+    pub predicted_load: Option<u32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PartitionLoadUpdateResult {
+    pub partition_id: GUID,
+    pub error_code: HRESULT,
+}
+
+impl PartitionLoadUpdateResult {
+    pub fn is_success(&self) -> bool {
+        self.error_code.is_ok()
+    }
+}
+
+impl From<&FABRIC_UPDATE_PARTITION_LOAD_QUERY_RESULT_ITEM> for PartitionLoadUpdateResult {
+    fn from(value: &FABRIC_UPDATE_PARTITION_LOAD_QUERY_RESULT_ITEM) -> Self {
+        Self {
+            partition_id: value.PartitionId,
+            error_code: value.ErrorCode,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UnplacedReplicaInformation {
+    pub service_name: String,
+    pub partition_id: GUID,
+    pub reasons: Vec<String>,
+}
+
+impl TryFrom<&FABRIC_UNPLACED_REPLICA_INFORMATION> for UnplacedReplicaInformation {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_UNPLACED_REPLICA_INFORMATION) -> Result<Self, Self::Error> {
+        let service_name = unsafe { wide_to_string(value.ServiceName)? };
+        let reasons = unsafe { string_list(value.UnplacedReplicaReasons)? };
+
+        Ok(Self {
+            service_name,
+            partition_id: value.PartitionId,
+            reasons,
+        })
+    }
+}
+
+/// Converts a possibly null `FABRIC_STRING_LIST` into owned strings.
+pub(crate) unsafe fn string_list(list: *const FABRIC_STRING_LIST) -> Result<Vec<String>, Error> {
+    match list.as_ref() {
+        Some(list) => list_items(list.Items, list.Count)
+            .iter()
+            .map(|item| wide_to_string(item.0))
+            .collect(),
+        None => Ok(vec![]),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ServiceType {
+    pub description: ServiceTypeDescription,
+    pub service_manifest_version: String,
+    pub service_manifest_name: Option<String>,
+    pub is_service_group: Option<bool>,
+}
+
+impl TryFrom<&FABRIC_SERVICE_TYPE_QUERY_RESULT_ITEM> for ServiceType {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_SERVICE_TYPE_QUERY_RESULT_ITEM) -> Result<Self, Self::Error> {
+        let description =
+            ServiceTypeDescription::try_from(unsafe { &*value.ServiceTypeDescription })?;
+        let service_manifest_version = unsafe { wide_to_string(value.ServiceManifestVersion.0)? };
+        let ex1 = unsafe {
+            (value.Reserved as *const FABRIC_SERVICE_TYPE_QUERY_RESULT_ITEM_EX1).as_ref()
+        };
+        let service_manifest_name = ex1
+            .map(|ex1| unsafe { wide_to_string(ex1.ServiceManifestName.0) })
+            .transpose()?;
+        let ex2 = ex1.and_then(|ex1| unsafe {
+            (ex1.Reserved as *const FABRIC_SERVICE_TYPE_QUERY_RESULT_ITEM_EX2).as_ref()
+        });
+        let is_service_group = ex2.map(|ex2| ex2.IsServiceGroup.as_bool());
+
+        Ok(Self {
+            description,
+            service_manifest_version,
+            service_manifest_name,
+            is_service_group,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ServiceTypeDescription {
+    Stateful(StatefulServiceTypeDescription),
+    Stateless(StatelessServiceTypeDescription),
+}
+
+impl ServiceTypeDescription {
+    pub fn kind(&self) -> ServiceKind {
+        match self {
+            Self::Stateful(_) => ServiceKind::Stateful,
+            Self::Stateless(_) => ServiceKind::Stateless,
+        }
+    }
+
+    pub fn service_type_name(&self) -> &str {
+        match self {
+            Self::Stateful(desc) => &desc.service_type_name,
+            Self::Stateless(desc) => &desc.service_type_name,
+        }
+    }
+}
+
+impl TryFrom<&FABRIC_SERVICE_TYPE_DESCRIPTION> for ServiceTypeDescription {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_SERVICE_TYPE_DESCRIPTION) -> Result<Self, Self::Error> {
+        match ServiceKind::from(value.Kind) {
+            ServiceKind::Stateful => {
+                let desc = StatefulServiceTypeDescription::try_from(unsafe {
+                    &*(value.Value as *const FABRIC_STATEFUL_SERVICE_TYPE_DESCRIPTION)
+                })?;
+                Ok(Self::Stateful(desc))
+            }
+            ServiceKind::Stateless => {
+                let desc = StatelessServiceTypeDescription::try_from(unsafe {
+                    &*(value.Value as *const FABRIC_STATELESS_SERVICE_TYPE_DESCRIPTION)
+                })?;
+                Ok(Self::Stateless(desc))
+            }
+            _ => Err(Error::InvalidServiceKind),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StatefulServiceTypeDescription {
+    pub service_type_name: String,
+    pub placement_constraints: String,
+    pub load_metrics: Vec<ServiceLoadMetricDescription>,
+    pub has_persisted_state: bool,
+}
+
+impl TryFrom<&FABRIC_STATEFUL_SERVICE_TYPE_DESCRIPTION> for StatefulServiceTypeDescription {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_STATEFUL_SERVICE_TYPE_DESCRIPTION) -> Result<Self, Self::Error> {
+        Ok(Self {
+            service_type_name: unsafe { wide_to_string(value.ServiceTypeName.0)? },
+            placement_constraints: unsafe { wide_to_string(value.PlacementConstraints.0)? },
+            load_metrics: unsafe { service_load_metrics(value.LoadMetrics)? },
+            has_persisted_state: value.HasPersistedState.as_bool(),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StatelessServiceTypeDescription {
+    pub service_type_name: String,
+    pub placement_constraints: String,
+    pub load_metrics: Vec<ServiceLoadMetricDescription>,
+    pub use_implicit_host: bool,
+}
+
+impl TryFrom<&FABRIC_STATELESS_SERVICE_TYPE_DESCRIPTION> for StatelessServiceTypeDescription {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_STATELESS_SERVICE_TYPE_DESCRIPTION) -> Result<Self, Self::Error> {
+        Ok(Self {
+            service_type_name: unsafe { wide_to_string(value.ServiceTypeName.0)? },
+            placement_constraints: unsafe { wide_to_string(value.PlacementConstraints.0)? },
+            load_metrics: unsafe { service_load_metrics(value.LoadMetrics)? },
+            use_implicit_host: value.UseImplicitHost.as_bool(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceLoadMetricDescription {
+    pub name: String,
+    pub weight: ServiceLoadMetricWeight,
+    pub primary_default_load: u32,
+    pub secondary_default_load: u32,
+}
+
+impl TryFrom<&FABRIC_SERVICE_LOAD_METRIC_DESCRIPTION> for ServiceLoadMetricDescription {
+    type Error = Error;
+
+    fn try_from(value: &FABRIC_SERVICE_LOAD_METRIC_DESCRIPTION) -> Result<Self, Self::Error> {
+        let name = unsafe { wide_to_string(value.Name.0)? };
+
+        Ok(Self {
+            name,
+            weight: value.Weight.try_into()?,
+            primary_default_load: value.PrimaryDefaultLoad,
+            secondary_default_load: value.SecondaryDefaultLoad,
+        })
+    }
+}
+
+pub(crate) unsafe fn service_load_metrics(
+    list: *const FABRIC_SERVICE_LOAD_METRIC_DESCRIPTION_LIST,
+) -> Result<Vec<ServiceLoadMetricDescription>, Error> {
+    match list.as_ref() {
+        Some(list) => list_items(list.Items, list.Count)
+            .iter()
+            .map(ServiceLoadMetricDescription::try_from)
+            .collect(),
+        None => Ok(vec![]),
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[repr(i32)]
+pub enum ServiceLoadMetricWeight {
+    Zero = FABRIC_SERVICE_LOAD_METRIC_WEIGHT_ZERO.0,
+    Low = FABRIC_SERVICE_LOAD_METRIC_WEIGHT_LOW.0,
+    Medium = FABRIC_SERVICE_LOAD_METRIC_WEIGHT_MEDIUM.0,
+    High = FABRIC_SERVICE_LOAD_METRIC_WEIGHT_HIGH.0,
+}
+
+impl TryFrom<FABRIC_SERVICE_LOAD_METRIC_WEIGHT> for ServiceLoadMetricWeight {
+    type Error = Error;
+
+    fn try_from(value: FABRIC_SERVICE_LOAD_METRIC_WEIGHT) -> Result<Self, Self::Error> {
+        match value {
+            FABRIC_SERVICE_LOAD_METRIC_WEIGHT_ZERO => Ok(Self::Zero),
+            FABRIC_SERVICE_LOAD_METRIC_WEIGHT_LOW => Ok(Self::Low),
+            FABRIC_SERVICE_LOAD_METRIC_WEIGHT_MEDIUM => Ok(Self::Medium),
+            FABRIC_SERVICE_LOAD_METRIC_WEIGHT_HIGH => Ok(Self::High),
+            _ => Err(Error::InvalidServiceLoadMetricWeight),
+        }
+    }
+}
+
+impl From<ServiceLoadMetricWeight> for FABRIC_SERVICE_LOAD_METRIC_WEIGHT {
+    fn from(value: ServiceLoadMetricWeight) -> Self {
+        match value {
+            ServiceLoadMetricWeight::Zero => FABRIC_SERVICE_LOAD_METRIC_WEIGHT_ZERO,
+            ServiceLoadMetricWeight::Low => FABRIC_SERVICE_LOAD_METRIC_WEIGHT_LOW,
+            ServiceLoadMetricWeight::Medium => FABRIC_SERVICE_LOAD_METRIC_WEIGHT_MEDIUM,
+            ServiceLoadMetricWeight::High => FABRIC_SERVICE_LOAD_METRIC_WEIGHT_HIGH,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DeployedServiceType {
+    pub service_type_name: String,
+    pub code_package_name: String,
+    pub service_manifest_name: String,
+    pub status: ServiceTypeRegistrationStatus,
+    pub service_package_activation_id: Option<String>,
+}
+
+impl DeployedServiceType {
+    pub fn is_registered(&self) -> bool {
+        self.status == ServiceTypeRegistrationStatus::Registered
+    }
+}
+
+impl TryFrom<&FABRIC_DEPLOYED_SERVICE_TYPE_QUERY_RESULT_ITEM> for DeployedServiceType {
+    type Error = Error;
+
+    fn try_from(
+        value: &FABRIC_DEPLOYED_SERVICE_TYPE_QUERY_RESULT_ITEM,
+    ) -> Result<Self, Self::Error> {
+        let service_type_name = unsafe { wide_to_string(value.ServiceTypeName.0)? };
+        let code_package_name = unsafe { wide_to_string(value.CodePackageName.0)? };
+        let service_manifest_name = unsafe { wide_to_string(value.ServiceManifestName.0)? };
+        let status = ServiceTypeRegistrationStatus::from(value.Status);
+        let service_package_activation_id = unsafe {
+            (value.Reserved as *const FABRIC_DEPLOYED_SERVICE_TYPE_QUERY_RESULT_ITEM_EX1).as_ref()
+        }
+        .map(|ex1| unsafe { wide_to_string(ex1.ServicePackageActivationId.0) })
+        .transpose()?;
+
+        Ok(Self {
+            service_type_name,
+            code_package_name,
+            service_manifest_name,
+            status,
+            service_package_activation_id,
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[repr(i32)]
+pub enum ServiceTypeRegistrationStatus {
+    Invalid = FABRIC_SERVICE_TYPE_REGISTRATION_STATUS_INVALID.0,
+    Disabled = FABRIC_SERVICE_TYPE_REGISTRATION_STATUS_DISABLED.0,
+    NotRegistered = FABRIC_SERVICE_TYPE_REGISTRATION_STATUS_NOT_REGISTERED.0,
+    Registered = FABRIC_SERVICE_TYPE_REGISTRATION_STATUS_REGISTERED.0,
+}
+
+impl From<FABRIC_SERVICE_TYPE_REGISTRATION_STATUS> for ServiceTypeRegistrationStatus {
+    fn from(status: FABRIC_SERVICE_TYPE_REGISTRATION_STATUS) -> Self {
+        match status {
+            FABRIC_SERVICE_TYPE_REGISTRATION_STATUS_INVALID => Self::Invalid,
+            FABRIC_SERVICE_TYPE_REGISTRATION_STATUS_DISABLED => Self::Disabled,
+            FABRIC_SERVICE_TYPE_REGISTRATION_STATUS_NOT_REGISTERED => Self::NotRegistered,
+            FABRIC_SERVICE_TYPE_REGISTRATION_STATUS_REGISTERED => Self::Registered,
+            _ => Self::Invalid,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+
+    use super::*;
+    use crate::{
+        arena::Arena, FABRIC_DEPLOYED_APPLICATION_QUERY_RESULT_LIST, FABRIC_NODE_QUERY_RESULT_ITEM,
+        FABRIC_NODE_QUERY_RESULT_ITEM_EX1, FABRIC_NODE_QUERY_RESULT_ITEM_EX2,
+        FABRIC_NODE_QUERY_RESULT_ITEM_EX3, FABRIC_QUERY_NODE_STATUS_UP,
+        FABRIC_QUERY_SERVICE_REPLICA_STATUS_READY, FABRIC_REPLICA_ROLE_PRIMARY,
+        FABRIC_SERVICE_KIND_INVALID, FABRIC_SERVICE_KIND_STATEFUL, FABRIC_SERVICE_KIND_STATELESS,
+    };
+
+    fn filetime(time: SystemTime) -> FILETIME {
+        let intervals = time.duration_since(UNIX_EPOCH).unwrap().as_nanos() as u64 / 100
+            + 116_444_736_000_000_000;
+        FILETIME {
+            dwLowDateTime: intervals as u32,
+            dwHighDateTime: (intervals >> 32) as u32,
+        }
+    }
+
+    #[test]
+    fn node_follows_the_extension_chain() {
+        let mut arena = Arena::new();
+        let deactivation = arena.alloc(FABRIC_NODE_DEACTIVATION_QUERY_RESULT_ITEM {
+            EffectiveIntent: FABRIC_NODE_DEACTIVATION_INTENT_RESTART,
+            Status: FABRIC_NODE_DEACTIVATION_STATUS_SAFETY_CHECK_IN_PROGRESS,
+            ..Default::default()
+        });
+        let ex3 = arena.alloc(FABRIC_NODE_QUERY_RESULT_ITEM_EX3 {
+            NodeDeactivationInfo: deactivation,
+            ..Default::default()
+        });
+        let ex2 = arena.alloc(FABRIC_NODE_QUERY_RESULT_ITEM_EX2 {
+            Reserved: ex3 as *mut _,
+            ..Default::default()
+        });
+        let ex1 = arena.alloc(FABRIC_NODE_QUERY_RESULT_ITEM_EX1 {
+            Reserved: ex2 as *mut _,
+            ..Default::default()
+        });
+        let raw = FABRIC_NODE_QUERY_RESULT_ITEM {
+            NodeName: arena.wide("_Node_0"),
+            IpAddressOrFQDN: arena.wide("10.0.0.4"),
+            NodeType: arena.wide("Front"),
+            CodeVersion: arena.wide("10.1.0"),
+            ConfigVersion: arena.wide("1.0"),
+            NodeStatus: FABRIC_QUERY_NODE_STATUS_UP,
+            NodeUpTimeInSeconds: 3600,
+            AggregatedHealthState: FABRIC_HEALTH_STATE_WARNING,
+            IsSeedNode: true.into(),
+            UpgradeDomain: arena.wide("UD0"),
+            FaultDomain: arena.uri("fd:/0"),
+            Reserved: ex1 as *mut _,
+        };
+
+        let node = Node::try_from(&raw).unwrap();
+        assert_eq!(node.name, "_Node_0");
+        assert_eq!(node.ip_address_or_fqdn, "10.0.0.4");
+        assert_eq!(node.node_type, "Front");
+        assert_eq!(node.code_version, "10.1.0");
+        assert_eq!(node.config_version, "1.0");
+        assert_eq!(node.status, NodeStatus::Up);
+        assert_eq!(node.up_time_in_seconds, 3600);
+        assert_eq!(node.health_state, HealthState::Warning);
+        assert!(node.is_seed_node);
+        assert_eq!(node.upgrade_domain, "UD0");
+        assert_eq!(node.fault_domain, "fd:/0");
+        assert_eq!(
+            node.deactivation_info,
+            Some(NodeDeactivationInfo {
+                effective_intent: Some(NodeDeactivationIntent::Restart),
+                status: NodeDeactivationStatus::SafetyCheckInProgress,
+            })
+        );
+    }
+
+    #[test]
+    fn node_without_extensions_or_strings() {
+        let node = Node::try_from(&FABRIC_NODE_QUERY_RESULT_ITEM::default()).unwrap();
+        assert_eq!(node.name, "");
+        assert_eq!(node.fault_domain, "");
+        assert_eq!(node.status, NodeStatus::Invalid);
+        assert!(!node.is_seed_node);
+        assert_eq!(node.deactivation_info, None);
+    }
+
+    #[test]
+    fn deployed_application_and_service_package() {
+        let mut arena = Arena::new();
+        let raw = FABRIC_DEPLOYED_APPLICATION_QUERY_RESULT_ITEM {
+            ApplicationName: arena.uri("fabric:/app"),
+            ApplicationTypeName: arena.wide("AppType"),
+            DeployedApplicationStatus: FABRIC_DEPLOYMENT_STATUS_ACTIVE,
+            Reserved: ptr::null_mut(),
+        };
+        let application = DeployedApplication::try_from(&raw).unwrap();
+        assert_eq!(application.application_name, "fabric:/app");
+        assert_eq!(application.application_type_name, "AppType");
+        assert_eq!(application.status, DeploymentStatus::Active);
+
+        let raw = FABRIC_DEPLOYED_SERVICE_PACKAGE_QUERY_RESULT_ITEM {
+            ServiceManifestName: arena.wide("WebPkg"),
+            ServiceManifestVersion: PCWSTR::null(),
+            DeployedServicePackageStatus: FABRIC_DEPLOYMENT_STATUS_DOWNLOADING,
+            Reserved: ptr::null_mut(),
+        };
+        let package = DeployedServicePackage::try_from(&raw).unwrap();
+        assert_eq!(package.service_manifest_name, "WebPkg");
+        assert_eq!(package.service_manifest_version, "");
+        assert_eq!(package.status, DeploymentStatus::Downloading);
+    }
+
+    #[test]
+    fn empty_result_lists() {
+        let list = FABRIC_DEPLOYED_APPLICATION_QUERY_RESULT_LIST {
+            Count: 0,
+            Items: ptr::null(),
+        };
+        let applications = unsafe { list_items(list.Items, list.Count) }
+            .iter()
+            .map(DeployedApplication::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert!(applications.is_empty());
+    }
+
+    #[test]
+    fn deployed_code_package_with_entry_points() {
+        let mut arena = Arena::new();
+        let activated = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let statistics = arena.alloc(FABRIC_CODE_PACKAGE_ENTRY_POINT_STATISTICS {
+            LastExitCode: 1,
+            LastActivationUtc: filetime(activated),
+            ActivationCount: 3,
+            ContinuousExitFailureCount: 2,
+            ..Default::default()
+        });
+        let entry_point = FABRIC_CODE_PACKAGE_ENTRY_POINT {
+            EntryPointLocation: arena.wide("web.exe"),
+            ProcessId: 4242,
+            RunAsUserName: PCWSTR::null(),
+            EntryPointStatus: FABRIC_ENTRY_POINT_STATUS_STARTED,
+            Statistics: statistics,
+            ..Default::default()
+        };
+        let entry_point = arena.alloc(entry_point);
+        let raw = FABRIC_DEPLOYED_CODE_PACKAGE_QUERY_RESULT_ITEM {
+            CodePackageName: arena.wide("Code"),
+            CodePackageVersion: arena.wide("1.0"),
+            ServiceManifestName: arena.wide("WebPkg"),
+            RunFrequencyInterval: 0,
+            DeployedCodePackageStatus: FABRIC_DEPLOYMENT_STATUS_ACTIVE,
+            SetupEntryPoint: ptr::null(),
+            EntryPoint: entry_point,
+            Reserved: ptr::null_mut(),
+        };
+
+        let package = DeployedCodePackage::try_from(&raw).unwrap();
+        assert_eq!(package.code_package_name, "Code");
+        assert_eq!(package.code_package_version, "1.0");
+        assert_eq!(package.service_manifest_name, "WebPkg");
+        assert_eq!(package.status, DeploymentStatus::Active);
+        assert!(package.setup_entry_point.is_none());
+
+        let entry_point = package.entry_point.unwrap();
+        assert_eq!(entry_point.location, "web.exe");
+        assert_eq!(entry_point.process_id, 4242);
+        assert_eq!(entry_point.run_as_user_name, "");
+        assert_eq!(entry_point.status, EntryPointStatus::Started);
+        assert_eq!(entry_point.next_activation, None);
+
+        let statistics = entry_point.statistics.unwrap();
+        assert_eq!(statistics.last_exit_code, 1);
+        assert_eq!(statistics.last_activation, Some(activated));
+        assert_eq!(statistics.last_exit, None);
+        assert_eq!(statistics.activation_count, 3);
+        assert_eq!(statistics.continuous_exit_failure_count, 2);
+    }
+
+    #[test]
+    fn deployed_replicas_by_kind() {
+        let mut arena = Arena::new();
+        let partition_id = GUID::from_u128(0x1234);
+
+        let stateful = FABRIC_DEPLOYED_STATEFUL_SERVICE_REPLICA_QUERY_RESULT_ITEM {
+            ServiceName: arena.uri("fabric:/app/store"),
+            ServiceTypeName: arena.wide("StoreType"),
+            ServiceManifestVersion: arena.wide("1.0"),
+            CodePackageName: arena.wide("Code"),
+            PartitionId: partition_id,
+            ReplicaId: 7,
+            ReplicaRole: FABRIC_REPLICA_ROLE_PRIMARY,
+            ReplicaStatus: FABRIC_QUERY_SERVICE_REPLICA_STATUS_READY,
+            Address: PCWSTR::null(),
+            Reserved: ptr::null_mut(),
+        };
+        let raw = FABRIC_DEPLOYED_SERVICE_REPLICA_QUERY_RESULT_ITEM {
+            Kind: FABRIC_SERVICE_KIND_STATEFUL,
+            Value: arena.alloc(stateful) as *mut _,
+        };
+        let DeployedReplica::Stateful(replica) = DeployedReplica::try_from(&raw).unwrap() else {
+            panic!("expected a stateful replica");
+        };
+        assert_eq!(replica.service_name, "fabric:/app/store");
+        assert_eq!(replica.service_type_name, "StoreType");
+        assert_eq!(replica.partition_id, partition_id);
+        assert_eq!(replica.replica_id, 7);
+        assert_eq!(replica.role, ReplicaRole::Primary);
+        assert_eq!(replica.status, ReplicaStatus::Ready);
+        assert_eq!(replica.address, "");
+
+        let stateless = FABRIC_DEPLOYED_STATELESS_SERVICE_INSTANCE_QUERY_RESULT_ITEM {
+            ServiceName: arena.uri("fabric:/app/web"),
+            PartitionId: partition_id,
+            InstanceId: 9,
+            ReplicaStatus: FABRIC_QUERY_SERVICE_REPLICA_STATUS_READY,
+            Address: arena.wide("http://10.0.0.4:80"),
+            ..Default::default()
+        };
+        let raw = FABRIC_DEPLOYED_SERVICE_REPLICA_QUERY_RESULT_ITEM {
+            Kind: FABRIC_SERVICE_KIND_STATELESS,
+            Value: arena.alloc(stateless) as *mut _,
+        };
+        let replica = DeployedReplica::try_from(&raw).unwrap();
+        assert_eq!(replica.partition_id(), partition_id);
+        let DeployedReplica::Stateless(instance) = replica else {
+            panic!("expected a stateless instance");
+        };
+        assert_eq!(instance.instance_id, 9);
+        assert_eq!(instance.service_type_name, "");
+        assert_eq!(instance.address, "http://10.0.0.4:80");
+
+        let raw = FABRIC_DEPLOYED_SERVICE_REPLICA_QUERY_RESULT_ITEM {
+            Kind: FABRIC_SERVICE_KIND_INVALID,
+            Value: ptr::null_mut(),
+        };
+        assert!(matches!(
+            DeployedReplica::try_from(&raw),
+            Err(Error::InvalidServiceKind)
+        ));
+    }
+
+    #[test]
+    fn service_types_by_kind() {
+        let mut arena = Arena::new();
+        let metric = FABRIC_SERVICE_LOAD_METRIC_DESCRIPTION {
+            Name: arena.wide("Memory"),
+            Weight: FABRIC_SERVICE_LOAD_METRIC_WEIGHT_HIGH,
+            PrimaryDefaultLoad: 20,
+            SecondaryDefaultLoad: 10,
+            Reserved: ptr::null_mut(),
+        };
+        let metrics = arena.slice(vec![metric]);
+        let metrics = arena.alloc(FABRIC_SERVICE_LOAD_METRIC_DESCRIPTION_LIST {
+            Count: 1,
+            Items: metrics,
+        });
+        let stateful = FABRIC_STATEFUL_SERVICE_TYPE_DESCRIPTION {
+            ServiceTypeName: arena.wide("StoreType"),
+            PlacementConstraints: arena.wide("NodeType == Back"),
+            LoadMetrics: metrics,
+            HasPersistedState: true.into(),
+            ..Default::default()
+        };
+        let description = FABRIC_SERVICE_TYPE_DESCRIPTION {
+            Kind: FABRIC_SERVICE_KIND_STATEFUL,
+            Value: arena.alloc(stateful) as *mut _,
+        };
+        let ex2 = arena.alloc(FABRIC_SERVICE_TYPE_QUERY_RESULT_ITEM_EX2 {
+            IsServiceGroup: false.into(),
+            Reserved: ptr::null_mut(),
+        });
+        let ex1 = FABRIC_SERVICE_TYPE_QUERY_RESULT_ITEM_EX1 {
+            ServiceManifestName: arena.wide("StorePkg"),
+            Reserved: ex2 as *mut _,
+        };
+        let raw = FABRIC_SERVICE_TYPE_QUERY_RESULT_ITEM {
+            ServiceTypeDescription: arena.alloc(description),
+            ServiceManifestVersion: arena.wide("1.0"),
+            Reserved: arena.alloc(ex1) as *mut _,
+        };
+
+        let service_type = ServiceType::try_from(&raw).unwrap();
+        assert_eq!(service_type.service_manifest_version, "1.0");
+        assert_eq!(
+            service_type.service_manifest_name.as_deref(),
+            Some("StorePkg")
+        );
+        assert_eq!(service_type.is_service_group, Some(false));
+        assert_eq!(service_type.description.kind(), ServiceKind::Stateful);
+        assert_eq!(service_type.description.service_type_name(), "StoreType");
+        let ServiceTypeDescription::Stateful(desc) = service_type.description else {
+            panic!("expected a stateful service type");
+        };
+        assert_eq!(desc.placement_constraints, "NodeType == Back");
+        assert!(desc.has_persisted_state);
+        assert_eq!(
+            desc.load_metrics,
+            vec![ServiceLoadMetricDescription {
+                name: "Memory".to_string(),
+                weight: ServiceLoadMetricWeight::High,
+                primary_default_load: 20,
+                secondary_default_load: 10,
+            }]
+        );
+
+        let stateless = FABRIC_STATELESS_SERVICE_TYPE_DESCRIPTION {
+            ServiceTypeName: arena.wide("WebType"),
+            UseImplicitHost: true.into(),
+            ..Default::default()
+        };
+        let description = FABRIC_SERVICE_TYPE_DESCRIPTION {
+            Kind: FABRIC_SERVICE_KIND_STATELESS,
+            Value: arena.alloc(stateless) as *mut _,
+        };
+        let raw = FABRIC_SERVICE_TYPE_QUERY_RESULT_ITEM {
+            ServiceTypeDescription: arena.alloc(description),
+            ServiceManifestVersion: PCWSTR::null(),
+            Reserved: ptr::null_mut(),
+        };
+
+        let service_type = ServiceType::try_from(&raw).unwrap();
+        assert_eq!(service_type.service_manifest_version, "");
+        assert_eq!(service_type.service_manifest_name, None);
+        assert_eq!(service_type.is_service_group, None);
+        let ServiceTypeDescription::Stateless(desc) = service_type.description else {
+            panic!("expected a stateless service type");
+        };
+        assert_eq!(desc.service_type_name, "WebType");
+        assert_eq!(desc.placement_constraints, "");
+        assert!(desc.load_metrics.is_empty());
+        assert!(desc.use_implicit_host);
+
+        let description = FABRIC_SERVICE_TYPE_DESCRIPTION {
+            Kind: FABRIC_SERVICE_KIND_INVALID,
+            Value: ptr::null_mut(),
+        };
+        assert!(matches!(
+            ServiceTypeDescription::try_from(&description),
+            Err(Error::InvalidServiceKind)
+        ));
+    }
+
+    #[test]
+    fn unknown_load_metric_weight() {
+        let raw = FABRIC_SERVICE_LOAD_METRIC_DESCRIPTION {
+            Weight: FABRIC_SERVICE_LOAD_METRIC_WEIGHT(42),
+            ..Default::default()
+        };
+        assert!(matches!(
+            ServiceLoadMetricDescription::try_from(&raw),
+            Err(Error::InvalidServiceLoadMetricWeight)
+        ));
+
+        for weight in [
+            ServiceLoadMetricWeight::Zero,
+            ServiceLoadMetricWeight::Low,
+            ServiceLoadMetricWeight::Medium,
+            ServiceLoadMetricWeight::High,
+        ] {
+            let raw = FABRIC_SERVICE_LOAD_METRIC_WEIGHT::from(weight);
+            assert_eq!(ServiceLoadMetricWeight::try_from(raw).unwrap(), weight);
+        }
+    }
+}
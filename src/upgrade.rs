@@ -0,0 +1,547 @@
+use std::{ffi::c_void, future::Future, ptr, time::Duration};
+
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::{
+    arena::Arena, error::Error, in_tokio_runtime, list_items, seconds, wide_to_string,
+    FABRIC_APPLICATION_UPGRADE_STATE, FABRIC_MONITORED_UPGRADE_FAILURE_ACTION,
+    FABRIC_MONITORED_UPGRADE_FAILURE_ACTION_MANUAL,
+    FABRIC_MONITORED_UPGRADE_FAILURE_ACTION_ROLLBACK, FABRIC_ROLLING_UPGRADE_MODE,
+    FABRIC_ROLLING_UPGRADE_MODE_MONITORED, FABRIC_ROLLING_UPGRADE_MODE_UNMONITORED_AUTO,
+    FABRIC_ROLLING_UPGRADE_MODE_UNMONITORED_MANUAL, FABRIC_ROLLING_UPGRADE_MONITORING_POLICY,
+    FABRIC_ROLLING_UPGRADE_MONITORING_POLICY_EX1, FABRIC_ROLLING_UPGRADE_POLICY_DESCRIPTION,
+    FABRIC_ROLLING_UPGRADE_POLICY_DESCRIPTION_EX1, FABRIC_UPGRADE_DOMAIN_STATE,
+    FABRIC_UPGRADE_DOMAIN_STATE_COMPLETED, FABRIC_UPGRADE_DOMAIN_STATE_IN_PROGRESS,
+    FABRIC_UPGRADE_DOMAIN_STATE_PENDING, FABRIC_UPGRADE_DOMAIN_STATUS_DESCRIPTION_LIST,
+    FABRIC_UPGRADE_STATE, FABRIC_UPGRADE_STATE_FAILED, FABRIC_UPGRADE_STATE_INVALID,
+    FABRIC_UPGRADE_STATE_ROLLING_BACK_COMPLETED, FABRIC_UPGRADE_STATE_ROLLING_BACK_IN_PROGRESS,
+    FABRIC_UPGRADE_STATE_ROLLING_BACK_PENDING, FABRIC_UPGRADE_STATE_ROLLING_FORWARD_COMPLETED,
+    FABRIC_UPGRADE_STATE_ROLLING_FORWARD_IN_PROGRESS, FABRIC_UPGRADE_STATE_ROLLING_FORWARD_PENDING,
+};
+
+/// How an upgrade moves from one upgrade domain to the next.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(i32)]
+pub enum UpgradeMode {
+    /// SF moves on automatically once each domain passes its health checks.
+    Monitored = FABRIC_ROLLING_UPGRADE_MODE_MONITORED.0,
+    /// SF moves on automatically without checking health.
+    UnmonitoredAuto = FABRIC_ROLLING_UPGRADE_MODE_UNMONITORED_AUTO.0,
+    /// SF waits for an explicit move to the next upgrade domain.
+    UnmonitoredManual = FABRIC_ROLLING_UPGRADE_MODE_UNMONITORED_MANUAL.0,
+}
+
+impl From<FABRIC_ROLLING_UPGRADE_MODE> for UpgradeMode {
+    fn from(value: FABRIC_ROLLING_UPGRADE_MODE) -> Self {
+        match value {
+            FABRIC_ROLLING_UPGRADE_MODE_MONITORED => Self::Monitored,
+            FABRIC_ROLLING_UPGRADE_MODE_UNMONITORED_AUTO => Self::UnmonitoredAuto,
+            _ => Self::UnmonitoredManual,
+        }
+    }
+}
+
+impl From<UpgradeMode> for FABRIC_ROLLING_UPGRADE_MODE {
+    fn from(value: UpgradeMode) -> Self {
+        FABRIC_ROLLING_UPGRADE_MODE(value as i32)
+    }
+}
+
+/// What SF does when a monitored upgrade fails its health checks or times
+/// out.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(i32)]
+pub enum FailureAction {
+    Rollback = FABRIC_MONITORED_UPGRADE_FAILURE_ACTION_ROLLBACK.0,
+    /// Switch to `UnmonitoredManual` mode and wait for an operator.
+    Manual = FABRIC_MONITORED_UPGRADE_FAILURE_ACTION_MANUAL.0,
+}
+
+impl From<FABRIC_MONITORED_UPGRADE_FAILURE_ACTION> for FailureAction {
+    fn from(value: FABRIC_MONITORED_UPGRADE_FAILURE_ACTION) -> Self {
+        match value {
+            FABRIC_MONITORED_UPGRADE_FAILURE_ACTION_ROLLBACK => Self::Rollback,
+            _ => Self::Manual,
+        }
+    }
+}
+
+impl From<FailureAction> for FABRIC_MONITORED_UPGRADE_FAILURE_ACTION {
+    fn from(value: FailureAction) -> Self {
+        FABRIC_MONITORED_UPGRADE_FAILURE_ACTION(value as i32)
+    }
+}
+
+/// Health check timing for a monitored upgrade. `None` timeouts never expire.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MonitoringPolicy {
+    pub failure_action: FailureAction,
+    /// How long to wait after finishing a domain before checking health.
+    pub health_check_wait: Duration,
+    /// How long health must stay good before moving to the next domain.
+    pub health_check_stable: Duration,
+    /// How long to keep retrying failed health checks before failing.
+    pub health_check_retry_timeout: Duration,
+    pub upgrade_timeout: Option<Duration>,
+    pub upgrade_domain_timeout: Option<Duration>,
+}
+
+impl MonitoringPolicy {
+    /// Creates a policy with SF's default health check timing.
+    pub fn new(failure_action: FailureAction) -> Self {
+        Self {
+            failure_action,
+            health_check_wait: Duration::ZERO,
+            health_check_stable: Duration::from_secs(120),
+            health_check_retry_timeout: Duration::from_secs(600),
+            upgrade_timeout: None,
+            upgrade_domain_timeout: None,
+        }
+    }
+
+    pub fn health_check_wait(mut self, health_check_wait: Duration) -> Self {
+        self.health_check_wait = health_check_wait;
+        self
+    }
+
+    pub fn health_check_stable(mut self, health_check_stable: Duration) -> Self {
+        self.health_check_stable = health_check_stable;
+        self
+    }
+
+    pub fn health_check_retry_timeout(mut self, health_check_retry_timeout: Duration) -> Self {
+        self.health_check_retry_timeout = health_check_retry_timeout;
+        self
+    }
+
+    pub fn upgrade_timeout(mut self, upgrade_timeout: Duration) -> Self {
+        self.upgrade_timeout = Some(upgrade_timeout);
+        self
+    }
+
+    pub fn upgrade_domain_timeout(mut self, upgrade_domain_timeout: Duration) -> Self {
+        self.upgrade_domain_timeout = Some(upgrade_domain_timeout);
+        self
+    }
+
+    fn to_raw(&self, arena: &mut Arena) -> FABRIC_ROLLING_UPGRADE_MONITORING_POLICY {
+        let ex1 = arena.alloc(FABRIC_ROLLING_UPGRADE_MONITORING_POLICY_EX1 {
+            HealthCheckStableDurationInSeconds: seconds(Some(self.health_check_stable)),
+            Reserved: ptr::null_mut(),
+        });
+
+        FABRIC_ROLLING_UPGRADE_MONITORING_POLICY {
+            FailureAction: self.failure_action.into(),
+            HealthCheckWaitDurationInSeconds: seconds(Some(self.health_check_wait)),
+            HealthCheckRetryTimeoutInSeconds: seconds(Some(self.health_check_retry_timeout)),
+            UpgradeTimeoutInSeconds: seconds(self.upgrade_timeout),
+            UpgradeDomainTimeoutInSeconds: seconds(self.upgrade_domain_timeout),
+            Reserved: ex1 as *mut c_void,
+        }
+    }
+}
+
+/// The rolling upgrade policy shared by application and cluster upgrades.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RollingUpgradePolicy {
+    pub mode: UpgradeMode,
+    /// Restart code packages even when only their config or data changed.
+    pub force_restart: bool,
+    /// How long to wait for a replica set to become safe to upgrade before
+    /// going ahead anyway. `None` waits indefinitely.
+    pub replica_set_check_timeout: Option<Duration>,
+    /// Only used in `Monitored` mode.
+    pub monitoring_policy: MonitoringPolicy,
+}
+
+impl RollingUpgradePolicy {
+    pub fn monitored(monitoring_policy: MonitoringPolicy) -> Self {
+        Self {
+            mode: UpgradeMode::Monitored,
+            force_restart: false,
+            replica_set_check_timeout: None,
+            monitoring_policy,
+        }
+    }
+
+    pub fn unmonitored_auto() -> Self {
+        Self {
+            mode: UpgradeMode::UnmonitoredAuto,
+            ..Self::monitored(MonitoringPolicy::new(FailureAction::Manual))
+        }
+    }
+
+    pub fn unmonitored_manual() -> Self {
+        Self {
+            mode: UpgradeMode::UnmonitoredManual,
+            ..Self::monitored(MonitoringPolicy::new(FailureAction::Manual))
+        }
+    }
+
+    pub fn force_restart(mut self, force_restart: bool) -> Self {
+        self.force_restart = force_restart;
+        self
+    }
+
+    pub fn replica_set_check_timeout(mut self, replica_set_check_timeout: Duration) -> Self {
+        self.replica_set_check_timeout = Some(replica_set_check_timeout);
+        self
+    }
+
+    /// Builds the policy description with `health_policy` as the
+    /// application or cluster health policy it is evaluated against.
+    /// `extension` is chained after the first extension struct, for the
+    /// fields only cluster upgrades use.
+    pub(crate) fn to_raw(
+        &self,
+        arena: &mut Arena,
+        health_policy: *mut c_void,
+        extension: *mut c_void,
+    ) -> *mut c_void {
+        let monitoring_policy = self.monitoring_policy.to_raw(arena);
+        let monitoring_policy = arena.alloc(monitoring_policy);
+        let ex1 = arena.alloc(FABRIC_ROLLING_UPGRADE_POLICY_DESCRIPTION_EX1 {
+            MonitoringPolicy: monitoring_policy,
+            HealthPolicy: health_policy,
+            Reserved: extension,
+        });
+
+        arena.alloc(FABRIC_ROLLING_UPGRADE_POLICY_DESCRIPTION {
+            RollingUpgradeMode: self.mode.into(),
+            ForceRestart: self.force_restart.into(),
+            UpgradeReplicaSetCheckTimeoutInSeconds: seconds(self.replica_set_check_timeout),
+            Reserved: ex1 as *mut c_void,
+        }) as *mut c_void
+    }
+}
+
+/// The state of an application or cluster upgrade.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(i32)]
+pub enum UpgradeState {
+    Invalid = FABRIC_UPGRADE_STATE_INVALID.0,
+    RollingBackInProgress = FABRIC_UPGRADE_STATE_ROLLING_BACK_IN_PROGRESS.0,
+    RollingBackCompleted = FABRIC_UPGRADE_STATE_ROLLING_BACK_COMPLETED.0,
+    RollingForwardPending = FABRIC_UPGRADE_STATE_ROLLING_FORWARD_PENDING.0,
+    RollingForwardInProgress = FABRIC_UPGRADE_STATE_ROLLING_FORWARD_IN_PROGRESS.0,
+    RollingForwardCompleted = FABRIC_UPGRADE_STATE_ROLLING_FORWARD_COMPLETED.0,
+    Failed = FABRIC_UPGRADE_STATE_FAILED.0,
+    RollingBackPending = FABRIC_UPGRADE_STATE_ROLLING_BACK_PENDING.0,
+}
+
+impl UpgradeState {
+    /// Whether the upgrade has stopped, either because it completed, rolled
+    /// back or failed. `Invalid`, which also covers states this crate does not
+    /// know about yet, is not treated as finished.
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            Self::RollingForwardCompleted | Self::RollingBackCompleted | Self::Failed
+        )
+    }
+}
+
+impl From<FABRIC_UPGRADE_STATE> for UpgradeState {
+    fn from(value: FABRIC_UPGRADE_STATE) -> Self {
+        match value {
+            FABRIC_UPGRADE_STATE_ROLLING_BACK_IN_PROGRESS => Self::RollingBackInProgress,
+            FABRIC_UPGRADE_STATE_ROLLING_BACK_COMPLETED => Self::RollingBackCompleted,
+            FABRIC_UPGRADE_STATE_ROLLING_FORWARD_PENDING => Self::RollingForwardPending,
+            FABRIC_UPGRADE_STATE_ROLLING_FORWARD_IN_PROGRESS => Self::RollingForwardInProgress,
+            FABRIC_UPGRADE_STATE_ROLLING_FORWARD_COMPLETED => Self::RollingForwardCompleted,
+            FABRIC_UPGRADE_STATE_FAILED => Self::Failed,
+            FABRIC_UPGRADE_STATE_ROLLING_BACK_PENDING => Self::RollingBackPending,
+            _ => Self::Invalid,
+        }
+    }
+}
+
+/// Application upgrades report their state with a separate enum that uses
+/// the same values.
+impl From<FABRIC_APPLICATION_UPGRADE_STATE> for UpgradeState {
+    fn from(value: FABRIC_APPLICATION_UPGRADE_STATE) -> Self {
+        FABRIC_UPGRADE_STATE(value.0).into()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(i32)]
+pub enum UpgradeDomainState {
+    Pending = FABRIC_UPGRADE_DOMAIN_STATE_PENDING.0,
+    InProgress = FABRIC_UPGRADE_DOMAIN_STATE_IN_PROGRESS.0,
+    Completed = FABRIC_UPGRADE_DOMAIN_STATE_COMPLETED.0,
+}
+
+impl From<FABRIC_UPGRADE_DOMAIN_STATE> for UpgradeDomainState {
+    fn from(value: FABRIC_UPGRADE_DOMAIN_STATE) -> Self {
+        match value {
+            FABRIC_UPGRADE_DOMAIN_STATE_IN_PROGRESS => Self::InProgress,
+            FABRIC_UPGRADE_DOMAIN_STATE_COMPLETED => Self::Completed,
+            _ => Self::Pending,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpgradeDomainStatus {
+    pub name: String,
+    pub state: UpgradeDomainState,
+}
+
+pub(crate) unsafe fn upgrade_domain_list(
+    list: *const FABRIC_UPGRADE_DOMAIN_STATUS_DESCRIPTION_LIST,
+) -> Result<Vec<UpgradeDomainStatus>, Error> {
+    let Some(list) = list.as_ref() else {
+        return Ok(vec![]);
+    };
+
+    list_items(list.Items, list.Count)
+        .iter()
+        .map(|domain| {
+            Ok(UpgradeDomainStatus {
+                name: wide_to_string(domain.Name.0)?,
+                state: domain.State.into(),
+            })
+        })
+        .collect()
+}
+
+/// An upgrade domain whose state changed between two progress polls.
+/// `previous` is `None` the first time a domain is seen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpgradeDomainTransition {
+    pub upgrade_domain: String,
+    pub previous: Option<UpgradeDomainState>,
+    pub current: UpgradeDomainState,
+}
+
+/// An item from an upgrade watch stream. `Finished` is always the last item
+/// and carries the state the upgrade ended in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UpgradeEvent {
+    UpgradeDomain(UpgradeDomainTransition),
+    Finished(UpgradeState),
+}
+
+fn upgrade_domain_transitions(
+    previous: &[UpgradeDomainStatus],
+    current: &[UpgradeDomainStatus],
+) -> Vec<UpgradeDomainTransition> {
+    current
+        .iter()
+        .filter_map(|domain| {
+            let previous = previous
+                .iter()
+                .find(|previous| previous.name == domain.name)
+                .map(|previous| previous.state);
+            (previous != Some(domain.state)).then(|| UpgradeDomainTransition {
+                upgrade_domain: domain.name.clone(),
+                previous,
+                current: domain.state,
+            })
+        })
+        .collect()
+}
+
+/// Polls upgrade progress every `interval` and streams upgrade domain
+/// transitions until the upgrade finishes. `poll` returns the upgrade state
+/// and its upgrade domains. Polling stops once the stream is dropped. Outside
+/// a tokio runtime the stream yields a single `Error::NoTokioRuntime`.
+pub(crate) fn poll_upgrade<F, Fut>(
+    interval: Duration,
+    mut poll: F,
+) -> ReceiverStream<Result<UpgradeEvent, Error>>
+where
+    F: FnMut() -> Fut + Send + 'static,
+    Fut: Future<Output = Result<(UpgradeState, Vec<UpgradeDomainStatus>), Error>> + Send,
+{
+    let (tx, rx) = mpsc::channel(16);
+    if !in_tokio_runtime() {
+        let _ = tx.try_send(Err(Error::NoTokioRuntime("poll_upgrade")));
+        return ReceiverStream::new(rx);
+    }
+
+    tokio::spawn(async move {
+        let mut upgrade_domains = vec![];
+        loop {
+            let (state, current) = match poll().await {
+                Ok(progress) => progress,
+                Err(e) => {
+                    let _ = tx.send(Err(e)).await;
+                    return;
+                }
+            };
+
+            for transition in upgrade_domain_transitions(&upgrade_domains, &current) {
+                if tx
+                    .send(Ok(UpgradeEvent::UpgradeDomain(transition)))
+                    .await
+                    .is_err()
+                {
+                    return;
+                }
+            }
+
+            if state.is_finished() {
+                let _ = tx.send(Ok(UpgradeEvent::Finished(state))).await;
+                return;
+            }
+
+            upgrade_domains = current;
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = tx.closed() => return,
+            }
+        }
+    });
+
+    ReceiverStream::new(rx)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::VecDeque,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+    };
+
+    use tokio_stream::StreamExt;
+
+    use super::*;
+
+    type Progress = Result<(UpgradeState, Vec<UpgradeDomainStatus>), Error>;
+
+    fn domains(states: &[(&str, UpgradeDomainState)]) -> Vec<UpgradeDomainStatus> {
+        states
+            .iter()
+            .map(|(name, state)| UpgradeDomainStatus {
+                name: name.to_string(),
+                state: *state,
+            })
+            .collect()
+    }
+
+    fn fake_poll(
+        polls: Vec<Progress>,
+    ) -> impl FnMut() -> std::future::Ready<Progress> + Send + 'static {
+        let polls = Arc::new(Mutex::new(VecDeque::from(polls)));
+        move || {
+            let next = polls.lock().unwrap().pop_front();
+            std::future::ready(next.unwrap_or(Err(Error::Abandoned("GetUpgradeProgress"))))
+        }
+    }
+
+    fn transition(
+        upgrade_domain: &str,
+        previous: Option<UpgradeDomainState>,
+        current: UpgradeDomainState,
+    ) -> UpgradeEvent {
+        UpgradeEvent::UpgradeDomain(UpgradeDomainTransition {
+            upgrade_domain: upgrade_domain.to_string(),
+            previous,
+            current,
+        })
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn poll_upgrade_emits_changed_domains_then_finished() {
+        use UpgradeDomainState::*;
+
+        let polls = vec![
+            Ok((
+                UpgradeState::RollingForwardInProgress,
+                domains(&[("UD0", InProgress), ("UD1", Pending)]),
+            )),
+            Ok((
+                UpgradeState::RollingForwardInProgress,
+                domains(&[("UD0", InProgress), ("UD1", Pending)]),
+            )),
+            Ok((
+                UpgradeState::RollingForwardInProgress,
+                domains(&[("UD0", Completed), ("UD1", Pending)]),
+            )),
+            Ok((
+                UpgradeState::RollingForwardCompleted,
+                domains(&[("UD0", Completed), ("UD1", Completed)]),
+            )),
+        ];
+        let stream = poll_upgrade(Duration::from_secs(10), fake_poll(polls));
+        let events: Vec<_> = stream.map(Result::unwrap).collect().await;
+
+        assert_eq!(
+            events,
+            vec![
+                transition("UD0", None, InProgress),
+                transition("UD1", None, Pending),
+                transition("UD0", Some(InProgress), Completed),
+                transition("UD1", Some(Pending), Completed),
+                UpgradeEvent::Finished(UpgradeState::RollingForwardCompleted),
+            ]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn poll_upgrade_ends_after_an_error() {
+        let polls = vec![Ok((
+            UpgradeState::RollingForwardInProgress,
+            domains(&[("UD0", UpgradeDomainState::InProgress)]),
+        ))];
+        let mut stream = poll_upgrade(Duration::from_secs(10), fake_poll(polls));
+
+        assert!(stream.next().await.unwrap().is_ok());
+        assert!(matches!(
+            stream.next().await.unwrap(),
+            Err(Error::Abandoned("GetUpgradeProgress"))
+        ));
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn poll_upgrade_stops_polling_once_dropped() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let polls = count.clone();
+        let stream = poll_upgrade(Duration::from_secs(10), move || {
+            polls.fetch_add(1, Ordering::SeqCst);
+            std::future::ready(Ok((UpgradeState::RollingForwardPending, vec![])))
+        });
+
+        tokio::time::sleep(Duration::from_secs(25)).await;
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+
+        drop(stream);
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn poll_upgrade_keeps_polling_through_unknown_states() {
+        use UpgradeDomainState::*;
+
+        let polls = vec![
+            Ok((UpgradeState::Invalid, domains(&[("UD0", InProgress)]))),
+            Ok((
+                UpgradeState::from(FABRIC_UPGRADE_STATE(42)),
+                domains(&[("UD0", Completed)]),
+            )),
+            Ok((
+                UpgradeState::RollingForwardCompleted,
+                domains(&[("UD0", Completed)]),
+            )),
+        ];
+        let stream = poll_upgrade(Duration::from_secs(10), fake_poll(polls));
+        let events: Vec<_> = stream.map(Result::unwrap).collect().await;
+
+        assert_eq!(
+            events,
+            vec![
+                transition("UD0", None, InProgress),
+                transition("UD0", Some(InProgress), Completed),
+                UpgradeEvent::Finished(UpgradeState::RollingForwardCompleted),
+            ]
+        );
+    }
+}
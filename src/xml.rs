@@ -0,0 +1,284 @@
+//! A small XML reader, just enough for the documents SF hands back to us
+//! (cluster manifests and the like). It builds an element tree, drops
+//! namespace prefixes, text, comments and processing instructions, and
+//! expands the predefined and numeric character references in attribute
+//! values. DTDs are skipped rather than interpreted.
+
+use crate::error::Error;
+
+/// How deeply elements may nest. SF's manifests stay well under this; the
+/// limit only guards the recursive parser against hostile input.
+const MAX_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Element>,
+}
+
+impl Element {
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    pub fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+}
+
+/// Parses `input` and returns its root element. Errors are reported through
+/// `make_error` so callers can say which kind of document was malformed.
+pub(crate) fn parse(input: &str, make_error: fn(String) -> Error) -> Result<Element, Error> {
+    let mut parser = Parser {
+        input,
+        pos: 0,
+        depth: 0,
+        make_error,
+    };
+
+    parser.skip_misc()?;
+    let root = parser.element()?;
+    parser.skip_misc()?;
+    if parser.pos != input.len() {
+        return Err(parser.error("unexpected content after the root element"));
+    }
+
+    Ok(root)
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+    depth: usize,
+    make_error: fn(String) -> Error,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn error(&self, message: &str) -> Error {
+        (self.make_error)(format!("{} at byte {}", message, self.pos))
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn skip_past(&mut self, terminator: &str) -> Result<(), Error> {
+        match self.rest().find(terminator) {
+            Some(index) => {
+                self.pos += index + terminator.len();
+                Ok(())
+            }
+            None => Err(self.error(&format!("missing '{}'", terminator))),
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), Error> {
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", token)))
+        }
+    }
+
+    /// Skips the prolog, comments, processing instructions and whitespace
+    /// around the root element.
+    fn skip_misc(&mut self) -> Result<(), Error> {
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if rest.starts_with("<!DOCTYPE") {
+                self.skip_past(">")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Reads a name and strips any namespace prefix from it.
+    fn name(&mut self) -> Result<String, Error> {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| c.is_whitespace() || matches!(c, '/' | '>' | '=' | '<'))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error("expected a name"));
+        }
+
+        let name = &rest[..len];
+        self.pos += len;
+        Ok(name.rsplit(':').next().unwrap_or(name).to_string())
+    }
+
+    fn attribute_value(&mut self) -> Result<String, Error> {
+        let quote = match self.rest().chars().next() {
+            Some(quote @ ('"' | '\'')) => quote,
+            _ => return Err(self.error("expected a quoted attribute value")),
+        };
+        self.pos += 1;
+
+        let rest = self.rest();
+        let len = rest
+            .find(quote)
+            .ok_or_else(|| self.error("unterminated attribute value"))?;
+        let value = unescape(&rest[..len]).ok_or_else(|| self.error("invalid entity reference"))?;
+        self.pos += len + 1;
+
+        Ok(value)
+    }
+
+    fn element(&mut self) -> Result<Element, Error> {
+        self.expect("<")?;
+        let name = self.name()?;
+        let mut element = Element {
+            name,
+            attributes: vec![],
+            children: vec![],
+        };
+
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("/>") {
+                self.pos += 2;
+                return Ok(element);
+            }
+            if self.rest().starts_with('>') {
+                self.pos += 1;
+                break;
+            }
+
+            let key = self.name()?;
+            self.skip_whitespace();
+            self.expect("=")?;
+            self.skip_whitespace();
+            let value = self.attribute_value()?;
+            element.attributes.push((key, value));
+        }
+
+        loop {
+            let rest = self.rest();
+            if rest.starts_with("</") {
+                self.pos += 2;
+                let name = self.name()?;
+                if name != element.name {
+                    return Err(self.error(&format!(
+                        "closing tag '{}' doesn't match '{}'",
+                        name, element.name
+                    )));
+                }
+                self.skip_whitespace();
+                self.expect(">")?;
+                return Ok(element);
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if rest.starts_with("<![CDATA[") {
+                self.skip_past("]]>")?;
+            } else if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with('<') {
+                if self.depth == MAX_DEPTH {
+                    return Err(self.error("nesting too deep"));
+                }
+                self.depth += 1;
+                let child = self.element()?;
+                self.depth -= 1;
+                element.children.push(child);
+            } else {
+                match rest.find('<') {
+                    Some(index) => self.pos += index,
+                    None => return Err(self.error(&format!("unclosed element '{}'", element.name))),
+                }
+            }
+        }
+    }
+}
+
+fn unescape(value: &str) -> Option<String> {
+    let mut result = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        let end = rest[start..].find(';')? + start;
+        let entity = &rest[start + 1..end];
+        let c = match entity {
+            "amp" => '&',
+            "lt" => '<',
+            "gt" => '>',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => {
+                let code = match entity.strip_prefix("#x") {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                    None => entity.strip_prefix('#')?.parse().ok()?,
+                };
+                char::from_u32(code)?
+            }
+        };
+        result.push(c);
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_manifest(input: &str) -> Result<Element, Error> {
+        parse(input, Error::InvalidClusterManifest)
+    }
+
+    fn nested(depth: usize) -> String {
+        "<a>".repeat(depth) + &"</a>".repeat(depth)
+    }
+
+    #[test]
+    fn nesting_is_limited() {
+        assert!(parse_manifest(&nested(MAX_DEPTH + 1)).is_ok());
+
+        let err = parse_manifest(&nested(MAX_DEPTH + 2)).unwrap_err();
+        assert!(err.to_string().contains("nesting too deep"));
+    }
+
+    #[test]
+    fn entities_are_expanded_in_attribute_values() {
+        let root = parse_manifest(
+            r#"<Parameter Value="a &lt;b&gt; &amp; &quot;c&quot; &apos;d&apos; &#65;&#x42;" />"#,
+        )
+        .unwrap();
+        assert_eq!(root.attribute("Value"), Some(r#"a <b> & "c" 'd' AB"#));
+
+        assert!(parse_manifest(r#"<Parameter Value="&bogus;" />"#).is_err());
+        assert!(parse_manifest(r#"<Parameter Value="&amp" />"#).is_err());
+    }
+
+    #[test]
+    fn cdata_and_comments_are_skipped() {
+        let root = parse_manifest(
+            "<Section><![CDATA[<NotAnElement a='1'>]]><!-- <Hidden/> --><Parameter/></Section>",
+        )
+        .unwrap();
+        assert_eq!(root.children.len(), 1);
+        assert_eq!(root.children[0].name, "Parameter");
+    }
+}